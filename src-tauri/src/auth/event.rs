@@ -1,5 +1,6 @@
+use crate::vrchat_utils::AppError;
 use serde::Serialize;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
 use vrchatapi::models;
 
 #[derive(Serialize, Clone)]
@@ -23,7 +24,14 @@ pub enum AuthEvent {
 }
 
 impl AuthEvent {
-    pub fn emit(&self, app: &AppHandle) {
+    pub fn failure(err: &AppError) -> Self {
+        AuthEvent::Failure {
+            message: err.to_string(),
+            code: Some(err.code().to_string()),
+        }
+    }
+
+    pub fn emit<R: Runtime>(&self, app: &AppHandle<R>) {
         let _ = app.emit("vrc:auth", self);
     }
 }
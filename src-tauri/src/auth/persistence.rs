@@ -1,4 +1,5 @@
 use super::state::AuthSession;
+use crate::config::Endpoints;
 use keyring::Entry;
 use reqwest::cookie::{CookieStore, Jar};
 use reqwest::header::HeaderValue;
@@ -11,12 +12,15 @@ const KEYCHAIN_SERVICE: &str = "vfriends";
 const KEYCHAIN_ACCOUNT: &str = "vrchat_auth_cookies";
 
 impl AuthSession {
-    pub fn new() -> Self {
-        Self::from_cookie_header(load_saved_cookie_header())
+    pub fn new(endpoints: Endpoints) -> Self {
+        let cookie_header = load_saved_cookie_header(&endpoints);
+        Self::from_cookie_header(endpoints, cookie_header, true)
     }
 
-    pub fn new_without_cookies() -> Self {
-        Self::from_cookie_header(None)
+    /// A session that never reads or writes the keychain.
+    #[cfg(feature = "test-support")]
+    pub fn ephemeral(endpoints: Endpoints) -> Self {
+        Self::from_cookie_header(endpoints, None, false)
     }
 
     pub fn cookie_header(&self) -> Option<String> {
@@ -29,26 +33,37 @@ impl AuthSession {
     }
 
     pub fn save_cookies(&self) -> Result<(), keyring::Error> {
+        if !self.uses_keychain {
+            return Ok(());
+        }
         if let Some(header) = self.cookie_header() {
-            let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)?;
+            let entry = Entry::new(KEYCHAIN_SERVICE, &keychain_account(&self.endpoints))?;
             entry.set_password(&header)?;
         }
         Ok(())
     }
 
-    pub fn clear_saved_cookies() -> Result<(), keyring::Error> {
-        let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)?;
+    pub fn clear_saved_cookies(&self) -> Result<(), keyring::Error> {
+        if !self.uses_keychain {
+            return Ok(());
+        }
+        let entry = Entry::new(KEYCHAIN_SERVICE, &keychain_account(&self.endpoints))?;
         entry.delete_credential()
     }
 
     pub fn reset(&mut self) {
-        *self = Self::new_without_cookies();
+        *self = Self::from_cookie_header(self.endpoints.clone(), None, self.uses_keychain);
     }
 
-    fn from_cookie_header(cookie_header: Option<String>) -> Self {
+    fn from_cookie_header(
+        endpoints: Endpoints,
+        cookie_header: Option<String>,
+        uses_keychain: bool,
+    ) -> Self {
         let cookie_jar = Arc::new(Jar::default());
 
         let mut config = Configuration::default();
+        config.base_path = endpoints.api_base_path.clone();
         config.user_agent = Some(String::from("vfriends"));
         config.client = Client::builder()
             .cookie_provider(cookie_jar.clone())
@@ -71,6 +86,8 @@ impl AuthSession {
         Self {
             config,
             cookie_jar,
+            endpoints,
+            uses_keychain,
             is_pending_2fa: false,
         }
     }
@@ -86,13 +103,27 @@ fn cookie_base_url(config: &Configuration) -> Url {
         }
         Err(err) => {
             log::warn!("Failed to parse base path for cookie URL: {err}");
-            Url::parse("https://api.vrchat.cloud").expect("Invalid fallback cookie URL")
+            Url::parse(&Endpoints::default().api_base_path)
+                .map(|mut url| {
+                    url.set_path("/");
+                    url
+                })
+                .expect("Invalid fallback cookie URL")
         }
     }
 }
 
-fn load_saved_cookie_header() -> Option<String> {
-    let entry = match Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT) {
+/// Cookies of a stand-in server are kept apart so they never replace the real session.
+fn keychain_account(endpoints: &Endpoints) -> String {
+    if endpoints.api_base_path == Endpoints::default().api_base_path {
+        return String::from(KEYCHAIN_ACCOUNT);
+    }
+    let host = endpoints.api_host().unwrap_or_default();
+    format!("{KEYCHAIN_ACCOUNT}@{host}")
+}
+
+fn load_saved_cookie_header(endpoints: &Endpoints) -> Option<String> {
+    let entry = match Entry::new(KEYCHAIN_SERVICE, &keychain_account(endpoints)) {
         Ok(entry) => entry,
         Err(err) => {
             log::warn!("Failed to access keychain entry: {err}");
@@ -1,22 +1,44 @@
 use crate::auth::event::{AuthAction, AuthEvent};
-use crate::vrchat_utils::AppResult;
-use crate::websocket;
+use crate::config::Endpoints;
+use crate::roster::FriendRoster;
+use crate::vrchat_utils::{ApiService, AppError, AppResult};
+use crate::{notifier, websocket};
 use keyring::Error as KeyringError;
 use reqwest::cookie::Jar;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, Runtime};
 use vrchatapi::apis::authentication_api::{get_current_user, verify2_fa, verify2_fa_email_code, verify_recovery_code};
 use vrchatapi::apis::configuration::Configuration;
 use vrchatapi::models;
 
+/// Pipeline rejections in a row after which the session is given up on, even when the API still
+/// accepts it.
+const MAX_PIPELINE_REJECTIONS: u32 = 3;
+
 pub struct AuthState {
     pub session: Mutex<AuthSession>,
+    recovering: AtomicBool,
+    /// Pipeline rejections since the last login or stable connection.
+    pipeline_rejections: AtomicU32,
 }
 
 impl AuthState {
-    pub fn new() -> Self {
+    pub fn new(endpoints: Endpoints) -> Self {
+        Self {
+            session: Mutex::new(AuthSession::new(endpoints)),
+            recovering: AtomicBool::new(false),
+            pipeline_rejections: AtomicU32::new(0),
+        }
+    }
+
+    /// Keeps the session in memory only, e.g. for tests against a stand-in server.
+    #[cfg(feature = "test-support")]
+    pub fn ephemeral(endpoints: Endpoints) -> Self {
         Self {
-            session: Mutex::new(AuthSession::new()),
+            session: Mutex::new(AuthSession::ephemeral(endpoints)),
+            recovering: AtomicBool::new(false),
+            pipeline_rejections: AtomicU32::new(0),
         }
     }
 
@@ -24,7 +46,7 @@ impl AuthState {
         let session = self
             .session
             .lock()
-            .map_err(|_| String::from("Auth state lock poisoned."))?;
+            .map_err(|_| AppError::internal("Auth state lock poisoned."))?;
         Ok(f(&session))
     }
 
@@ -32,13 +54,13 @@ impl AuthState {
         let mut session = self
             .session
             .lock()
-            .map_err(|_| String::from("Auth state lock poisoned."))?;
+            .map_err(|_| AppError::internal("Auth state lock poisoned."))?;
         Ok(f(&mut session))
     }
 
-    pub async fn begin_auth_flow(
+    pub async fn begin_auth_flow<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
         username: String,
         password: String,
     ) -> AppResult<()> {
@@ -48,7 +70,7 @@ impl AuthState {
         .emit(&app);
 
         let config = self.set_basic_auth(username, password)?;
-        match get_current_user(&config).await {
+        match fetch_current_user(app, &config).await {
             Ok(models::EitherUserOrTwoFactor::CurrentUser(current_user)) => {
                 self.finish_auth_flow(app, &current_user)?;
             }
@@ -65,45 +87,52 @@ impl AuthState {
             }
             Err(err) => {
                 self.clear_basic_auth();
-                AuthEvent::Failure {
-                    message: err.to_string(),
-                    code: None,
-                }
-                .emit(&app);
+                AuthEvent::failure(&err).emit(&app);
             }
         }
 
         Ok(())
     }
 
-    pub fn finish_auth_flow(
+    pub fn finish_auth_flow<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
         current_user: &models::CurrentUser,
     ) -> AppResult<()> {
-        let (cookie_header, user_agent) = self.with_session_mut(|session| {
+        self.pipeline_rejections.store(0, Ordering::Release);
+        self.start_session(app)?;
+
+        AuthEvent::Success {
+            user: current_user.clone(),
+        }
+        .emit(&app);
+
+        Ok(())
+    }
+
+    /// Saves the session and connects to the pipeline with it.
+    fn start_session<R: Runtime>(&self, app: &AppHandle<R>) -> AppResult<()> {
+        let (cookie_header, user_agent, pipeline_url) = self.with_session_mut(|session| {
             session.is_pending_2fa = false;
             session.config.basic_auth = None;
             if let Err(err) = session.save_cookies() {
                 log::warn!("Failed to save auth cookies to keychain: {err}");
             }
-            (session.cookie_header(), session.config.user_agent.clone())
+            (
+                session.cookie_header(),
+                session.config.user_agent.clone(),
+                session.endpoints.pipeline_url.clone(),
+            )
         })?;
 
         let websocket = app.state::<websocket::WebsocketState>();
-        websocket.start_with_cookie_header(app, cookie_header, user_agent);
-
-        AuthEvent::Success {
-            user: current_user.clone(),
-        }
-        .emit(&app);
-
+        websocket.start_with_cookie_header(app, pipeline_url, cookie_header, user_agent);
         Ok(())
     }
 
-    pub async fn verify_two_factor(
+    pub async fn verify_two_factor<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
         two_factor_code: String,
         two_factor_method: String,
     ) -> AppResult<()> {
@@ -114,11 +143,7 @@ impl AuthState {
             self.with_session(|session| (session.config.clone(), session.is_pending_2fa))?;
 
         if !is_pending_2fa {
-            AuthEvent::Failure {
-                message: String::from("2FA session not found. Please log in again"),
-                code: None,
-            }
-            .emit(&app);
+            AuthEvent::failure(&AppError::SessionExpired).emit(&app);
             return Ok(());
         }
 
@@ -127,23 +152,17 @@ impl AuthState {
         }
         .emit(&app);
 
-        if let Err(err) = dispatch_2fa_verification(&config, code, method).await {
-            AuthEvent::Failure {
-                message: err,
-                code: None,
-            }
-            .emit(&app);
+        let api = app.state::<ApiService>();
+        let verification = api.send_once(|| dispatch_2fa_verification(&config, code, method));
+        if let Err(err) = verification.await {
+            AuthEvent::failure(&err).emit(&app);
             return Ok(());
         }
 
-        let login_result = match get_current_user(&config).await {
+        let login_result = match fetch_current_user(app, &config).await {
             Ok(login_result) => login_result,
             Err(err) => {
-                AuthEvent::Failure {
-                    message: err.to_string(),
-                    code: None,
-                }
-                .emit(&app);
+                AuthEvent::failure(&err).emit(&app);
                 return Ok(());
             }
         };
@@ -167,7 +186,20 @@ impl AuthState {
         Ok(())
     }
 
-    pub async fn restore_session(&self, app: &AppHandle) -> AppResult<Option<models::CurrentUser>> {
+    pub async fn restore_session<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+    ) -> AppResult<Option<models::CurrentUser>> {
+        self.restore(app, true).await
+    }
+
+    /// Restores the saved session. `announce` tells the front end about the login; a session
+    /// restored in the background is not a new one.
+    async fn restore<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        announce: bool,
+    ) -> AppResult<Option<models::CurrentUser>> {
         let (config, has_cookies) = self
             .with_session(|session| (session.config.clone(), session.cookie_header().is_some()))?;
 
@@ -175,23 +207,27 @@ impl AuthState {
             return Ok(None);
         }
 
-        match get_current_user(&config).await {
+        match fetch_current_user(app, &config).await {
             Ok(models::EitherUserOrTwoFactor::CurrentUser(current_user)) => {
-                self.finish_auth_flow(app, &current_user)?;
+                if announce {
+                    self.finish_auth_flow(app, &current_user)?;
+                } else {
+                    self.start_session(app)?;
+                }
                 return Ok(Some(current_user));
             }
             Ok(models::EitherUserOrTwoFactor::RequiresTwoFactorAuth(_)) => {
                 let websocket = app.state::<websocket::WebsocketState>();
-                websocket.stop();
+                websocket.stop(app, Some(String::from("Session expired")));
                 self.reset_session();
-                clear_saved_cookies();
+                self.clear_saved_cookies();
             }
             Err(err) => {
-                if is_auth_error(&err.to_string()) {
+                if err.is_unauthorized() {
                     let websocket = app.state::<websocket::WebsocketState>();
-                    websocket.stop();
+                    websocket.stop(app, Some(String::from("Session expired")));
                     self.reset_session();
-                    clear_saved_cookies();
+                    self.clear_saved_cookies();
                 }
             }
         }
@@ -199,11 +235,66 @@ impl AuthState {
         Ok(None)
     }
 
-    pub fn logout(&self, app: &AppHandle) -> AppResult<()> {
+    /// Called when the pipeline or the API rejects the session. Tries to restore it, and asks the
+    /// user to log in again when that fails. Concurrent calls are ignored.
+    pub async fn recover_session<R: Runtime>(&self, app: &AppHandle<R>) {
+        if self.recovering.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        log::warn!("The session was rejected. Trying to restore it...");
+        let restored = match self.restore(app, false).await {
+            Ok(user) => user.is_some(),
+            Err(err) => {
+                log::warn!("Failed to restore the session: {err}");
+                false
+            }
+        };
+
+        if !restored {
+            self.expire_session(app);
+        }
+
+        self.recovering.store(false, Ordering::Release);
+    }
+
+    /// Called when the pipeline rejects the session. The API may still accept it, so restoring
+    /// is retried with a growing delay, and the session is given up on after
+    /// `MAX_PIPELINE_REJECTIONS` rejections in a row. `was_stable` starts the count over.
+    pub async fn recover_rejected_session<R: Runtime>(&self, app: &AppHandle<R>, was_stable: bool) {
+        if was_stable {
+            self.pipeline_rejections.store(0, Ordering::Release);
+        }
+        let rejections = self.pipeline_rejections.fetch_add(1, Ordering::AcqRel) + 1;
+        if rejections > MAX_PIPELINE_REJECTIONS {
+            log::warn!("The pipeline keeps rejecting the session. Giving up on it.");
+            self.expire_session(app);
+            return;
+        }
+        if rejections > 1 {
+            tokio::time::sleep(websocket::backoff_delay(rejections - 2)).await;
+        }
+        self.recover_session(app).await;
+    }
+
+    fn expire_session<R: Runtime>(&self, app: &AppHandle<R>) {
+        let websocket = app.state::<websocket::WebsocketState>();
+        websocket.stop(app, Some(String::from("Session expired")));
+        app.state::<FriendRoster>().clear();
+        app.state::<ApiService>().clear_cache();
+        self.reset_session();
+        self.clear_saved_cookies();
+        AuthEvent::failure(&AppError::SessionExpired).emit(app);
+        notifier::notify_session_expired(app);
+    }
+
+    pub fn logout<R: Runtime>(&self, app: &AppHandle<R>) -> AppResult<()> {
         let websocket = app.state::<websocket::WebsocketState>();
-        websocket.stop();
+        websocket.stop(app, None);
+        app.state::<FriendRoster>().clear();
+        app.state::<ApiService>().clear_cache();
         self.reset_session();
-        clear_saved_cookies();
+        self.clear_saved_cookies();
         AuthEvent::LoggedOut.emit(app);
         Ok(())
     }
@@ -224,18 +315,38 @@ impl AuthState {
     }
 
     fn reset_session(&self) {
+        self.pipeline_rejections.store(0, Ordering::Release);
         let _ = self.with_session_mut(|session| {
             session.reset();
         });
     }
+
+    fn clear_saved_cookies(&self) {
+        match self.with_session(|session| session.clear_saved_cookies()) {
+            Ok(Ok(())) | Ok(Err(KeyringError::NoEntry)) | Err(_) => {}
+            Ok(Err(err)) => {
+                log::warn!("Failed to clear auth cookies from keychain: {err}");
+            }
+        }
+    }
 }
 
 pub struct AuthSession {
     pub config: Configuration,
     pub cookie_jar: Arc<Jar>,
+    pub endpoints: Endpoints,
+    pub uses_keychain: bool,
     pub is_pending_2fa: bool,
 }
 
+async fn fetch_current_user<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &Configuration,
+) -> AppResult<models::EitherUserOrTwoFactor> {
+    let api = app.state::<ApiService>();
+    api.send(|| async move { Ok(get_current_user(config).await?) }).await
+}
+
 async fn dispatch_2fa_verification(
     config: &Configuration,
     code: &str,
@@ -247,35 +358,22 @@ async fn dispatch_2fa_verification(
             verify2_fa(&config, auth_code)
                 .await
                 .map(|_| ())
-                .map_err(|e| e.to_string())
+                .map_err(AppError::from)
         }
         "emailOtp" => {
             let email_code = models::TwoFactorEmailCode::new(code.to_string());
             verify2_fa_email_code(&config, email_code)
                 .await
                 .map(|_| ())
-                .map_err(|e| e.to_string())
+                .map_err(AppError::from)
         }
         "otp" => {
             let auth_code = models::TwoFactorAuthCode::new(code.to_string());
             verify_recovery_code(&config, auth_code)
                 .await
                 .map(|_| ())
-                .map_err(|e| e.to_string())
-        }
-        _ => Err("Unsupported 2FA method.".to_string()),
-    }
-}
-
-fn clear_saved_cookies() {
-    match AuthSession::clear_saved_cookies() {
-        Ok(()) | Err(KeyringError::NoEntry) => {}
-        Err(err) => {
-            log::warn!("Failed to clear auth cookies from keychain: {err}");
+                .map_err(AppError::from)
         }
+        _ => Err(AppError::invalid_input("Unsupported 2FA method.")),
     }
 }
-
-fn is_auth_error(message: &str) -> bool {
-    message.contains("HTTP 401") || message.contains("HTTP 403")
-}
@@ -1,33 +1,77 @@
 use crate::auth::AuthState;
-use crate::config::{AppSettings, FriendSettings, SettingsStore};
-use crate::vrchat_utils::AppResult;
-use crate::{auth, notifier, vrchat_utils};
-use serde::Deserialize;
+use crate::config::{
+    AppSettings, ArchivedFriendSettings, FlapSuppression, FlapSuppressionOverride, FriendSettings,
+    FriendToggle, MissedOnlineMode, PlatformFilter, ProfileSubscription, QuietHours,
+    SettingsStore,
+};
+use crate::history::{
+    export_history, ExportFormat, HistoryFilter, HistoryPage, HistoryQuery, HistoryStore,
+};
+use crate::presence::{
+    co_presence_graph, presence_stats, top_companions, CoPresenceGraph, Companion, PresenceStats,
+    PresenceStore, DEFAULT_HEATMAP_WEEKS,
+};
+use crate::roster::{FriendRoster, ResyncReason};
+use crate::vrchat_utils::{ApiService, AppError, AppResult, Location};
+use crate::websocket::{ConnectionState, PipelineRecorder, ReplaySpeed, WebsocketState};
+use crate::{auth, notifier, roster, vrchat_utils, websocket};
+use chrono::Local;
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager, State};
-use vrchatapi::apis::worlds_api;
 use vrchatapi::models;
 use vrchatapi::models::{CurrentUser, LimitedUserFriend};
 
 const DEFAULT_PREVIEW_DURATION_MS: u64 = 1_500;
+/// How far back co-presence looks unless asked otherwise.
+const DEFAULT_CO_PRESENCE_DAYS: i64 = 30;
+const DEFAULT_COMPANION_LIMIT: usize = 5;
 
 #[tauri::command]
-pub async fn fetch_friends(state: State<'_, AuthState>) -> AppResult<Vec<LimitedUserFriend>> {
-    vrchat_utils::fetch_all_friends(state.inner()).await
+pub async fn fetch_friends(
+    app: AppHandle,
+    roster: State<'_, FriendRoster>,
+) -> AppResult<Vec<LimitedUserFriend>> {
+    if roster.is_seeded() {
+        return Ok(roster.friends());
+    }
+    roster::resync(&app, ResyncReason::Seed).await
 }
 
 #[tauri::command]
 pub async fn fetch_world(
+    api: State<'_, ApiService>,
     state: State<'_, AuthState>,
     world_id: String,
 ) -> AppResult<models::World> {
-    let config = state.with_session(|session| session.config.clone())?;
-    match worlds_api::get_world(&config, &world_id).await {
-        Ok(world) => Ok(world),
-        Err(err) => Err(err.to_string()),
-    }
+    vrchat_utils::fetch_world(&api, &state, &world_id).await
+}
+
+#[tauri::command]
+pub fn parse_location(location: String) -> AppResult<Location> {
+    Ok(Location::parse(&location)?)
+}
+
+#[tauri::command]
+pub fn fetch_connection_state(state: State<'_, WebsocketState>) -> AppResult<ConnectionState> {
+    Ok(state.connection_state())
+}
+
+#[tauri::command]
+pub fn fetch_pipeline_recording_dir(state: State<'_, PipelineRecorder>) -> AppResult<String> {
+    Ok(state.directory().to_string_lossy().to_string())
+}
+
+/// `speed` is a multiple of real time. Without it, the recording is replayed at once.
+#[tauri::command]
+pub async fn replay_pipeline_recording(
+    app: AppHandle,
+    path: String,
+    speed: Option<f64>,
+) -> AppResult<usize> {
+    websocket::replay(&app, &PathBuf::from(path), ReplaySpeed::from_factor(speed)).await
 }
 
 #[tauri::command]
@@ -75,6 +119,14 @@ pub fn fetch_friend_settings(
     Ok(app_settings.friend_settings)
 }
 
+#[tauri::command]
+pub fn fetch_archived_friend_settings(
+    state: State<'_, SettingsStore>,
+) -> AppResult<HashMap<String, ArchivedFriendSettings>> {
+    let app_settings = state.snapshot();
+    Ok(app_settings.archived_friend_settings)
+}
+
 #[tauri::command]
 pub fn set_friend_settings(
     app: AppHandle,
@@ -82,12 +134,19 @@ pub fn set_friend_settings(
     friend_id: String,
     patch: FriendSettingsPatch,
 ) -> AppResult<()> {
-    if let Some(sound_override) = patch.sound_override.as_ref() {
-        let trimmed = sound_override.trim();
-        if !trimmed.is_empty() {
-            notifier::validate_sound_path(trimmed)?;
-        }
-    }
+    validate_sound_patch(patch.sound_override.as_ref())?;
+    validate_sound_patch(patch.offline_sound_override.as_ref())?;
+    validate_sound_patch(patch.location_sound_override.as_ref())?;
+    validate_template_patch("titleOverride", patch.title_override.as_ref())?;
+    validate_template_patch("messageOverride", patch.message_override.as_ref())?;
+    validate_template_patch(
+        "offlineMessageOverride",
+        patch.offline_message_override.as_ref(),
+    )?;
+    validate_template_patch(
+        "locationMessageOverride",
+        patch.location_message_override.as_ref(),
+    )?;
     state.consume(|settings| {
         let entry = settings
             .friend_settings
@@ -100,13 +159,46 @@ pub fn set_friend_settings(
         if let Some(use_override) = patch.use_override {
             entry.use_override = use_override;
         }
+        if let Some(title_override) = patch.title_override {
+            entry.title_override = normalize_optional(title_override);
+        }
         if let Some(message_override) = patch.message_override {
             entry.message_override = normalize_optional(message_override);
         }
         if let Some(sound_override) = patch.sound_override {
             entry.sound_override = normalize_optional(sound_override);
         }
-    });
+        if let Some(offline_enabled) = patch.offline_enabled {
+            entry.offline_enabled = offline_enabled;
+        }
+        if let Some(offline_message_override) = patch.offline_message_override {
+            entry.offline_message_override = normalize_optional(offline_message_override);
+        }
+        if let Some(offline_sound_override) = patch.offline_sound_override {
+            entry.offline_sound_override = normalize_optional(offline_sound_override);
+        }
+        if let Some(location_enabled) = patch.location_enabled {
+            entry.location_enabled = location_enabled;
+        }
+        if let Some(location_message_override) = patch.location_message_override {
+            entry.location_message_override = normalize_optional(location_message_override);
+        }
+        if let Some(location_sound_override) = patch.location_sound_override {
+            entry.location_sound_override = normalize_optional(location_sound_override);
+        }
+        if let Some(profile_subscriptions) = patch.profile_subscriptions {
+            entry.profile_subscriptions = profile_subscriptions;
+        }
+        if let Some(platform_filter) = patch.platform_filter {
+            entry.platform_filter = platform_filter;
+        }
+        if let Some(flap_suppression) = patch.flap_suppression {
+            entry.flap_suppression = flap_suppression;
+        }
+        if let Some(bypass_quiet_hours) = patch.bypass_quiet_hours {
+            entry.bypass_quiet_hours = bypass_quiet_hours;
+        }
+    })?;
     notifier::cleanup_unused_sounds(&app, &state.snapshot());
     Ok(())
 }
@@ -117,30 +209,212 @@ pub fn fetch_app_settings(state: State<'_, SettingsStore>) -> AppResult<AppSetti
 }
 
 #[tauri::command]
-pub fn set_app_settings(
+pub async fn set_app_settings(
     app: AppHandle,
     state: State<'_, SettingsStore>,
     settings: AppSettingsPatch,
 ) -> AppResult<AppSettings> {
-    if let Some(default_sound) = settings.default_sound.as_ref() {
-        let trimmed = default_sound.trim();
-        if !trimmed.is_empty() {
-            notifier::validate_sound_path(trimmed)?;
-        }
-    }
+    validate_sound_patch(settings.default_sound.as_ref())?;
+    validate_sound_patch(settings.default_offline_sound.as_ref())?;
+    validate_template_patch("defaultTitle", settings.default_title.as_ref())?;
+    validate_template_patch("defaultMessage", settings.default_message.as_ref())?;
+    validate_template_patch(
+        "defaultOfflineMessage",
+        settings.default_offline_message.as_ref(),
+    )?;
+    validate_template_patch(
+        "defaultLocationMessage",
+        settings.default_location_message.as_ref(),
+    )?;
+    validate_template_patch(
+        "defaultFriendAddedMessage",
+        settings.default_friend_added_message.as_ref(),
+    )?;
+    validate_template_patch(
+        "defaultFriendRemovedMessage",
+        settings.default_friend_removed_message.as_ref(),
+    )?;
+    let previous_retention_days = state.snapshot().history_retention_days;
     state.consume(|current| {
+        if let Some(default_title) = settings.default_title {
+            current.default_title = default_title;
+        }
         if let Some(default_message) = settings.default_message {
             current.default_message = default_message;
         }
         if let Some(default_sound) = settings.default_sound {
             current.default_sound = normalize_optional(default_sound);
         }
-    });
+        if let Some(offline_notifications_enabled) = settings.offline_notifications_enabled {
+            current.offline_notifications_enabled = offline_notifications_enabled;
+        }
+        if let Some(default_offline_message) = settings.default_offline_message {
+            current.default_offline_message = default_offline_message;
+        }
+        if let Some(default_offline_sound) = settings.default_offline_sound {
+            current.default_offline_sound = normalize_optional(default_offline_sound);
+        }
+        if let Some(location_notifications_enabled) = settings.location_notifications_enabled {
+            current.location_notifications_enabled = location_notifications_enabled;
+        }
+        if let Some(default_location_message) = settings.default_location_message {
+            current.default_location_message = default_location_message;
+        }
+        if let Some(enabled) = settings.friend_added_notifications_enabled {
+            current.friend_added_notifications_enabled = enabled;
+        }
+        if let Some(default_friend_added_message) = settings.default_friend_added_message {
+            current.default_friend_added_message = default_friend_added_message;
+        }
+        if let Some(enabled) = settings.friend_removed_notifications_enabled {
+            current.friend_removed_notifications_enabled = enabled;
+        }
+        if let Some(default_friend_removed_message) = settings.default_friend_removed_message {
+            current.default_friend_removed_message = default_friend_removed_message;
+        }
+        if let Some(missed_online_notifications) = settings.missed_online_notifications {
+            current.missed_online_notifications = missed_online_notifications;
+        }
+        if let Some(platform_filter) = settings.platform_filter {
+            current.platform_filter = platform_filter;
+        }
+        if let Some(online_batch_window_ms) = settings.online_batch_window_ms {
+            current.online_batch_window_ms = online_batch_window_ms;
+        }
+        if let Some(online_batch_keeps_overrides) = settings.online_batch_keeps_overrides {
+            current.online_batch_keeps_overrides = online_batch_keeps_overrides;
+        }
+        if let Some(flap_suppression) = settings.flap_suppression {
+            current.flap_suppression = flap_suppression;
+        }
+        if let Some(quiet_hours) = settings.quiet_hours {
+            current.quiet_hours = quiet_hours;
+        }
+        if let Some(history_retention_days) = settings.history_retention_days {
+            current.history_retention_days = history_retention_days;
+        }
+        if let Some(record_pipeline) = settings.record_pipeline {
+            current.record_pipeline = record_pipeline;
+        }
+    })?;
     let snapshot = state.snapshot();
+    app.state::<PipelineRecorder>().set_enabled(snapshot.record_pipeline);
+    if snapshot.history_retention_days != previous_retention_days {
+        if let Some(retention) = snapshot.history_retention() {
+            let app = app.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                app.state::<HistoryStore>().purge(retention)
+            })
+            .await??;
+        }
+    }
     notifier::cleanup_unused_sounds(&app, &snapshot);
     Ok(snapshot)
 }
 
+#[tauri::command]
+pub async fn fetch_notification_history(
+    app: AppHandle,
+    query: Option<HistoryQuery>,
+) -> AppResult<HistoryPage> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<HistoryStore>().page(&query.unwrap_or_default())
+    })
+    .await?
+}
+
+/// Writes the records matching `filter` to `path`, oldest first. Returns how many were written.
+#[tauri::command]
+pub async fn export_notification_history(
+    app: AppHandle,
+    filter: Option<HistoryFilter>,
+    format: ExportFormat,
+    path: PathBuf,
+) -> AppResult<usize> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let records = app.state::<HistoryStore>().all(&filter.unwrap_or_default())?;
+        export_history(&records, format, &path)
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn clear_notification_history(app: AppHandle) -> AppResult<()> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<HistoryStore>().clear()).await?
+}
+
+/// When the friend is usually online, over the last `weeks` weeks for the heatmap.
+#[tauri::command]
+pub async fn fetch_presence_stats(
+    app: AppHandle,
+    friend_id: String,
+    weeks: Option<u32>,
+) -> AppResult<PresenceStats> {
+    let weeks = weeks.unwrap_or(DEFAULT_HEATMAP_WEEKS);
+    tauri::async_runtime::spawn_blocking(move || {
+        let presence = app.state::<PresenceStore>();
+        presence_stats(&presence, &friend_id, weeks, Local::now())
+    })
+    .await?
+}
+
+/// The statistics of every friend that has been seen online.
+#[tauri::command]
+pub async fn fetch_all_presence_stats(
+    app: AppHandle,
+    weeks: Option<u32>,
+) -> AppResult<Vec<PresenceStats>> {
+    let weeks = weeks.unwrap_or(DEFAULT_HEATMAP_WEEKS);
+    tauri::async_runtime::spawn_blocking(move || {
+        let presence = app.state::<PresenceStore>();
+        let now = Local::now();
+        presence
+            .friend_ids()?
+            .iter()
+            .map(|friend_id| presence_stats(&presence, friend_id, weeks, now))
+            .collect()
+    })
+    .await?
+}
+
+/// Who played with whom, weighted by the time spent in the same instance. Timestamps are Unix
+/// milliseconds and default to the last 30 days.
+#[tauri::command]
+pub async fn fetch_co_presence_graph(
+    app: AppHandle,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> AppResult<CoPresenceGraph> {
+    let (since, until) = co_presence_range(since, until);
+    tauri::async_runtime::spawn_blocking(move || {
+        co_presence_graph(&app.state::<PresenceStore>(), since, until)
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn fetch_top_companions(
+    app: AppHandle,
+    friend_id: String,
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<usize>,
+) -> AppResult<Vec<Companion>> {
+    let (since, until) = co_presence_range(since, until);
+    let limit = limit.unwrap_or(DEFAULT_COMPANION_LIMIT);
+    tauri::async_runtime::spawn_blocking(move || {
+        let presence = app.state::<PresenceStore>();
+        top_companions(&presence, &friend_id, since, until, limit)
+    })
+    .await?
+}
+
+fn co_presence_range(since: Option<i64>, until: Option<i64>) -> (i64, i64) {
+    let until = until.unwrap_or_else(|| Local::now().timestamp_millis());
+    let since = since.unwrap_or(until - DEFAULT_CO_PRESENCE_DAYS * 24 * 60 * 60 * 1000);
+    (since, until)
+}
+
 #[tauri::command]
 pub async fn preview_notification_sound(
     app: AppHandle,
@@ -169,8 +443,7 @@ pub async fn save_notification_sound(
     let path = tauri::async_runtime::spawn_blocking(move || {
         notifier::store_custom_sound(&handle, &name, &bytes)
     })
-    .await
-    .map_err(|error| error.to_string())??;
+    .await??;
     Ok(path.to_string_lossy().to_string())
 }
 
@@ -180,8 +453,7 @@ pub async fn save_notification_sound_path(app: AppHandle, path: String) -> AppRe
     let stored = tauri::async_runtime::spawn_blocking(move || {
         notifier::store_custom_sound_from_path(&handle, PathBuf::from(path).as_path())
     })
-    .await
-    .map_err(|error| error.to_string())??;
+    .await??;
     Ok(stored.to_string_lossy().to_string())
 }
 
@@ -210,15 +482,65 @@ pub async fn fetch_icon_data_uri(
 pub struct FriendSettingsPatch {
     pub enabled: Option<bool>,
     pub use_override: Option<bool>,
+    pub title_override: Option<String>,
     pub message_override: Option<String>,
     pub sound_override: Option<String>,
+    pub offline_enabled: Option<FriendToggle>,
+    pub offline_message_override: Option<String>,
+    pub offline_sound_override: Option<String>,
+    pub location_enabled: Option<FriendToggle>,
+    pub location_message_override: Option<String>,
+    pub location_sound_override: Option<String>,
+    pub profile_subscriptions: Option<Vec<ProfileSubscription>>,
+    /// `null` goes back to the global filter.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub platform_filter: Option<Option<PlatformFilter>>,
+    pub flap_suppression: Option<FlapSuppressionOverride>,
+    pub bypass_quiet_hours: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettingsPatch {
+    pub default_title: Option<String>,
     pub default_message: Option<String>,
     pub default_sound: Option<String>,
+    pub offline_notifications_enabled: Option<bool>,
+    pub default_offline_message: Option<String>,
+    pub default_offline_sound: Option<String>,
+    pub location_notifications_enabled: Option<bool>,
+    pub default_location_message: Option<String>,
+    pub friend_added_notifications_enabled: Option<bool>,
+    pub default_friend_added_message: Option<String>,
+    pub friend_removed_notifications_enabled: Option<bool>,
+    pub default_friend_removed_message: Option<String>,
+    pub missed_online_notifications: Option<MissedOnlineMode>,
+    pub platform_filter: Option<PlatformFilter>,
+    pub online_batch_window_ms: Option<u64>,
+    pub online_batch_keeps_overrides: Option<bool>,
+    pub flap_suppression: Option<FlapSuppression>,
+    pub quiet_hours: Option<QuietHours>,
+    pub history_retention_days: Option<u32>,
+    pub record_pipeline: Option<bool>,
+}
+
+fn validate_sound_patch(sound: Option<&String>) -> AppResult<()> {
+    if let Some(sound) = sound {
+        let trimmed = sound.trim();
+        if !trimmed.is_empty() {
+            notifier::validate_sound_path(trimmed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rejects a template that does not parse, pointing at the `field` and position of the problem.
+fn validate_template_patch(field: &str, template: Option<&String>) -> AppResult<()> {
+    if let Some(template) = template {
+        notifier::Template::parse(template)
+            .map_err(|err| AppError::invalid_template(field, err.position, err.message))?;
+    }
+    Ok(())
 }
 
 fn normalize_optional(value: String) -> Option<String> {
@@ -229,3 +551,12 @@ fn normalize_optional(value: String) -> Option<String> {
         Some(trimmed.to_string())
     }
 }
+
+/// Tells a field set to `null` apart from a missing one.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,81 @@
+use super::types::EndpointSettings;
+use reqwest::Url;
+
+const API_BASE_PATH_ENV: &str = "VFRIENDS_API_BASE_PATH";
+const PIPELINE_URL_ENV: &str = "VFRIENDS_PIPELINE_URL";
+
+const DEFAULT_API_BASE_PATH: &str = "https://api.vrchat.cloud/api/1";
+const DEFAULT_PIPELINE_URL: &str = "wss://pipeline.vrchat.cloud/";
+
+/// Where the REST API and the pipeline are reached.
+/// Both can point at a stand-in server for testing and demos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    pub api_base_path: String,
+    pub pipeline_url: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            api_base_path: String::from(DEFAULT_API_BASE_PATH),
+            pipeline_url: String::from(DEFAULT_PIPELINE_URL),
+        }
+    }
+}
+
+impl Endpoints {
+    /// Environment variables take precedence over the settings file. Invalid URLs are ignored.
+    pub fn resolve(settings: &EndpointSettings) -> Self {
+        let defaults = Self::default();
+        Self {
+            api_base_path: pick_url(
+                API_BASE_PATH_ENV,
+                settings.api_base_path.as_deref(),
+                &["http", "https"],
+            )
+            .map(|path| path.trim_end_matches('/').to_string())
+            .unwrap_or(defaults.api_base_path),
+            pipeline_url: pick_url(
+                PIPELINE_URL_ENV,
+                settings.pipeline_url.as_deref(),
+                &["ws", "wss"],
+            )
+            .unwrap_or(defaults.pipeline_url),
+        }
+    }
+
+    /// The `host[:port]` of the REST API, used to keep the credentials of different servers apart.
+    pub fn api_host(&self) -> Option<String> {
+        let url = Url::parse(&self.api_base_path).ok()?;
+        let host = url.host_str()?;
+        Some(match url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        })
+    }
+}
+
+fn pick_url(env_key: &str, configured: Option<&str>, schemes: &[&str]) -> Option<String> {
+    let from_env = std::env::var(env_key).ok();
+    [from_env.as_deref(), configured]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .find(|value| match Url::parse(value) {
+            Ok(url) if schemes.contains(&url.scheme()) => true,
+            Ok(url) => {
+                log::warn!(
+                    "Ignoring endpoint '{value}'. Unsupported scheme '{}'.",
+                    url.scheme()
+                );
+                false
+            }
+            Err(err) => {
+                log::warn!("Ignoring endpoint '{value}': {err}");
+                false
+            }
+        })
+        .map(str::to_string)
+}
@@ -1,5 +1,7 @@
+mod endpoints;
 mod persistence;
 mod types;
 
+pub use endpoints::Endpoints;
 pub use persistence::SettingsStore;
 pub use types::*;
@@ -1,4 +1,5 @@
 use super::types::AppSettings;
+use crate::vrchat_utils::{AppError, AppResult};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager};
@@ -6,7 +7,8 @@ use tauri::{AppHandle, Manager};
 const FILE_NAME: &str = "AppSettings.json";
 
 pub struct SettingsStore {
-    path: PathBuf,
+    /// `None` keeps the settings in memory only.
+    path: Option<PathBuf>,
     state: Mutex<AppSettings>,
 }
 
@@ -15,7 +17,16 @@ impl SettingsStore {
         let path = settings_path(app);
         let settings = read_settings(&path);
         Self {
-            path,
+            path: Some(path),
+            state: Mutex::new(settings),
+        }
+    }
+
+    /// Never reads or writes the settings file, e.g. for tests.
+    #[cfg(feature = "test-support")]
+    pub fn ephemeral(settings: AppSettings) -> Self {
+        Self {
+            path: None,
             state: Mutex::new(settings),
         }
     }
@@ -27,7 +38,8 @@ impl SettingsStore {
             .unwrap_or_default()
     }
 
-    pub fn consume(&self, consumer: impl FnOnce(&mut AppSettings)) {
+    /// Applies `consumer` and saves the result. The change is kept in memory even if saving fails.
+    pub fn consume(&self, consumer: impl FnOnce(&mut AppSettings)) -> AppResult<()> {
         let snapshot = {
             let mut guard = self
                 .state
@@ -37,9 +49,15 @@ impl SettingsStore {
             guard.clone()
         };
 
-        if let Err(err) = write_settings(&self.path, &snapshot) {
+        let Some(path) = self.path.as_deref() else {
+            return Ok(());
+        };
+        write_settings(path, &snapshot).map_err(|err| {
             log::warn!("Failed to save app config: {err}");
-        }
+            AppError::Settings {
+                message: format!("Failed to save settings: {err}"),
+            }
+        })
     }
 }
 
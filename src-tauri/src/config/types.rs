@@ -1,20 +1,75 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use vrchatapi::models;
+
+/// Settings of removed friends are dropped once they have been archived for this long.
+const ARCHIVE_RETENTION: Duration = Duration::from_secs(180 * 24 * 60 * 60);
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct AppSettings {
+    /// The title of notifications about a friend. Like the messages, a template.
+    pub default_title: String,
     pub default_message: String,
     pub default_sound: Option<String>,
+    pub offline_notifications_enabled: bool,
+    pub default_offline_message: String,
+    pub default_offline_sound: Option<String>,
+    pub location_notifications_enabled: bool,
+    pub default_location_message: String,
+    pub friend_added_notifications_enabled: bool,
+    pub default_friend_added_message: String,
+    pub friend_removed_notifications_enabled: bool,
+    pub default_friend_removed_message: String,
+    pub missed_online_notifications: MissedOnlineMode,
+    /// Platforms to notify about when friends come online. Website activity counts as "web".
+    pub platform_filter: PlatformFilter,
+    /// Online events arriving within this many milliseconds of each other are merged into one
+    /// notification. `0` notifies about each friend right away.
+    pub online_batch_window_ms: u64,
+    /// Friends with their own message or sound still get an individual notification.
+    pub online_batch_keeps_overrides: bool,
+    pub flap_suppression: FlapSuppression,
+    pub quiet_hours: QuietHours,
     pub friend_settings: HashMap<String, FriendSettings>,
+    pub archived_friend_settings: HashMap<String, ArchivedFriendSettings>,
+    /// Records in the notification history are deleted after this many days. `0` keeps them.
+    pub history_retention_days: u32,
+    pub endpoints: EndpointSettings,
+    /// Appends raw pipeline traffic to JSONL files for later replay.
+    pub record_pipeline: bool,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            default_title: String::from("{displayName}"),
             default_message: String::from("%s is now online!"),
             default_sound: None,
+            offline_notifications_enabled: false,
+            default_offline_message: String::from(
+                "{displayName} is now offline. (online for {onlineFor})",
+            ),
+            default_offline_sound: None,
+            location_notifications_enabled: false,
+            default_location_message: String::from("{displayName} moved to {world}"),
+            friend_added_notifications_enabled: true,
+            default_friend_added_message: String::from("You and %s are now friends!"),
+            friend_removed_notifications_enabled: false,
+            default_friend_removed_message: String::from("%s is no longer your friend."),
+            missed_online_notifications: MissedOnlineMode::Batched,
+            platform_filter: PlatformFilter::default(),
+            online_batch_window_ms: 3_000,
+            online_batch_keeps_overrides: true,
+            flap_suppression: FlapSuppression::default(),
+            quiet_hours: QuietHours::default(),
             friend_settings: HashMap::new(),
+            archived_friend_settings: HashMap::new(),
+            history_retention_days: 30,
+            endpoints: EndpointSettings::default(),
+            record_pipeline: false,
         }
     }
 }
@@ -23,15 +78,98 @@ impl AppSettings {
     pub fn friend_settings_of(&self, friend_id: &str) -> Option<&FriendSettings> {
         self.friend_settings.get(friend_id)
     }
+
+    pub fn platform_filter_of(&self, friend_id: &str) -> PlatformFilter {
+        self.friend_settings_of(friend_id)
+            .and_then(|settings| settings.platform_filter)
+            .unwrap_or(self.platform_filter)
+    }
+
+    /// The global flap suppression with the overrides of the friend applied.
+    pub fn flap_suppression_of(&self, friend_id: &str) -> FlapSuppression {
+        match self.friend_settings_of(friend_id) {
+            Some(settings) => self
+                .flap_suppression
+                .with_override(&settings.flap_suppression),
+            None => self.flap_suppression,
+        }
+    }
+
+    pub fn bypasses_quiet_hours(&self, friend_id: &str) -> bool {
+        self.friend_settings_of(friend_id)
+            .is_some_and(|settings| settings.bypass_quiet_hours)
+    }
+
+    /// Whether notifications about the friend are held back at `at`, in local time.
+    pub fn is_quiet_for(&self, friend_id: &str, at: NaiveDateTime) -> bool {
+        !self.bypasses_quiet_hours(friend_id) && self.quiet_hours.is_quiet_at(at)
+    }
+
+    pub fn history_retention(&self) -> Option<Duration> {
+        (self.history_retention_days > 0)
+            .then(|| Duration::from_secs(u64::from(self.history_retention_days) * 24 * 60 * 60))
+    }
+
+    /// Moves the settings of a removed friend out of `friend_settings`.
+    /// Returns `false` if there was nothing to archive.
+    pub fn archive_friend_settings(&mut self, friend_id: &str, display_name: &str) -> bool {
+        self.purge_expired_archives();
+        let Some(settings) = self.friend_settings.remove(friend_id) else {
+            return false;
+        };
+        self.archived_friend_settings.insert(
+            friend_id.to_string(),
+            ArchivedFriendSettings {
+                display_name: display_name.to_string(),
+                archived_at: unix_seconds(SystemTime::now()),
+                settings,
+            },
+        );
+        true
+    }
+
+    /// Brings back the settings of a friend that has been added again.
+    /// Returns `false` if nothing was archived for them.
+    pub fn restore_friend_settings(&mut self, friend_id: &str) -> bool {
+        let Some(archived) = self.archived_friend_settings.remove(friend_id) else {
+            return false;
+        };
+        self.friend_settings
+            .entry(friend_id.to_string())
+            .or_insert(archived.settings);
+        true
+    }
+
+    fn purge_expired_archives(&mut self) {
+        let Some(cutoff) = SystemTime::now().checked_sub(ARCHIVE_RETENTION) else {
+            return;
+        };
+        let cutoff = unix_seconds(cutoff);
+        self.archived_friend_settings
+            .retain(|_, archived| archived.archived_at >= cutoff);
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct FriendSettings {
     pub enabled: bool,
     pub use_override: bool,
+    pub title_override: Option<String>,
     pub message_override: Option<String>,
     pub sound_override: Option<String>,
+    pub offline_enabled: FriendToggle,
+    pub offline_message_override: Option<String>,
+    pub offline_sound_override: Option<String>,
+    pub location_enabled: FriendToggle,
+    pub location_message_override: Option<String>,
+    pub location_sound_override: Option<String>,
+    pub profile_subscriptions: Vec<ProfileSubscription>,
+    /// Replaces the global platform filter when set.
+    pub platform_filter: Option<PlatformFilter>,
+    pub flap_suppression: FlapSuppressionOverride,
+    /// Notifications about this friend are shown as usual during quiet hours.
+    pub bypass_quiet_hours: bool,
 }
 
 impl Default for FriendSettings {
@@ -39,8 +177,217 @@ impl Default for FriendSettings {
         Self {
             enabled: true,
             use_override: false,
+            title_override: None,
             message_override: None,
             sound_override: None,
+            offline_enabled: FriendToggle::Inherit,
+            offline_message_override: None,
+            offline_sound_override: None,
+            location_enabled: FriendToggle::Inherit,
+            location_message_override: None,
+            location_sound_override: None,
+            profile_subscriptions: Vec::new(),
+            platform_filter: None,
+            flap_suppression: FlapSuppressionOverride::default(),
+            bypass_quiet_hours: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFriendSettings {
+    /// The last known display name, as the friend can no longer be looked up.
+    pub display_name: String,
+    /// Unix timestamp in seconds.
+    pub archived_at: u64,
+    pub settings: FriendSettings,
+}
+
+/// Overrides of the VRChat endpoints. Read once at startup.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EndpointSettings {
+    pub api_base_path: Option<String>,
+    pub pipeline_url: Option<String>,
+}
+
+/// Which platforms coming online is notified for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PlatformFilter {
+    /// `standalonewindows`
+    pub pc: bool,
+    /// Quest and other Android devices.
+    pub android: bool,
+    pub ios: bool,
+    pub web: bool,
+}
+
+impl Default for PlatformFilter {
+    fn default() -> Self {
+        Self {
+            pc: true,
+            android: true,
+            ios: true,
+            web: false,
+        }
+    }
+}
+
+impl PlatformFilter {
+    /// Platforms VRChat may add later are let through.
+    pub fn allows(&self, platform: &str) -> bool {
+        match platform {
+            "standalonewindows" => self.pc,
+            "android" => self.android,
+            "ios" => self.ios,
+            "web" => self.web,
+            _ => true,
         }
     }
 }
+
+/// Keeps friends with unstable connections from producing a notification per reconnect.
+/// All durations are in seconds and `0` turns the respective check off.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FlapSuppression {
+    /// After an online notification, further ones for the same friend are skipped this long.
+    pub online_cooldown_secs: u64,
+    /// Friends have to stay online this long before they are notified about.
+    pub min_online_secs: u64,
+    /// Repeated online events with the same location and platform are dropped within this window.
+    pub dedup_window_secs: u64,
+}
+
+impl Default for FlapSuppression {
+    fn default() -> Self {
+        Self {
+            online_cooldown_secs: 0,
+            min_online_secs: 0,
+            dedup_window_secs: 10,
+        }
+    }
+}
+
+impl FlapSuppression {
+    pub fn with_override(self, overrides: &FlapSuppressionOverride) -> Self {
+        Self {
+            online_cooldown_secs: overrides
+                .online_cooldown_secs
+                .unwrap_or(self.online_cooldown_secs),
+            min_online_secs: overrides.min_online_secs.unwrap_or(self.min_online_secs),
+            dedup_window_secs: overrides
+                .dedup_window_secs
+                .unwrap_or(self.dedup_window_secs),
+        }
+    }
+}
+
+/// Per-friend values for [`FlapSuppression`]. `None` follows the global setting.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FlapSuppressionOverride {
+    pub online_cooldown_secs: Option<u64>,
+    pub min_online_secs: Option<u64>,
+    pub dedup_window_secs: Option<u64>,
+}
+
+/// A weekly schedule in local time during which notifications are held back.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub mode: QuietMode,
+    pub windows: Vec<QuietWindow>,
+}
+
+impl QuietHours {
+    pub fn is_quiet_at(&self, at: NaiveDateTime) -> bool {
+        self.enabled && self.windows.iter().any(|window| window.contains(at))
+    }
+}
+
+/// What happens to notifications during quiet hours.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum QuietMode {
+    /// Shown without any sound.
+    #[default]
+    Silent,
+    Drop,
+    /// Collected and shown as one summary once quiet hours end.
+    Digest,
+}
+
+/// Starts at `start` on each of `days`. Ends at `end` on the same day, or on the next day if
+/// `end` is not after `start`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietWindow {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietWindow {
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let (day, time) = (at.weekday(), at.time());
+        if self.start < self.end {
+            return self.days.contains(&day) && self.start <= time && time < self.end;
+        }
+        (self.days.contains(&day) && time >= self.start)
+            || (self.days.contains(&day.pred()) && time < self.end)
+    }
+}
+
+/// How friends that came online while the pipeline was disconnected are announced.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MissedOnlineMode {
+    /// One notification listing everyone.
+    #[default]
+    Batched,
+    /// The usual online notification for each friend.
+    Individual,
+    Off,
+}
+
+/// A profile change of one friend that should be notified about.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "field", rename_all = "camelCase")]
+pub enum ProfileSubscription {
+    /// Any status switch, or only switches to `to` when it is set.
+    Status { to: Option<models::UserStatus> },
+    StatusDescription,
+    Bio,
+    Avatar,
+}
+
+/// A per-friend switch for a notification that is also configured globally.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FriendToggle {
+    /// Follow the global setting.
+    #[default]
+    Inherit,
+    Enabled,
+    Disabled,
+}
+
+impl FriendToggle {
+    pub fn resolve(self, global: bool) -> bool {
+        match self {
+            FriendToggle::Inherit => global,
+            FriendToggle::Enabled => true,
+            FriendToggle::Disabled => false,
+        }
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,61 @@
+use crate::vrchat_utils::AppResult;
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Prepares a freshly opened connection, e.g. by creating the schema.
+pub type Init = fn(&Connection) -> AppResult<()>;
+
+/// A local SQLite database shared by every thread of the app.
+pub struct Database {
+    connection: Mutex<Connection>,
+}
+
+impl Database {
+    pub fn open(path: &Path, init: Init) -> AppResult<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::with_connection(Connection::open(path)?, init)
+    }
+
+    /// A database that is gone when the app exits.
+    pub fn in_memory(init: Init) -> AppResult<Self> {
+        Self::with_connection(Connection::open_in_memory()?, init)
+    }
+
+    /// Opens `file_name` in the app data directory. Falls back to memory if that fails, so the
+    /// app keeps working. `description` names the database in the log.
+    pub fn for_app<R: Runtime>(
+        app: &AppHandle<R>,
+        file_name: &str,
+        description: &str,
+        init: Init,
+    ) -> Self {
+        let path = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("vfriends"))
+            .join(file_name);
+        Self::open(&path, init)
+            .or_else(|err| {
+                log::warn!("Failed to open the {description}. Keeping it in memory: {err}");
+                Self::in_memory(init)
+            })
+            .unwrap_or_else(|err| panic!("Failed to open an in-memory {description}: {err}"))
+    }
+
+    fn with_connection(connection: Connection, init: Init) -> AppResult<Self> {
+        init(&connection)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
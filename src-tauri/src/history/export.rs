@@ -0,0 +1,68 @@
+use crate::history::types::{ExportFormat, HistoryRecord};
+use crate::vrchat_utils::{AppError, AppResult};
+use chrono::{Local, TimeZone};
+use std::path::Path;
+
+const CSV_HEADER: &str = "recordedAt,friendId,displayName,kind,location,decision,reason";
+
+/// Writes `records` to `path`. Returns how many were written.
+pub fn export_history(
+    records: &[HistoryRecord],
+    format: ExportFormat,
+    path: &Path,
+) -> AppResult<usize> {
+    let contents = match format {
+        ExportFormat::Csv => to_csv(records),
+        ExportFormat::Json => serde_json::to_string_pretty(records)
+            .map_err(|err| AppError::internal(err.to_string()))?,
+    };
+    std::fs::write(path, contents)?;
+    Ok(records.len())
+}
+
+fn to_csv(records: &[HistoryRecord]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push_str("\r\n");
+    for record in records {
+        let fields = [
+            format_timestamp(record.recorded_at),
+            record.friend_id.clone(),
+            record.display_name.clone(),
+            record.kind.as_str().to_string(),
+            record.location.clone().unwrap_or_default(),
+            record.decision.as_str().to_string(),
+            record
+                .reason
+                .map(|reason| reason.as_str().to_string())
+                .unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| escape_csv(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Local time in RFC 3339, so spreadsheets can sort by it.
+fn format_timestamp(millis: i64) -> String {
+    Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| millis.to_string())
+}
+
+/// Quotes fields that need it. Fields that a spreadsheet would run as a formula, like a display
+/// name starting with `=`, are prefixed with `'`.
+fn escape_csv(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
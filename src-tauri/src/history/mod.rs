@@ -0,0 +1,54 @@
+mod export;
+mod store;
+mod types;
+
+use crate::config::SettingsStore;
+pub use export::export_history;
+pub use store::HistoryStore;
+pub use types::{
+    Decision, ExportFormat, HistoryFilter, HistoryPage, HistoryQuery, HistoryRecord,
+    NotificationKind, Subject, SuppressionReason,
+};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Records what became of a notification about `subjects`. Failing to record is only logged,
+/// so the history never stands in the way of a notification.
+pub fn record<R: Runtime>(
+    app: &AppHandle<R>,
+    subjects: &[Subject],
+    decision: Decision,
+    reason: Option<SuppressionReason>,
+) {
+    let Some(store) = app.try_state::<HistoryStore>() else {
+        return;
+    };
+    for subject in subjects {
+        if let Err(err) = store.record(subject, decision, reason) {
+            log::warn!("Failed to record notification history: {err}");
+        }
+    }
+
+    let retention = app.state::<SettingsStore>().snapshot().history_retention();
+    if let Some(retention) = retention {
+        store.purge_if_due(retention);
+    }
+}
+
+/// Records a notification that was shown. One about several friends is recorded as batched for
+/// each of them.
+pub fn record_shown<R: Runtime>(app: &AppHandle<R>, subjects: &[Subject]) {
+    let decision = if subjects.len() > 1 {
+        Decision::Batched
+    } else {
+        Decision::Shown
+    };
+    record(app, subjects, decision, None);
+}
+
+pub fn record_suppressed<R: Runtime>(
+    app: &AppHandle<R>,
+    subjects: &[Subject],
+    reason: SuppressionReason,
+) {
+    record(app, subjects, Decision::Suppressed, Some(reason));
+}
@@ -0,0 +1,275 @@
+use crate::database::Database;
+use crate::history::types::{
+    Decision, HistoryFilter, HistoryPage, HistoryQuery, HistoryRecord, NotificationKind, Subject,
+    SuppressionReason,
+};
+use crate::vrchat_utils::{AppError, AppResult};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Runtime};
+
+const FILE_NAME: &str = "NotificationHistory.sqlite3";
+/// How often recording purges the records that are past the retention.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Pages larger than this are cut down, so a careless query cannot load the whole history.
+const MAX_PAGE_SIZE: u32 = 500;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notification_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recorded_at INTEGER NOT NULL,
+    friend_id TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    location TEXT,
+    decision TEXT NOT NULL,
+    reason TEXT
+);
+CREATE INDEX IF NOT EXISTS notification_history_recorded_at
+    ON notification_history (recorded_at);
+CREATE INDEX IF NOT EXISTS notification_history_friend
+    ON notification_history (friend_id, recorded_at);
+";
+
+/// Keeps a record of every notification decision in a local SQLite database.
+pub struct HistoryStore {
+    database: Database,
+    last_purge: Mutex<Option<Instant>>,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> AppResult<Self> {
+        Ok(Self::with_database(Database::open(path, init)?))
+    }
+
+    /// A store that is gone when the app exits.
+    pub fn in_memory() -> AppResult<Self> {
+        Ok(Self::with_database(Database::in_memory(init)?))
+    }
+
+    /// Opens the database in the app data directory. Falls back to memory if that fails, so
+    /// notifications keep working.
+    pub fn for_app<R: Runtime>(app: &AppHandle<R>) -> Self {
+        Self::with_database(Database::for_app(
+            app,
+            FILE_NAME,
+            "notification history",
+            init,
+        ))
+    }
+
+    fn with_database(database: Database) -> Self {
+        Self {
+            database,
+            last_purge: Mutex::new(None),
+        }
+    }
+
+    pub fn record(
+        &self,
+        subject: &Subject,
+        decision: Decision,
+        reason: Option<SuppressionReason>,
+    ) -> AppResult<()> {
+        self.database.lock().execute(
+            "INSERT INTO notification_history
+                    (recorded_at, friend_id, display_name, kind, location, decision, reason)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                unix_millis(SystemTime::now()),
+                subject.friend_id,
+                subject.display_name,
+                subject.kind.as_str(),
+                subject.location,
+                decision.as_str(),
+                reason.map(SuppressionReason::as_str),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn page(&self, query: &HistoryQuery) -> AppResult<HistoryPage> {
+        let (condition, mut values) = where_clause(&query.filter);
+        let connection = self.database.lock();
+
+        let total: i64 = connection.query_row(
+            &format!("SELECT COUNT(*) FROM notification_history {condition}"),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        values.push(Value::Integer(i64::from(query.limit.min(MAX_PAGE_SIZE))));
+        values.push(Value::Integer(i64::from(query.offset)));
+        let records = select(
+            &connection,
+            &format!("{condition} ORDER BY recorded_at DESC, id DESC LIMIT ? OFFSET ?"),
+            values,
+        )?;
+
+        Ok(HistoryPage {
+            records,
+            total: total.max(0) as u64,
+        })
+    }
+
+    /// Every record matching `filter`, oldest first.
+    pub fn all(&self, filter: &HistoryFilter) -> AppResult<Vec<HistoryRecord>> {
+        let (condition, values) = where_clause(filter);
+        select(
+            &self.database.lock(),
+            &format!("{condition} ORDER BY recorded_at, id"),
+            values,
+        )
+    }
+
+    /// Deletes the records older than `retention`. Returns how many were deleted.
+    pub fn purge(&self, retention: Duration) -> AppResult<usize> {
+        let Some(cutoff) = SystemTime::now().checked_sub(retention) else {
+            return Ok(0);
+        };
+        self.database
+            .lock()
+            .execute(
+                "DELETE FROM notification_history WHERE recorded_at < ?1",
+                params![unix_millis(cutoff)],
+            )
+            .map_err(AppError::from)
+    }
+
+    /// Purges unless that was done within [`PURGE_INTERVAL`].
+    pub fn purge_if_due(&self, retention: Duration) {
+        {
+            let mut last_purge = self
+                .last_purge
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if last_purge.is_some_and(|at| at.elapsed() < PURGE_INTERVAL) {
+                return;
+            }
+            *last_purge = Some(Instant::now());
+        }
+        if let Err(err) = self.purge(retention) {
+            log::warn!("Failed to purge the notification history: {err}");
+        }
+    }
+
+    pub fn clear(&self) -> AppResult<()> {
+        self.database
+            .lock()
+            .execute("DELETE FROM notification_history", [])?;
+        Ok(())
+    }
+}
+
+fn init(connection: &Connection) -> AppResult<()> {
+    connection.execute_batch(SCHEMA)?;
+    Ok(())
+}
+
+fn select(
+    connection: &Connection,
+    tail: &str,
+    values: Vec<Value>,
+) -> AppResult<Vec<HistoryRecord>> {
+    let sql = format!(
+        "SELECT id, recorded_at, friend_id, display_name, kind, location, decision, reason
+         FROM notification_history {tail}"
+    );
+    let mut statement = connection.prepare(&sql)?;
+    let rows = statement.query_map(params_from_iter(values), read_record)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+}
+
+fn read_record(row: &Row<'_>) -> rusqlite::Result<HistoryRecord> {
+    let kind: String = row.get(4)?;
+    let decision: String = row.get(6)?;
+    let reason: Option<String> = row.get(7)?;
+    Ok(HistoryRecord {
+        id: row.get(0)?,
+        recorded_at: row.get(1)?,
+        friend_id: row.get(2)?,
+        display_name: row.get(3)?,
+        kind: NotificationKind::parse(&kind).ok_or_else(|| invalid_column(4, &kind))?,
+        location: row.get(5)?,
+        decision: Decision::parse(&decision).ok_or_else(|| invalid_column(6, &decision))?,
+        reason: reason.as_deref().and_then(SuppressionReason::parse),
+    })
+}
+
+fn where_clause(filter: &HistoryFilter) -> (String, Vec<Value>) {
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    if let Some(friend_id) = &filter.friend_id {
+        conditions.push(String::from("friend_id = ?"));
+        values.push(Value::Text(friend_id.clone()));
+    }
+    if let Some(display_name) = filter.display_name.as_deref().map(str::trim) {
+        if !display_name.is_empty() {
+            conditions.push(String::from("display_name LIKE ? ESCAPE '\\'"));
+            values.push(Value::Text(format!("%{}%", escape_like(display_name))));
+        }
+    }
+    if !filter.kinds.is_empty() {
+        conditions.push(format!("kind IN ({})", placeholders(filter.kinds.len())));
+        values.extend(
+            filter
+                .kinds
+                .iter()
+                .map(|kind| Value::Text(kind.as_str().to_string())),
+        );
+    }
+    if !filter.decisions.is_empty() {
+        conditions.push(format!(
+            "decision IN ({})",
+            placeholders(filter.decisions.len())
+        ));
+        values.extend(
+            filter
+                .decisions
+                .iter()
+                .map(|decision| Value::Text(decision.as_str().to_string())),
+        );
+    }
+    if let Some(since) = filter.since {
+        conditions.push(String::from("recorded_at >= ?"));
+        values.push(Value::Integer(since));
+    }
+    if let Some(until) = filter.until {
+        conditions.push(String::from("recorded_at < ?"));
+        values.push(Value::Integer(until));
+    }
+
+    if conditions.is_empty() {
+        return (String::new(), values);
+    }
+    (format!("WHERE {}", conditions.join(" AND ")), values)
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn invalid_column(index: usize, value: &str) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(
+        index,
+        rusqlite::types::Type::Text,
+        format!("Unknown value '{value}'").into(),
+    )
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,210 @@
+use crate::roster::RosterEntry;
+use serde::{Deserialize, Serialize};
+
+/// What a notification is about.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationKind {
+    Online,
+    Offline,
+    Location,
+    FriendAdded,
+    FriendRemoved,
+    ProfileChange,
+}
+
+/// What became of a notification.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Decision {
+    Shown,
+    /// Merged into a notification about several friends.
+    Batched,
+    /// Held back for the quiet hours digest, which is recorded once it is shown.
+    Deferred,
+    Suppressed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SuppressionReason {
+    /// Turned off for the friend.
+    Disabled,
+    /// The platform filter does not include the platform of the friend.
+    Platform,
+    /// The pipeline sent the same event again.
+    Duplicate,
+    /// Held back by the flap suppression.
+    Flapping,
+    QuietHours,
+}
+
+/// The friend and event a notification is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subject {
+    pub friend_id: String,
+    pub display_name: String,
+    pub kind: NotificationKind,
+    pub location: Option<String>,
+}
+
+impl Subject {
+    pub fn new(
+        kind: NotificationKind,
+        friend_id: &str,
+        display_name: &str,
+        location: Option<&str>,
+    ) -> Self {
+        Self {
+            friend_id: friend_id.to_string(),
+            display_name: display_name.to_string(),
+            kind,
+            location: location.map(str::to_string),
+        }
+    }
+
+    pub fn of_entry(kind: NotificationKind, entry: &RosterEntry) -> Self {
+        Self::new(
+            kind,
+            &entry.friend.id,
+            entry.display_name(),
+            Some(entry.location()),
+        )
+    }
+}
+
+/// One recorded decision.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRecord {
+    pub id: i64,
+    /// Unix timestamp in milliseconds.
+    pub recorded_at: i64,
+    pub friend_id: String,
+    pub display_name: String,
+    pub kind: NotificationKind,
+    pub location: Option<String>,
+    pub decision: Decision,
+    pub reason: Option<SuppressionReason>,
+}
+
+/// Narrows the history down. Every field that is set has to match.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HistoryFilter {
+    pub friend_id: Option<String>,
+    /// Part of the display name, ignoring case.
+    pub display_name: Option<String>,
+    pub kinds: Vec<NotificationKind>,
+    pub decisions: Vec<Decision>,
+    /// Unix timestamps in milliseconds. `since` is inclusive, `until` is not.
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+/// A page of the history, newest first.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HistoryQuery {
+    #[serde(flatten)]
+    pub filter: HistoryFilter,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            filter: HistoryFilter::default(),
+            offset: 0,
+            limit: 50,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub records: Vec<HistoryRecord>,
+    /// The number of records matching the filter across all pages.
+    pub total: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl NotificationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::Online => "online",
+            NotificationKind::Offline => "offline",
+            NotificationKind::Location => "location",
+            NotificationKind::FriendAdded => "friendAdded",
+            NotificationKind::FriendRemoved => "friendRemoved",
+            NotificationKind::ProfileChange => "profileChange",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            NotificationKind::Online,
+            NotificationKind::Offline,
+            NotificationKind::Location,
+            NotificationKind::FriendAdded,
+            NotificationKind::FriendRemoved,
+            NotificationKind::ProfileChange,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == value)
+    }
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Shown => "shown",
+            Decision::Batched => "batched",
+            Decision::Deferred => "deferred",
+            Decision::Suppressed => "suppressed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            Decision::Shown,
+            Decision::Batched,
+            Decision::Deferred,
+            Decision::Suppressed,
+        ]
+        .into_iter()
+        .find(|decision| decision.as_str() == value)
+    }
+}
+
+impl SuppressionReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SuppressionReason::Disabled => "disabled",
+            SuppressionReason::Platform => "platform",
+            SuppressionReason::Duplicate => "duplicate",
+            SuppressionReason::Flapping => "flapping",
+            SuppressionReason::QuietHours => "quietHours",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            SuppressionReason::Disabled,
+            SuppressionReason::Platform,
+            SuppressionReason::Duplicate,
+            SuppressionReason::Flapping,
+            SuppressionReason::QuietHours,
+        ]
+        .into_iter()
+        .find(|reason| reason.as_str() == value)
+    }
+}
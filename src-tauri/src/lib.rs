@@ -1,8 +1,14 @@
 mod auth;
 mod commands;
 mod config;
+mod database;
+mod history;
 mod notifier;
+mod presence;
+mod roster;
 mod shell;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 mod vrchat_utils;
 mod websocket;
 
@@ -12,8 +18,11 @@ use tauri::Manager;
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
-        .manage(auth::AuthState::new())
         .manage(websocket::WebsocketState::default())
+        .manage(websocket::PipelineDispatcher::default())
+        .manage(roster::FriendRoster::default())
+        .manage(vrchat_utils::ApiService::default())
+        .manage(notifier::NotifierScope::live())
         .plugin(
             tauri_plugin_frame::FramePluginBuilder::new()
                 .titlebar_height(48)
@@ -38,7 +47,35 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .setup(|app| {
-            app.manage(SettingsStore::load(app.handle()));
+            let settings = SettingsStore::load(app.handle());
+            let snapshot = settings.snapshot();
+            app.manage(settings);
+            app.manage(auth::AuthState::new(config::Endpoints::resolve(
+                &snapshot.endpoints,
+            )));
+            app.manage(websocket::PipelineRecorder::for_app(
+                app.handle(),
+                snapshot.record_pipeline,
+            ));
+            app.manage(notifier::NotifierState::for_platform(app.handle()));
+            let history = history::HistoryStore::for_app(app.handle());
+            if let Some(retention) = snapshot.history_retention() {
+                history.purge_if_due(retention);
+            }
+            app.manage(history);
+            app.manage(presence::PresenceStore::for_app(app.handle()));
+            let dispatcher = app.state::<websocket::PipelineDispatcher>();
+            let notifier_scope = app.state::<notifier::NotifierScope>();
+            notifier::register_pipeline_handlers(app.handle(), &dispatcher, &notifier_scope);
+            roster::register_pipeline_handlers(app.handle(), &dispatcher);
+            let friend_roster = app.state::<roster::FriendRoster>();
+            roster::register_frontend_updates(app.handle(), &friend_roster);
+            notifier::register_roster_listeners(app.handle(), &friend_roster, &notifier_scope);
+            notifier::register_settings_archive(app.handle(), &friend_roster);
+            presence::register_roster_listeners(app.handle(), &friend_roster);
+            presence::start_checkpoints(app.handle());
+            websocket::register_roster_snapshots(app.handle(), &friend_roster);
+            roster::start_periodic_resync(app.handle());
             shell::setup(app.handle())?;
             Ok(())
         })
@@ -50,10 +87,22 @@ pub fn run() {
             commands::logout,
             commands::fetch_friends,
             commands::fetch_world,
+            commands::parse_location,
+            commands::fetch_connection_state,
+            commands::fetch_pipeline_recording_dir,
+            commands::replay_pipeline_recording,
             commands::fetch_friend_settings,
+            commands::fetch_archived_friend_settings,
             commands::set_friend_settings,
             commands::fetch_app_settings,
             commands::set_app_settings,
+            commands::fetch_notification_history,
+            commands::export_notification_history,
+            commands::clear_notification_history,
+            commands::fetch_presence_stats,
+            commands::fetch_all_presence_stats,
+            commands::fetch_co_presence_graph,
+            commands::fetch_top_companions,
             commands::preview_notification_sound,
             commands::save_notification_sound,
             commands::save_notification_sound_path,
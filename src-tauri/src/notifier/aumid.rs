@@ -1,16 +1,16 @@
-use crate::vrchat_utils::AppResult;
+use crate::vrchat_utils::{AppError, AppResult};
 use std::sync::OnceLock;
-use tauri::AppHandle;
+use tauri::{AppHandle, Runtime};
 use windows_registry::CURRENT_USER;
 
 static APP_USER_ID_READY: OnceLock<bool> = OnceLock::new();
 
-pub fn ensure_app_user_model_id(app: &AppHandle) -> AppResult<()> {
+pub fn ensure_app_user_model_id<R: Runtime>(app: &AppHandle<R>) -> AppResult<()> {
     if let Some(ready) = APP_USER_ID_READY.get() {
         return if *ready {
             Ok(())
         } else {
-            Err(String::from("AppUserModelID is not ready."))
+            Err(AppError::notification("AppUserModelID is not ready."))
         };
     }
 
@@ -25,24 +25,30 @@ pub fn ensure_app_user_model_id(app: &AppHandle) -> AppResult<()> {
     Ok(())
 }
 
-fn register_app_user_model_id(app: &AppHandle) -> AppResult<()> {
+fn register_app_user_model_id<R: Runtime>(app: &AppHandle<R>) -> AppResult<()> {
     let app_id = app.config().identifier.clone();
 
     let key = CURRENT_USER
         .create(format!(r"SOFTWARE\Classes\AppUserModelId\{app_id}"))
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| AppError::notification(err.to_string()))?;
 
     let display_name = app
         .config()
         .product_name
         .clone()
-        .ok_or_else(|| String::from("Product name is not set in app config."))?;
+        .ok_or_else(|| AppError::notification("Product name is not set in app config."))?;
 
-    key.set_string("DisplayName", display_name)
-        .map_err(|err| format!("Failed to set Application User Model ID DisplayName: {err}"))?;
+    key.set_string("DisplayName", display_name).map_err(|err| {
+        AppError::notification(format!(
+            "Failed to set Application User Model ID DisplayName: {err}"
+        ))
+    })?;
 
-    key.set_string("IconBackgroundColor", "0")
-        .map_err(|err| format!("Failed to set AppUserModelID IconBackgroundColor: {err}"))?;
+    key.set_string("IconBackgroundColor", "0").map_err(|err| {
+        AppError::notification(format!(
+            "Failed to set AppUserModelID IconBackgroundColor: {err}"
+        ))
+    })?;
 
     if let Ok(dir) = std::env::current_dir() {
         let candidates = [
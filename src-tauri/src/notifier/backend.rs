@@ -0,0 +1,116 @@
+use crate::notifier::custom_sounds;
+use crate::vrchat_utils::AppResult;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Runtime};
+
+#[cfg(any(test, feature = "test-support"))]
+const BACKEND_ENV: &str = "VFRIENDS_NOTIFICATION_BACKEND";
+
+/// A notification that has been fully rendered and only needs to be displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub icon_src: Option<String>,
+    /// Custom sound to play alongside the notification, if any.
+    pub sound: Option<PathBuf>,
+    /// Whether the system sound of the notification should be suppressed.
+    pub silent: bool,
+}
+
+impl Notification {
+    /// Custom sounds are skipped in silent mode and replace the system sound otherwise.
+    pub fn new(title: String, body: String, sound: Option<&str>, silent_mode: bool) -> Self {
+        let sound = sound.filter(|_| !silent_mode).map(PathBuf::from);
+        Self {
+            title,
+            body,
+            icon_src: None,
+            silent: silent_mode || sound.is_some(),
+            sound,
+        }
+    }
+}
+
+/// Displays notifications on behalf of the notifier.
+pub trait NotificationBackend: Send + Sync {
+    fn show(&self, notification: &Notification) -> AppResult<()>;
+
+    fn is_silent_mode(&self) -> bool;
+
+    /// Shows a short notification so the user can hear the system notification sound.
+    fn preview_sound(&self) -> AppResult<()>;
+
+    fn play_sound(&self, path: &Path) {
+        custom_sounds::play_custom_sound(path.to_path_buf());
+    }
+}
+
+pub struct NotifierState {
+    backend: Arc<dyn NotificationBackend>,
+}
+
+impl NotifierState {
+    pub fn new(backend: Arc<dyn NotificationBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Selects the backend of the current platform. Platforms without one only log notifications.
+    /// With the `test-support` feature, `VFRIENDS_NOTIFICATION_BACKEND=recording` keeps
+    /// notifications in memory instead.
+    pub fn for_platform<R: Runtime>(app: &AppHandle<R>) -> Self {
+        #[cfg(any(test, feature = "test-support"))]
+        if std::env::var(BACKEND_ENV).is_ok_and(|value| value == "recording") {
+            log::info!("Using the recording notification backend.");
+            return Self::new(Arc::new(crate::notifier::RecordingBackend::default()));
+        }
+
+        #[cfg(target_os = "windows")]
+        let backend = Arc::new(crate::notifier::windows_os::WindowsToastBackend::new(app));
+        #[cfg(target_os = "linux")]
+        let backend = Arc::new(crate::notifier::linux_os::FreedesktopBackend::new(app));
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let backend = {
+            let _ = app;
+            Arc::new(LogBackend)
+        };
+
+        Self::new(backend)
+    }
+
+    pub fn backend(&self) -> &dyn NotificationBackend {
+        self.backend.as_ref()
+    }
+
+    pub fn deliver(&self, notification: &Notification) -> AppResult<()> {
+        if let Some(sound) = notification.sound.as_deref() {
+            self.backend.play_sound(sound);
+        }
+        self.backend.show(notification)
+    }
+}
+
+/// Logs notifications on platforms without native notifications.
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+struct LogBackend;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+impl NotificationBackend for LogBackend {
+    fn show(&self, notification: &Notification) -> AppResult<()> {
+        log::info!(
+            "Notification: {} - {}",
+            notification.title,
+            notification.body
+        );
+        Ok(())
+    }
+
+    fn is_silent_mode(&self) -> bool {
+        false
+    }
+
+    fn preview_sound(&self) -> AppResult<()> {
+        Ok(())
+    }
+}
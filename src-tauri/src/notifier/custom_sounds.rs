@@ -1,4 +1,4 @@
-use crate::vrchat_utils::AppResult;
+use crate::vrchat_utils::{AppError, AppResult};
 use rodio::{Decoder, OutputStream, Sink, Source};
 use sha2::{Digest, Sha256};
 use std::fs::File;
@@ -62,9 +62,11 @@ fn sound_sender() -> mpsc::Sender<PathBuf> {
 pub fn validate_sound_path(path: &str) -> AppResult<()> {
     let path = Path::new(path);
     let _ = sound_extension(path)?;
-    let file = File::open(path).map_err(|err| format!("Failed to open sound file: {err}"))?;
+    let file = File::open(path)
+        .map_err(|err| AppError::invalid_sound(format!("Failed to open sound file: {err}")))?;
     let reader = BufReader::new(file);
-    let decoder = Decoder::new(reader).map_err(|_| String::from("Unsupported audio format."))?;
+    let decoder =
+        Decoder::new(reader).map_err(|_| AppError::invalid_sound("Unsupported audio format."))?;
     ensure_sound_duration(decoder)
 }
 
@@ -75,7 +77,7 @@ pub fn play_custom_sound(path: PathBuf) {
     }
 }
 
-pub fn store_custom_sound(app: &AppHandle, name: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+pub fn store_custom_sound(app: &AppHandle, name: &str, bytes: &[u8]) -> AppResult<PathBuf> {
     validate_sound_bytes(name, bytes)?;
     let ext = sound_extension(Path::new(name))?;
     let hash = hash_bytes(bytes);
@@ -83,12 +85,12 @@ pub fn store_custom_sound(app: &AppHandle, name: &str, bytes: &[u8]) -> Result<P
     let base_path = sound_directory(app)?;
     let path = base_path.join(file_name);
     if !path.exists() {
-        std::fs::write(&path, bytes).map_err(|err| err.to_string())?;
+        std::fs::write(&path, bytes)?;
     }
     Ok(path)
 }
 
-pub fn store_custom_sound_from_path(app: &AppHandle, path: &Path) -> Result<PathBuf, String> {
+pub fn store_custom_sound_from_path(app: &AppHandle, path: &Path) -> AppResult<PathBuf> {
     let _ = validate_sound_path(path.to_string_lossy().as_ref())?;
     let ext = sound_extension(path)?;
     let base_path = sound_directory(app)?;
@@ -99,15 +101,16 @@ pub fn store_custom_sound_from_path(app: &AppHandle, path: &Path) -> Result<Path
     let file_name = format!("{hash}.{ext}");
     let target = base_path.join(file_name);
     if !target.exists() {
-        std::fs::copy(path, &target).map_err(|err| err.to_string())?;
+        std::fs::copy(path, &target)?;
     }
     Ok(target)
 }
 
-pub fn sound_duration_ms(path: &Path) -> Result<u64, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
+pub fn sound_duration_ms(path: &Path) -> AppResult<u64> {
+    let file = File::open(path)?;
     let reader = BufReader::new(file);
-    let decoder = Decoder::new(reader).map_err(|_| String::from("Unsupported audio format."))?;
+    let decoder =
+        Decoder::new(reader).map_err(|_| AppError::invalid_sound("Unsupported audio format."))?;
     let duration = match decoder.total_duration() {
         Some(duration) => duration,
         None => estimate_duration(decoder)?,
@@ -115,25 +118,21 @@ pub fn sound_duration_ms(path: &Path) -> Result<u64, String> {
     Ok(duration.as_millis().max(1) as u64)
 }
 
-pub(crate) fn sound_directory(app: &AppHandle) -> Result<PathBuf, String> {
-    let base = app
-        .path()
-        .app_data_dir()
-        .map_err(|err| err.to_string())?
-        .join(SOUND_DIR_NAME);
-    std::fs::create_dir_all(&base).map_err(|err| err.to_string())?;
+pub(crate) fn sound_directory(app: &AppHandle) -> AppResult<PathBuf> {
+    let base = app.path().app_data_dir()?.join(SOUND_DIR_NAME);
+    std::fs::create_dir_all(&base)?;
     Ok(base)
 }
 
 pub fn cleanup_custom_sounds(
     app: &AppHandle,
     referenced_files: &std::collections::HashSet<String>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let base_path = sound_directory(app)?;
-    let entries = std::fs::read_dir(&base_path).map_err(|err| err.to_string())?;
+    let entries = std::fs::read_dir(&base_path)?;
 
     for entry in entries {
-        let entry = entry.map_err(|err| err.to_string())?;
+        let entry = entry?;
         let path = entry.path();
         if !path.is_file() {
             continue;
@@ -157,10 +156,10 @@ fn sound_extension(path: &Path) -> AppResult<String> {
         .and_then(|ext| ext.to_str())
         .map(|ext| ext.to_lowercase())
         .filter(|ext| !ext.is_empty())
-        .ok_or_else(|| String::from("Unsupported audio format."))?;
+        .ok_or_else(|| AppError::invalid_sound("Unsupported audio format."))?;
 
     if !ALLOWED_EXTENSIONS.contains(&ext.as_str()) {
-        return Err(String::from("Unsupported audio format."));
+        return Err(AppError::invalid_sound("Unsupported audio format."));
     }
 
     Ok(ext)
@@ -170,7 +169,8 @@ fn validate_sound_bytes(name: &str, bytes: &[u8]) -> AppResult<()> {
     let _ = sound_extension(Path::new(name))?;
     let cursor = Cursor::new(bytes.to_vec());
     let reader = BufReader::new(cursor);
-    let decoder = Decoder::new(reader).map_err(|_| String::from("Unsupported audio format."))?;
+    let decoder =
+        Decoder::new(reader).map_err(|_| AppError::invalid_sound("Unsupported audio format."))?;
     ensure_sound_duration(decoder)
 }
 
@@ -180,7 +180,9 @@ fn ensure_sound_duration<R: Read + Seek + Send + Sync + 'static>(
     let max_duration = Duration::from_secs(MAX_SOUND_SECONDS);
     if let Some(duration) = decoder.total_duration() {
         if duration >= max_duration {
-            return Err(String::from("Sound must be shorter than 15 seconds."));
+            return Err(AppError::invalid_sound(
+                "Sound must be shorter than 15 seconds.",
+            ));
         }
         return Ok(());
     }
@@ -192,7 +194,9 @@ fn ensure_sound_duration<R: Read + Seek + Send + Sync + 'static>(
     while let Some(_sample) = decoder.next() {
         samples += 1;
         if samples >= max_samples {
-            return Err(String::from("Sound must be shorter than 15 seconds."));
+            return Err(AppError::invalid_sound(
+                "Sound must be shorter than 15 seconds.",
+            ));
         }
     }
     Ok(())
@@ -200,7 +204,7 @@ fn ensure_sound_duration<R: Read + Seek + Send + Sync + 'static>(
 
 fn estimate_duration<R: Read + Seek + Send + Sync + 'static>(
     mut decoder: Decoder<R>,
-) -> Result<Duration, String> {
+) -> AppResult<Duration> {
     let channels = decoder.channels().max(1) as u64;
     let sample_rate = decoder.sample_rate().max(1) as u64;
     let mut samples = 0u64;
@@ -219,12 +223,12 @@ fn hash_bytes(bytes: &[u8]) -> String {
     format!("{:x}", digest)
 }
 
-fn hash_file(path: &Path) -> Result<String, String> {
-    let mut file = File::open(path).map_err(|err| err.to_string())?;
+fn hash_file(path: &Path) -> AppResult<String> {
+    let mut file = File::open(path)?;
     let mut hasher = Sha256::new();
     let mut buffer = [0u8; 8192];
     loop {
-        let read = file.read(&mut buffer).map_err(|err| err.to_string())?;
+        let read = file.read(&mut buffer)?;
         if read == 0 {
             break;
         }
@@ -0,0 +1,122 @@
+use crate::config::FlapSuppression;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// What to do with an online event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnlineVerdict {
+    Notify,
+    /// The same event as the last one, sent again within the dedup window, or any online event
+    /// while an earlier one waits for confirmation.
+    Duplicate,
+    /// The friend came online again too soon after the last notification.
+    Cooldown,
+    /// Notify after `after` unless the friend went offline meanwhile. Pass `generation` to
+    /// [`FlapGuard::confirm_online`].
+    Confirm {
+        after: Duration,
+        generation: u64,
+    },
+}
+
+#[derive(Default)]
+struct FriendPresence {
+    /// The last online event, identified by its location and platform.
+    last_online_event: Option<(Instant, String)>,
+    last_notified_online: Option<Instant>,
+    /// An [`OnlineVerdict::Confirm`] has been given and not confirmed yet.
+    confirm_pending: bool,
+    /// Counts offline events, so a pending confirmation can tell whether the friend left.
+    offline_generation: u64,
+    /// The friend is online but has not been notified about, so going offline is not either.
+    online_suppressed: bool,
+}
+
+/// Tracks the recent presence of each friend to suppress notifications about flapping
+/// connections and duplicate pipeline events.
+#[derive(Default)]
+pub struct FlapGuard {
+    friends: Mutex<HashMap<String, FriendPresence>>,
+}
+
+impl FlapGuard {
+    pub fn on_online(
+        &self,
+        user_id: &str,
+        fingerprint: &str,
+        settings: &FlapSuppression,
+    ) -> OnlineVerdict {
+        let now = Instant::now();
+        let mut friends = self.lock();
+        let presence = friends.entry(user_id.to_string()).or_default();
+
+        let previous_event = presence
+            .last_online_event
+            .replace((now, fingerprint.to_string()));
+        let is_duplicate = previous_event.is_some_and(|(at, previous)| {
+            previous == fingerprint && within(now, at, settings.dedup_window_secs)
+        });
+        if is_duplicate || presence.confirm_pending {
+            return OnlineVerdict::Duplicate;
+        }
+
+        let in_cooldown = presence
+            .last_notified_online
+            .is_some_and(|at| within(now, at, settings.online_cooldown_secs));
+        if in_cooldown {
+            presence.online_suppressed = true;
+            return OnlineVerdict::Cooldown;
+        }
+
+        if settings.min_online_secs > 0 {
+            presence.online_suppressed = true;
+            presence.confirm_pending = true;
+            return OnlineVerdict::Confirm {
+                after: Duration::from_secs(settings.min_online_secs),
+                generation: presence.offline_generation,
+            };
+        }
+
+        presence.online_suppressed = false;
+        presence.last_notified_online = Some(now);
+        OnlineVerdict::Notify
+    }
+
+    /// Returns `true` if the friend stayed online since [`OnlineVerdict::Confirm`] was given.
+    /// Only the first call for a confirmation can succeed.
+    pub fn confirm_online(&self, user_id: &str, generation: u64) -> bool {
+        let mut friends = self.lock();
+        let Some(presence) = friends.get_mut(user_id) else {
+            return false;
+        };
+        if presence.offline_generation != generation || !presence.confirm_pending {
+            return false;
+        }
+        presence.confirm_pending = false;
+        presence.online_suppressed = false;
+        presence.last_notified_online = Some(Instant::now());
+        true
+    }
+
+    /// Returns `false` if going offline should not be notified, because coming online was not.
+    pub fn on_offline(&self, user_id: &str) -> bool {
+        let mut friends = self.lock();
+        let presence = friends.entry(user_id.to_string()).or_default();
+        presence.offline_generation += 1;
+        presence.last_online_event = None;
+        presence.confirm_pending = false;
+        !std::mem::take(&mut presence.online_suppressed)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, FriendPresence>> {
+        self.friends
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn within(now: Instant, at: Instant, window_secs: u64) -> bool {
+    window_secs > 0 && now.duration_since(at) < Duration::from_secs(window_secs)
+}
@@ -0,0 +1,196 @@
+use crate::auth::AuthState;
+use crate::config::{AppSettings, SettingsStore};
+use crate::history::{NotificationKind, Subject};
+use crate::notifier::template::{render_template, render_title, TemplateValues};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::roster::{ChangeCause, FriendRoster, RosterChange, RosterEntry};
+use crate::vrchat_utils;
+use crate::vrchat_utils::{ApiService, AppResult, Location};
+use crate::websocket::PipelineEvent;
+use log::{error, warn};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// The last location each friend has settled in, skipping the `traveling` step in between.
+#[derive(Default)]
+struct SettledLocations {
+    locations: Mutex<HashMap<String, String>>,
+}
+
+impl SettledLocations {
+    /// Records the new location and returns the one it replaces.
+    fn replace(&self, user_id: &str, location: &str) -> Option<String> {
+        let mut locations = self
+            .locations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        locations.insert(user_id.to_string(), location.to_string())
+    }
+
+    fn forget(&self, user_id: &str) {
+        let mut locations = self
+            .locations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        locations.remove(user_id);
+    }
+}
+
+pub fn register_roster_listeners<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &FriendRoster,
+    scope: &NotifierScope,
+) {
+    let app = app.clone();
+    let scope = scope.clone();
+    let settled = SettledLocations::default();
+    roster.subscribe(move |change| {
+        let ChangeCause::Pipeline(event) = &change.cause else {
+            return;
+        };
+
+        let Some(current) = change.current.as_ref().filter(|entry| entry.is_online()) else {
+            settled.forget(&change.user_id);
+            return;
+        };
+        if !matches!(event, PipelineEvent::FriendLocation(_)) {
+            if !is_traveling(current.location()) {
+                settled.replace(&change.user_id, current.location());
+            }
+            return;
+        }
+        if is_traveling(current.location()) {
+            return;
+        }
+
+        let previous_location = settled
+            .replace(&change.user_id, current.location())
+            .or_else(|| previous_settled_location(change));
+        if !is_worth_notifying(previous_location.as_deref(), current.location()) {
+            return;
+        }
+
+        let app = app.clone();
+        let scope = scope.clone();
+        let current = current.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = notify_friend_location(&app, &scope, current).await {
+                error!("Failed to notify friend location: {}", err);
+            }
+        });
+    });
+}
+
+pub async fn notify_friend_location<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    entry: RosterEntry,
+) -> AppResult<()> {
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    if !is_enabled(&app_settings, &entry.friend.id) {
+        return Ok(());
+    }
+
+    let world_name = resolve_world_name(app, entry.location()).await;
+
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+    let Some(mut notification) =
+        compose_friend_location(&app_settings, &entry, world_name.as_deref(), silent_mode)
+    else {
+        return Ok(());
+    };
+    let icon_url = vrchat_utils::resolve_friend_icon_url(&entry.friend);
+    notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+
+    let subject = Subject::of_entry(NotificationKind::Location, &entry);
+    quiet_hours::deliver(app, scope, &app_settings, notification, &[subject])
+}
+
+/// Looks up the name of the world of `location`. `None` for private instances and when the
+/// lookup fails.
+pub(super) async fn resolve_world_name<R: Runtime>(
+    app: &AppHandle<R>,
+    location: &str,
+) -> Option<String> {
+    let location = Location::parse(location).ok()?;
+    let world_id = location.world_id()?;
+    let api = app.state::<ApiService>();
+    let auth_state = app.state::<AuthState>();
+    match vrchat_utils::fetch_world_name(&api, &auth_state, world_id).await {
+        Ok(name) => Some(name),
+        Err(err) => {
+            warn!("Failed to resolve world name of '{world_id}': {err}");
+            None
+        }
+    }
+}
+
+/// Renders the notification for a friend that changed instances, or returns `None` when it should not be shown.
+/// `world_name` is `None` when it could not be looked up.
+pub fn compose_friend_location(
+    app_settings: &AppSettings,
+    entry: &RosterEntry,
+    world_name: Option<&str>,
+    silent_mode: bool,
+) -> Option<Notification> {
+    if !is_enabled(app_settings, &entry.friend.id) {
+        return None;
+    }
+
+    let friend_settings = app_settings.friend_settings_of(&entry.friend.id);
+
+    let values = TemplateValues::for_entry(entry).with_location(entry.location(), world_name);
+    let title = render_title(app_settings, &entry.friend.id, &values);
+    let body = render_template(
+        friend_settings
+            .filter(|fs| fs.use_override)
+            .and_then(|fs| fs.location_message_override.as_deref())
+            .unwrap_or(&app_settings.default_location_message),
+        &values,
+    );
+
+    let override_sound = friend_settings
+        .filter(|settings| settings.use_override)
+        .and_then(|settings| settings.location_sound_override.as_deref());
+    let sound = override_sound.or(app_settings.default_sound.as_deref());
+
+    Some(Notification::new(title, body, sound, silent_mode))
+}
+
+/// A friend that is turned off is not notified about, whatever the toggle says.
+fn is_enabled(app_settings: &AppSettings, friend_id: &str) -> bool {
+    let friend_settings = app_settings.friend_settings_of(friend_id);
+    let disabled = friend_settings.is_some_and(|fs| !fs.enabled);
+    !disabled
+        && friend_settings
+            .map(|fs| fs.location_enabled)
+            .unwrap_or_default()
+            .resolve(app_settings.location_notifications_enabled)
+}
+
+fn previous_settled_location(change: &RosterChange) -> Option<String> {
+    change
+        .previous
+        .as_ref()
+        .map(|entry| entry.location())
+        .filter(|location| !is_traveling(location))
+        .map(|location| location.to_string())
+}
+
+fn is_traveling(location: &str) -> bool {
+    matches!(Location::parse(location), Ok(Location::Traveling))
+}
+
+/// Hopping between private instances or coming online is not a move worth a toast.
+fn is_worth_notifying(previous: Option<&str>, current: &str) -> bool {
+    let Some(previous) = previous.filter(|previous| !previous.is_empty() && *previous != current)
+    else {
+        return false;
+    };
+    match (Location::parse(previous), Location::parse(current)) {
+        (Ok(Location::Offline), _) => false,
+        (Ok(Location::Private), Ok(Location::Private)) => false,
+        _ => true,
+    }
+}
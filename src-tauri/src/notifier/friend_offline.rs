@@ -0,0 +1,133 @@
+use crate::config::{AppSettings, SettingsStore};
+use crate::history::{NotificationKind, Subject, SuppressionReason};
+use crate::notifier::template::{render_template, render_title, TemplateValues};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::roster::{ChangeCause, FriendRoster, RosterEntry};
+use crate::vrchat_utils;
+use crate::vrchat_utils::AppResult;
+use crate::websocket::PipelineEvent;
+use log::error;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// The offline payload has no user object, so this reacts to the roster where the last known
+/// state of the friend is still available.
+pub fn register_roster_listeners<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &FriendRoster,
+    scope: &NotifierScope,
+) {
+    let app = app.clone();
+    let scope = scope.clone();
+    roster.subscribe(move |change| {
+        if !matches!(
+            change.cause,
+            ChangeCause::Pipeline(PipelineEvent::FriendOffline(_))
+        ) {
+            return;
+        }
+        let notified_online = scope.flap_guard().on_offline(&change.user_id);
+        let Some(previous) = change.previous.clone().filter(RosterEntry::is_online) else {
+            return;
+        };
+        if !notified_online {
+            let app_settings = app.state::<SettingsStore>().snapshot();
+            if is_enabled(&app_settings, &change.user_id) {
+                let subject = Subject::of_entry(NotificationKind::Offline, &previous);
+                scope.record_suppressed(&app, &[subject], SuppressionReason::Flapping);
+            }
+            return;
+        }
+
+        let app = app.clone();
+        let scope = scope.clone();
+        tauri::async_runtime::spawn(async move {
+            let offline_at = SystemTime::now();
+            if let Err(err) = notify_friend_offline(&app, &scope, previous, offline_at).await {
+                error!("Failed to notify friend offline: {}", err);
+            }
+        });
+    });
+}
+
+pub async fn notify_friend_offline<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    last_known: RosterEntry,
+    offline_at: SystemTime,
+) -> AppResult<()> {
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+
+    let Some(mut notification) =
+        compose_friend_offline(&app_settings, &last_known, offline_at, silent_mode)
+    else {
+        return Ok(());
+    };
+    let icon_url = vrchat_utils::resolve_friend_icon_url(&last_known.friend);
+    notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+
+    let subject = Subject::of_entry(NotificationKind::Offline, &last_known);
+    quiet_hours::deliver(app, scope, &app_settings, notification, &[subject])
+}
+
+/// Renders the notification for a friend that went offline, or returns `None` when it should not be shown.
+pub fn compose_friend_offline(
+    app_settings: &AppSettings,
+    last_known: &RosterEntry,
+    offline_at: SystemTime,
+    silent_mode: bool,
+) -> Option<Notification> {
+    if !is_enabled(app_settings, &last_known.friend.id) {
+        return None;
+    }
+    let friend_settings = app_settings.friend_settings_of(&last_known.friend.id);
+
+    let online_for = last_known
+        .online_since
+        .and_then(|since| offline_at.duration_since(since).ok());
+
+    let values = TemplateValues::for_entry(last_known)
+        .with_location(last_known.location(), None)
+        .with("onlineFor", format_online_duration(online_for));
+    let title = render_title(app_settings, &last_known.friend.id, &values);
+    let body = render_template(
+        friend_settings
+            .filter(|fs| fs.use_override)
+            .and_then(|fs| fs.offline_message_override.as_deref())
+            .unwrap_or(&app_settings.default_offline_message),
+        &values,
+    );
+
+    let override_sound = friend_settings
+        .filter(|settings| settings.use_override)
+        .and_then(|settings| settings.offline_sound_override.as_deref());
+    let sound = override_sound.or(app_settings.default_offline_sound.as_deref());
+
+    Some(Notification::new(title, body, sound, silent_mode))
+}
+
+/// A friend that is turned off is not notified about, whatever the toggle says.
+fn is_enabled(app_settings: &AppSettings, friend_id: &str) -> bool {
+    let friend_settings = app_settings.friend_settings_of(friend_id);
+    let disabled = friend_settings.is_some_and(|fs| !fs.enabled);
+    !disabled
+        && friend_settings
+            .map(|fs| fs.offline_enabled)
+            .unwrap_or_default()
+            .resolve(app_settings.offline_notifications_enabled)
+}
+
+pub fn format_online_duration(duration: Option<Duration>) -> String {
+    let Some(duration) = duration else {
+        return String::from("a while");
+    };
+
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => String::from("less than a minute"),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, 0) => format!("{hours}h"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
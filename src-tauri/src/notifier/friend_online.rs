@@ -1,67 +1,245 @@
-use crate::config::SettingsStore;
-use crate::notifier::aumid::ensure_app_user_model_id;
-use crate::notifier::{custom_sounds, windows_os};
+use crate::config::{AppSettings, SettingsStore};
+use crate::history::{NotificationKind, Subject, SuppressionReason};
+use crate::notifier::flap_guard::OnlineVerdict;
+use crate::notifier::friend_location::resolve_world_name;
+use crate::notifier::online_batch::OnlineBatcher;
+use crate::notifier::template::{
+    render_template, render_title, template_uses, title_of, TemplateValues,
+};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::vrchat_utils;
 use crate::vrchat_utils::AppResult;
-use crate::websocket::FriendOnlineEvent;
-use crate::{auth, vrchat_utils};
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use crate::websocket::{
+    FriendActiveEvent, FriendOnlineEvent, PipelineDispatcher, PipelineEvent, PipelineEventKind,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime};
 
-pub async fn notify_friend_online(app: &AppHandle, event: FriendOnlineEvent) -> AppResult<()> {
-    if event.platform == "web" {
-        return Ok(());
-    }
+const WEB_PLATFORM: &str = "web";
+
+pub fn register_pipeline_handlers<R: Runtime>(
+    app: &AppHandle<R>,
+    dispatcher: &PipelineDispatcher,
+    scope: &NotifierScope,
+) {
+    let app = app.clone();
+    let scope = scope.clone();
+    let batcher = Arc::new(OnlineBatcher::default());
+    let kinds = [
+        PipelineEventKind::FriendOnline,
+        PipelineEventKind::FriendActive,
+    ];
+    dispatcher.subscribe_all(&kinds, move |event| {
+        let event = match event {
+            PipelineEvent::FriendOnline(event) => event.clone(),
+            PipelineEvent::FriendActive(event) => website_activity(event),
+            _ => return,
+        };
+        let app_settings = app.state::<SettingsStore>().snapshot();
+        if !app_settings
+            .platform_filter_of(&event.user_id)
+            .allows(&event.platform)
+        {
+            let subject = subject_of(&event);
+            scope.record_suppressed(&app, &[subject], SuppressionReason::Platform);
+            return;
+        }
+        let flap_suppression = app_settings.flap_suppression_of(&event.user_id);
+        let fingerprint = format!("{}|{}", event.location, event.platform);
+        let guard = scope.flap_guard();
+
+        match guard.on_online(&event.user_id, &fingerprint, &flap_suppression) {
+            OnlineVerdict::Notify => batcher.push(&app, &scope, event),
+            // Repeated events are not decisions about a new notification, so only the first
+            // one is recorded.
+            OnlineVerdict::Duplicate => {}
+            OnlineVerdict::Cooldown => {
+                let subject = subject_of(&event);
+                scope.record_suppressed(&app, &[subject], SuppressionReason::Flapping);
+            }
+            OnlineVerdict::Confirm { after, generation } => {
+                let app = app.clone();
+                let scope = scope.clone();
+                let batcher = Arc::clone(&batcher);
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(after).await;
+                    if scope
+                        .flap_guard()
+                        .confirm_online(&event.user_id, generation)
+                    {
+                        batcher.push(&app, &scope, event);
+                    } else {
+                        let subject = subject_of(&event);
+                        scope.record_suppressed(&app, &[subject], SuppressionReason::Flapping);
+                    }
+                });
+            }
+        }
+    });
+}
 
-    ensure_app_user_model_id(&app)?;
+/// Website activity is notified like coming online, from the "web" platform, so the platform
+/// filter decides whether it is shown at all.
+fn website_activity(event: &FriendActiveEvent) -> FriendOnlineEvent {
+    FriendOnlineEvent {
+        user_id: event.user_id.clone(),
+        platform: event
+            .platform
+            .clone()
+            .filter(|platform| !platform.is_empty())
+            .unwrap_or_else(|| String::from(WEB_PLATFORM)),
+        location: String::from("offline"),
+        can_request_invite: false,
+        user: event.user.clone(),
+    }
+}
 
+pub async fn notify_friend_online<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    event: FriendOnlineEvent,
+) -> AppResult<()> {
     let app_settings = app.state::<SettingsStore>().snapshot();
-    let friend_settings = app_settings.friend_settings_of(&event.user_id);
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+
+    let world_names = resolve_world_names(app, &app_settings, std::slice::from_ref(&event)).await;
+    let Some(mut notification) =
+        compose_friend_online(&app_settings, &event, &world_names, silent_mode)
+    else {
+        record_filtered(app, scope, &app_settings, std::slice::from_ref(&event));
+        return Ok(());
+    };
+    let icon_url = vrchat_utils::resolve_user_icon_url(&event.user);
+    notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+
+    quiet_hours::deliver(
+        app,
+        scope,
+        &app_settings,
+        notification,
+        &[subject_of(&event)],
+    )
+}
+
+pub(super) fn subject_of(event: &FriendOnlineEvent) -> Subject {
+    Subject::new(
+        NotificationKind::Online,
+        &event.user_id,
+        &event.user.display_name,
+        Some(&event.location),
+    )
+}
 
-    if let Some(friend_settings) = friend_settings {
-        if !friend_settings.enabled {
-            return Ok(());
+/// Records `events` that the settings keep from being notified.
+pub(super) fn record_filtered<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    app_settings: &AppSettings,
+    events: &[FriendOnlineEvent],
+) {
+    for event in events {
+        if let Some(reason) = suppression_of(app_settings, &event.user_id, &event.platform) {
+            scope.record_suppressed(app, &[subject_of(event)], reason);
         }
     }
+}
 
-    let title = &event.user.display_name;
-    let body = friend_settings
-        .filter(|fs| fs.use_override)
-        .and_then(|fs| fs.message_override.as_deref())
-        .unwrap_or(&app_settings.default_message)
-        .replace("%s", &event.user.display_name);
-    let icon_src = match vrchat_utils::resolve_user_icon_url(&event.user) {
-        Some(url) => {
-            let auth_state = app.state::<auth::AuthState>();
-            let (client, user_agent) = auth_state.with_session(|session| {
-                (
-                    session.config.client.clone(),
-                    session
-                        .config
-                        .user_agent
-                        .clone()
-                        .unwrap_or_else(|| "vfriends".to_string()),
-                )
-            })?;
-            vrchat_utils::fetch_user_icon_file_uri(&url, &client, &user_agent).await
+/// Looks up the names of the worlds that the templates for `events` show, keyed by location.
+pub(super) async fn resolve_world_names<R: Runtime>(
+    app: &AppHandle<R>,
+    app_settings: &AppSettings,
+    events: &[FriendOnlineEvent],
+) -> HashMap<String, String> {
+    let mut world_names = HashMap::new();
+    for event in events {
+        if world_names.contains_key(&event.location) || !shows_world(app_settings, &event.user_id) {
+            continue;
         }
-        None => None,
-    };
+        if let Some(world_name) = resolve_world_name(app, &event.location).await {
+            world_names.insert(event.location.clone(), world_name);
+        }
+    }
+    world_names
+}
+
+fn shows_world(app_settings: &AppSettings, user_id: &str) -> bool {
+    [
+        message_of(app_settings, user_id),
+        title_of(app_settings, user_id),
+    ]
+    .into_iter()
+    .any(|source| template_uses(source, "worldName") || template_uses(source, "world"))
+}
+
+/// Renders the notification for `event`, or returns `None` when it should not be shown.
+/// `world_names` holds the names looked up with [`resolve_world_names`].
+pub fn compose_friend_online(
+    app_settings: &AppSettings,
+    event: &FriendOnlineEvent,
+    world_names: &HashMap<String, String>,
+    silent_mode: bool,
+) -> Option<Notification> {
+    let world_name = world_names.get(&event.location).map(String::as_str);
+    let values = TemplateValues::for_friend(
+        &event.user.display_name,
+        event.user.status,
+        &event.user.status_description,
+        &event.platform,
+    )
+    .with_location(&event.location, world_name);
+    compose_online(
+        app_settings,
+        &event.user_id,
+        &event.platform,
+        &values,
+        silent_mode,
+    )
+}
+
+pub(super) fn compose_online(
+    app_settings: &AppSettings,
+    user_id: &str,
+    platform: &str,
+    values: &TemplateValues,
+    silent_mode: bool,
+) -> Option<Notification> {
+    if suppression_of(app_settings, user_id, platform).is_some() {
+        return None;
+    }
+
+    let friend_settings = app_settings.friend_settings_of(user_id);
+
+    let title = render_title(app_settings, user_id, values);
+    let body = render_template(message_of(app_settings, user_id), values);
 
-    let silent_mode = windows_os::is_silent_mode(&app).unwrap_or(false);
     let override_sound = friend_settings
-        .as_ref()
         .filter(|settings| settings.use_override)
         .and_then(|settings| settings.sound_override.as_deref());
-    let sound_path = override_sound.or(app_settings.default_sound.as_deref());
-    let should_play_custom_sound = !silent_mode && sound_path.is_some();
+    let sound = override_sound.or(app_settings.default_sound.as_deref());
 
-    if should_play_custom_sound {
-        if let Some(sound_path) = sound_path {
-            custom_sounds::play_custom_sound(PathBuf::from(sound_path));
-        }
+    Some(Notification::new(title, body, sound, silent_mode))
+}
+
+/// Why the settings keep a friend coming online from `platform` from being notified, if they do.
+pub(super) fn suppression_of(
+    app_settings: &AppSettings,
+    user_id: &str,
+    platform: &str,
+) -> Option<SuppressionReason> {
+    if !app_settings.platform_filter_of(user_id).allows(platform) {
+        return Some(SuppressionReason::Platform);
     }
+    let disabled = app_settings
+        .friend_settings_of(user_id)
+        .is_some_and(|settings| !settings.enabled);
+    disabled.then_some(SuppressionReason::Disabled)
+}
 
-    let should_silence_toast = silent_mode || should_play_custom_sound;
-    windows_os::show_notification(app, title, &body, icon_src, should_silence_toast)
-        .map_err(|err| err.to_string())
+fn message_of<'a>(app_settings: &'a AppSettings, user_id: &str) -> &'a str {
+    app_settings
+        .friend_settings_of(user_id)
+        .filter(|fs| fs.use_override)
+        .and_then(|fs| fs.message_override.as_deref())
+        .unwrap_or(&app_settings.default_message)
 }
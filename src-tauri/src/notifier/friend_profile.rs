@@ -0,0 +1,131 @@
+use crate::config::{AppSettings, ProfileSubscription, SettingsStore};
+use crate::history::{NotificationKind, Subject};
+use crate::notifier::template::{render_template, render_title, status_label, TemplateValues};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::roster::{ChangeCause, FriendRoster, ProfileChange, RosterEntry};
+use crate::vrchat_utils;
+use crate::vrchat_utils::AppResult;
+use log::error;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// The bodies of profile change notifications, rendered like the user's templates.
+const STATUS_MESSAGE: &str = "{displayName} is now {status}.";
+const STATUS_DESCRIPTION_MESSAGE: &str = "{displayName}: \"{statusDescription}\"";
+const STATUS_CLEARED_MESSAGE: &str = "{displayName} cleared their status.";
+const BIO_MESSAGE: &str = "{displayName} updated their bio.";
+const AVATAR_MESSAGE: &str = "{displayName} changed their avatar.";
+
+/// Diffs every change the pipeline makes against the last known state of the friend and notifies
+/// about the changes the user subscribed to. Online and location events carry the user as well,
+/// so a new status may arrive with any of them.
+pub fn register_roster_listeners<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &FriendRoster,
+    scope: &NotifierScope,
+) {
+    let app = app.clone();
+    let scope = scope.clone();
+    roster.subscribe(move |change| {
+        if !matches!(change.cause, ChangeCause::Pipeline(_)) {
+            return;
+        }
+        let (Some(previous), Some(current)) = (change.previous.as_ref(), change.current.as_ref())
+        else {
+            return;
+        };
+
+        for profile_change in crate::roster::profile_changes(&previous.friend, &current.friend) {
+            let app = app.clone();
+            let scope = scope.clone();
+            let current = current.clone();
+            tauri::async_runtime::spawn(async move {
+                let result = notify_profile_change(&app, &scope, current, profile_change).await;
+                if let Err(err) = result {
+                    error!("Failed to notify profile change: {}", err);
+                }
+            });
+        }
+    });
+}
+
+pub async fn notify_profile_change<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    entry: RosterEntry,
+    change: ProfileChange,
+) -> AppResult<()> {
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+
+    let Some(mut notification) =
+        compose_profile_change(&app_settings, &entry, &change, silent_mode)
+    else {
+        return Ok(());
+    };
+    let icon_url = match &change {
+        ProfileChange::Avatar { thumbnail_url } => thumbnail_url.clone(),
+        _ => vrchat_utils::resolve_friend_icon_url(&entry.friend),
+    };
+    notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+
+    let subject = Subject::of_entry(NotificationKind::ProfileChange, &entry);
+    quiet_hours::deliver(app, scope, &app_settings, notification, &[subject])
+}
+
+/// Renders the notification for a profile change, or returns `None` when the user has not
+/// subscribed to it or turned the friend off.
+pub fn compose_profile_change(
+    app_settings: &AppSettings,
+    entry: &RosterEntry,
+    change: &ProfileChange,
+    silent_mode: bool,
+) -> Option<Notification> {
+    let friend_settings = app_settings
+        .friend_settings_of(&entry.friend.id)
+        .filter(|settings| settings.enabled)?;
+    let subscribed = friend_settings
+        .profile_subscriptions
+        .iter()
+        .any(|subscription| is_subscribed(subscription, change));
+    if !subscribed {
+        return None;
+    }
+
+    let values = TemplateValues::for_entry(entry);
+    let body = match change {
+        ProfileChange::Status { to, .. } => render_template(
+            STATUS_MESSAGE,
+            &values.clone().with("status", status_label(*to)),
+        ),
+        ProfileChange::StatusDescription { to, .. } if to.is_empty() => {
+            render_template(STATUS_CLEARED_MESSAGE, &values)
+        }
+        ProfileChange::StatusDescription { to, .. } => render_template(
+            STATUS_DESCRIPTION_MESSAGE,
+            &values.clone().with("statusDescription", to.as_str()),
+        ),
+        ProfileChange::Bio { .. } => render_template(BIO_MESSAGE, &values),
+        ProfileChange::Avatar { .. } => render_template(AVATAR_MESSAGE, &values),
+    };
+
+    let override_sound = Some(friend_settings)
+        .filter(|settings| settings.use_override)
+        .and_then(|settings| settings.sound_override.as_deref());
+    let sound = override_sound.or(app_settings.default_sound.as_deref());
+
+    let title = render_title(app_settings, &entry.friend.id, &values);
+    Some(Notification::new(title, body, sound, silent_mode))
+}
+
+fn is_subscribed(subscription: &ProfileSubscription, change: &ProfileChange) -> bool {
+    match (subscription, change) {
+        (ProfileSubscription::Status { to: None }, ProfileChange::Status { .. }) => true,
+        (ProfileSubscription::Status { to: Some(wanted) }, ProfileChange::Status { to, .. }) => {
+            wanted == to
+        }
+        (ProfileSubscription::StatusDescription, ProfileChange::StatusDescription { .. }) => true,
+        (ProfileSubscription::Bio, ProfileChange::Bio { .. }) => true,
+        (ProfileSubscription::Avatar, ProfileChange::Avatar { .. }) => true,
+        _ => false,
+    }
+}
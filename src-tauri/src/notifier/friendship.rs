@@ -0,0 +1,141 @@
+use crate::config::{AppSettings, SettingsStore};
+use crate::history::{NotificationKind, Subject};
+use crate::notifier::template::{render_template, render_title, TemplateValues};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::roster::{ChangeCause, FriendRoster, RosterChange, RosterEntry, ResyncReason};
+use crate::vrchat_utils;
+use crate::vrchat_utils::AppResult;
+use crate::websocket::PipelineEvent;
+use log::{error, info};
+use tauri::{AppHandle, Manager, Runtime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Friendship {
+    Added,
+    Removed,
+}
+
+/// Notifies about friends appearing in or disappearing from the roster. Besides the pipeline
+/// events, this also covers changes found by a resync, except for the initial download.
+pub fn register_roster_listeners<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &FriendRoster,
+    scope: &NotifierScope,
+) {
+    let app = app.clone();
+    let scope = scope.clone();
+    roster.subscribe(move |change| {
+        let Some((friendship, entry)) = friendship_of(change) else {
+            return;
+        };
+
+        let app = app.clone();
+        let scope = scope.clone();
+        let entry = entry.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = notify_friendship(&app, &scope, friendship, entry).await {
+                error!("Failed to notify friendship change: {}", err);
+            }
+        });
+    });
+}
+
+/// Archives the settings of removed friends and restores them when they are added again.
+/// Only the live roster should be listened to, as this saves the settings.
+pub fn register_settings_archive<R: Runtime>(app: &AppHandle<R>, roster: &FriendRoster) {
+    let app = app.clone();
+    roster.subscribe(move |change| {
+        let Some((friendship, entry)) = friendship_of(change) else {
+            return;
+        };
+
+        let store = app.state::<SettingsStore>();
+        // A failed save has already been logged, and the change still applies in memory.
+        let _ = match friendship {
+            Friendship::Added => store.consume(|settings| {
+                if settings.restore_friend_settings(&change.user_id) {
+                    info!("Restored archived settings of friend '{}'.", change.user_id);
+                }
+            }),
+            Friendship::Removed => store.consume(|settings| {
+                settings.archive_friend_settings(&change.user_id, entry.display_name());
+            }),
+        };
+    });
+}
+
+pub async fn notify_friendship<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    friendship: Friendship,
+    entry: RosterEntry,
+) -> AppResult<()> {
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+
+    let Some(mut notification) =
+        compose_friendship(&app_settings, friendship, &entry, silent_mode)
+    else {
+        return Ok(());
+    };
+    let icon_url = vrchat_utils::resolve_friend_icon_url(&entry.friend);
+    notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+
+    let kind = match friendship {
+        Friendship::Added => NotificationKind::FriendAdded,
+        Friendship::Removed => NotificationKind::FriendRemoved,
+    };
+    let subject = Subject::of_entry(kind, &entry);
+    quiet_hours::deliver(app, scope, &app_settings, notification, &[subject])
+}
+
+/// Renders the notification for a new or removed friend, or returns `None` when it should not be shown.
+/// `entry` is the last known state of the friend, so removed friends are still named.
+pub fn compose_friendship(
+    app_settings: &AppSettings,
+    friendship: Friendship,
+    entry: &RosterEntry,
+    silent_mode: bool,
+) -> Option<Notification> {
+    let (enabled, message) = match friendship {
+        Friendship::Added => (
+            app_settings.friend_added_notifications_enabled,
+            &app_settings.default_friend_added_message,
+        ),
+        Friendship::Removed => (
+            app_settings.friend_removed_notifications_enabled,
+            &app_settings.default_friend_removed_message,
+        ),
+    };
+    if !enabled {
+        return None;
+    }
+
+    let values = TemplateValues::for_entry(entry);
+    let title = render_title(app_settings, &entry.friend.id, &values);
+    let body = render_template(message, &values);
+
+    Some(Notification::new(
+        title,
+        body,
+        app_settings.default_sound.as_deref(),
+        silent_mode,
+    ))
+}
+
+/// Only the add and delete events, or a resync of a seeded roster, change the friend list.
+/// Other events also create missing entries, which are not new friends.
+fn friendship_of(change: &RosterChange) -> Option<(Friendship, &RosterEntry)> {
+    match (
+        &change.cause,
+        change.previous.as_ref(),
+        change.current.as_ref(),
+    ) {
+        (ChangeCause::Resync(ResyncReason::Seed), _, _) => None,
+        (ChangeCause::Pipeline(PipelineEvent::FriendAdd(_)), None, Some(current))
+        | (ChangeCause::Resync(_), None, Some(current)) => Some((Friendship::Added, current)),
+        (ChangeCause::Pipeline(PipelineEvent::FriendDelete(_)), Some(previous), None)
+        | (ChangeCause::Resync(_), Some(previous), None) => Some((Friendship::Removed, previous)),
+        _ => None,
+    }
+}
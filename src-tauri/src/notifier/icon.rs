@@ -0,0 +1,27 @@
+use crate::auth;
+use crate::vrchat_utils;
+use crate::vrchat_utils::AppResult;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Downloads the icon at `url` with the session's client and returns a file URI for the toast.
+pub async fn fetch_icon_src<R: Runtime>(
+    app: &AppHandle<R>,
+    url: Option<String>,
+) -> AppResult<Option<String>> {
+    let Some(url) = url else {
+        return Ok(None);
+    };
+
+    let auth_state = app.state::<auth::AuthState>();
+    let (client, user_agent) = auth_state.with_session(|session| {
+        (
+            session.config.client.clone(),
+            session
+                .config
+                .user_agent
+                .clone()
+                .unwrap_or_else(|| "vfriends".to_string()),
+        )
+    })?;
+    Ok(vrchat_utils::fetch_user_icon_file_uri(&url, &client, &user_agent).await)
+}
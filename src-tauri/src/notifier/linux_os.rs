@@ -0,0 +1,187 @@
+use crate::notifier::backend::{Notification, NotificationBackend};
+use crate::vrchat_utils::{AppError, AppResult};
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::Result;
+
+const NOTIFICATIONS_DESTINATION: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+pub struct FreedesktopBackend {
+    app_name: String,
+    desktop_entry: String,
+}
+
+impl FreedesktopBackend {
+    pub fn new<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let config = app.config();
+        Self {
+            app_name: config
+                .product_name
+                .clone()
+                .unwrap_or_else(|| config.identifier.clone()),
+            desktop_entry: config.identifier.clone(),
+        }
+    }
+}
+
+impl NotificationBackend for FreedesktopBackend {
+    fn show(&self, notification: &Notification) -> AppResult<()> {
+        show_notification(
+            &self.app_name,
+            &self.desktop_entry,
+            &notification.title,
+            &notification.body,
+            notification.icon_src.as_deref(),
+            notification.silent,
+        )
+        .map_err(|err| AppError::notification(err.to_string()))
+    }
+
+    fn is_silent_mode(&self) -> bool {
+        is_silent_mode().unwrap_or(false)
+    }
+
+    fn preview_sound(&self) -> AppResult<()> {
+        show_notification_sound_preview(&self.app_name, &self.desktop_entry)
+            .map_err(|err| AppError::notification(err.to_string()))
+    }
+}
+
+fn show_notification(
+    app_name: &str,
+    desktop_entry: &str,
+    title: &str,
+    body: &str,
+    icon_src: Option<&str>,
+    silent: bool,
+) -> Result<()> {
+    let connection = Connection::session()?;
+
+    let mut hints = HashMap::new();
+    hints.insert("desktop-entry", Value::from(desktop_entry.to_string()));
+    if let Some(icon_src) = icon_src {
+        hints.insert("image-path", Value::from(icon_src.to_string()));
+    }
+    if silent {
+        hints.insert("suppress-sound", Value::from(true));
+    }
+
+    notify(
+        &connection,
+        app_name,
+        icon_src.unwrap_or(""),
+        title,
+        body,
+        hints,
+        Duration::from_secs(8),
+    )
+}
+
+fn show_notification_sound_preview(app_name: &str, desktop_entry: &str) -> Result<()> {
+    let connection = Connection::session()?;
+
+    let mut hints = HashMap::new();
+    hints.insert("desktop-entry", Value::from(desktop_entry.to_string()));
+    hints.insert("sound-name", Value::from("message-new-instant"));
+
+    notify(
+        &connection,
+        app_name,
+        "",
+        "",
+        "The notification sound looks like this",
+        hints,
+        Duration::from_secs(1),
+    )
+}
+
+fn is_silent_mode() -> Result<bool> {
+    let connection = Connection::session()?;
+
+    // KDE Plasma (and servers following it) expose do-not-disturb as the `Inhibited` property.
+    if let Some(inhibited) = notifications_inhibited(&connection) {
+        return Ok(inhibited);
+    }
+
+    // GNOME keeps it in gsettings instead: banners are hidden while do-not-disturb is on.
+    Ok(gnome_banners_hidden().unwrap_or(false))
+}
+
+fn notify(
+    connection: &Connection,
+    app_name: &str,
+    app_icon: &str,
+    title: &str,
+    body: &str,
+    hints: HashMap<&str, Value<'_>>,
+    expire_after: Duration,
+) -> Result<()> {
+    let actions: Vec<&str> = Vec::new();
+    let expire_timeout = expire_after.as_millis() as i32;
+
+    let reply = connection.call_method(
+        Some(NOTIFICATIONS_DESTINATION),
+        NOTIFICATIONS_PATH,
+        Some(NOTIFICATIONS_INTERFACE),
+        "Notify",
+        &(
+            app_name,
+            0u32,
+            app_icon,
+            title,
+            escape_markup(body),
+            actions,
+            hints,
+            expire_timeout,
+        ),
+    )?;
+
+    let _notification_id: u32 = reply.body().deserialize()?;
+    Ok(())
+}
+
+fn notifications_inhibited(connection: &Connection) -> Option<bool> {
+    let reply = connection
+        .call_method(
+            Some(NOTIFICATIONS_DESTINATION),
+            NOTIFICATIONS_PATH,
+            Some(PROPERTIES_INTERFACE),
+            "Get",
+            &(NOTIFICATIONS_INTERFACE, "Inhibited"),
+        )
+        .ok()?;
+
+    let value: OwnedValue = reply.body().deserialize().ok()?;
+    bool::try_from(value).ok()
+}
+
+fn gnome_banners_hidden() -> Option<bool> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "true" => Some(false),
+        "false" => Some(true),
+        _ => None,
+    }
+}
+
+/// Notification servers may interpret the body as markup, so display names must not be able to inject tags.
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,161 @@
+use crate::config::{AppSettings, MissedOnlineMode, SettingsStore};
+use crate::history::{NotificationKind, Subject};
+use crate::notifier::friend_online::{compose_online, suppression_of};
+use crate::notifier::online_batch::join_names;
+use crate::notifier::template::{render_template, TemplateValues};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::roster::{FriendRoster, ResyncReason, RosterChange, RosterEntry};
+use crate::vrchat_utils;
+use crate::vrchat_utils::AppResult;
+use log::error;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// The summary of friends that came online while disconnected. `{names}` lists them.
+const BATCH_TITLE: &str = "While you were disconnected";
+const BATCH_MESSAGE: &str = "{names} came online.";
+
+/// Online events sent while the pipeline was down are lost. The roster still holds the presence
+/// from before the disconnect, so the resync after reconnecting reveals who came online meanwhile.
+/// Friends that also went offline again in between look unchanged and are ignored.
+pub fn register_roster_listeners<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &FriendRoster,
+    scope: &NotifierScope,
+) {
+    let app = app.clone();
+    let scope = scope.clone();
+    roster.subscribe_resync(move |reason, changes| {
+        if reason != ResyncReason::Reconnect {
+            return;
+        }
+        let came_online = came_online(changes);
+        if came_online.is_empty() {
+            return;
+        }
+
+        let app = app.clone();
+        let scope = scope.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = notify_missed_online(&app, &scope, came_online).await {
+                error!("Failed to notify friends that came online while disconnected: {}", err);
+            }
+        });
+    });
+}
+
+pub async fn notify_missed_online<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    came_online: Vec<RosterEntry>,
+) -> AppResult<()> {
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+
+    // Friends that bypass quiet hours are announced apart, so the rest can be held back.
+    for group in quiet_hours::partition(&app_settings, came_online, |entry| &entry.friend.id) {
+        if app_settings.missed_online_notifications != MissedOnlineMode::Off {
+            record_filtered(app, scope, &app_settings, &group);
+        }
+        let notifications: Vec<_> = compose_missed_online(&app_settings, &group, silent_mode)
+            .into_iter()
+            .map(|(notification, included)| {
+                let icon_url = match included.as_slice() {
+                    [entry] => vrchat_utils::resolve_friend_icon_url(&entry.friend),
+                    _ => None,
+                };
+                let subjects: Vec<_> = included
+                    .into_iter()
+                    .map(|entry| Subject::of_entry(NotificationKind::Online, entry))
+                    .collect();
+                (notification, icon_url, subjects)
+            })
+            .collect();
+
+        for (mut notification, icon_url, subjects) in notifications {
+            notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+            quiet_hours::deliver(app, scope, &app_settings, notification, &subjects)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders the notifications for friends that came online while disconnected, each with the
+/// friends it covers.
+pub fn compose_missed_online<'a>(
+    app_settings: &AppSettings,
+    came_online: &'a [RosterEntry],
+    silent_mode: bool,
+) -> Vec<(Notification, Vec<&'a RosterEntry>)> {
+    let individual = came_online.iter().filter_map(|entry| {
+        let values = TemplateValues::for_entry(entry).with_location(entry.location(), None);
+        compose_online(
+            app_settings,
+            &entry.friend.id,
+            &entry.friend.platform,
+            &values,
+            silent_mode,
+        )
+        .map(|notification| (notification, vec![entry]))
+    });
+
+    match app_settings.missed_online_notifications {
+        MissedOnlineMode::Off => Vec::new(),
+        MissedOnlineMode::Individual => individual.collect(),
+        MissedOnlineMode::Batched => {
+            let included: Vec<_> = individual.flat_map(|(_, entries)| entries).collect();
+            compose_batch(app_settings, included, silent_mode)
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+fn compose_batch<'a>(
+    app_settings: &AppSettings,
+    entries: Vec<&'a RosterEntry>,
+    silent_mode: bool,
+) -> Option<(Notification, Vec<&'a RosterEntry>)> {
+    if entries.is_empty() {
+        return None;
+    }
+    let names: Vec<&str> = entries.iter().map(|entry| entry.display_name()).collect();
+
+    let values = TemplateValues::default().with("names", join_names(&names));
+    let notification = Notification::new(
+        render_template(BATCH_TITLE, &values),
+        render_template(BATCH_MESSAGE, &values),
+        app_settings.default_sound.as_deref(),
+        silent_mode,
+    );
+    Some((notification, entries))
+}
+
+/// Records the friends that the settings keep from being notified.
+fn record_filtered<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    app_settings: &AppSettings,
+    came_online: &[RosterEntry],
+) {
+    for entry in came_online {
+        let reason = suppression_of(app_settings, &entry.friend.id, &entry.friend.platform);
+        if let Some(reason) = reason {
+            let subject = Subject::of_entry(NotificationKind::Online, entry);
+            scope.record_suppressed(app, &[subject], reason);
+        }
+    }
+}
+
+fn came_online(changes: &[RosterChange]) -> Vec<RosterEntry> {
+    changes
+        .iter()
+        .filter(|change| {
+            change
+                .previous
+                .as_ref()
+                .is_some_and(|previous| !previous.is_online())
+        })
+        .filter_map(|change| change.current.clone())
+        .filter(RosterEntry::is_online)
+        .collect()
+}
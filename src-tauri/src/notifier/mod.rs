@@ -1,9 +1,31 @@
+#[cfg(target_os = "windows")]
 mod aumid;
+mod backend;
 mod custom_sounds;
+mod flap_guard;
+mod friend_location;
+mod friend_offline;
 mod friend_online;
+mod friend_profile;
+mod friendship;
+mod icon;
+#[cfg(target_os = "linux")]
+mod linux_os;
+mod missed_online;
+mod online_batch;
+mod quiet_hours;
+#[cfg(any(test, feature = "test-support"))]
+mod recording;
+mod scope;
+mod session;
+mod template;
+#[cfg(target_os = "windows")]
 mod windows_os;
 
 use crate::config::AppSettings;
+use crate::roster::FriendRoster;
+use crate::websocket::PipelineDispatcher;
+pub use backend::{Notification, NotificationBackend, NotifierState};
 pub use custom_sounds::{
     play_custom_sound,
     sound_duration_ms,
@@ -11,15 +33,50 @@ pub use custom_sounds::{
     store_custom_sound_from_path,
     validate_sound_path,
 };
-pub use friend_online::notify_friend_online;
+pub use flap_guard::{FlapGuard, OnlineVerdict};
+pub use friend_location::{compose_friend_location, notify_friend_location};
+pub use friend_offline::{compose_friend_offline, notify_friend_offline};
+pub use friend_online::{compose_friend_online, notify_friend_online};
+pub use friend_profile::{compose_profile_change, notify_profile_change};
+pub use friendship::{
+    compose_friendship, notify_friendship, register_settings_archive, Friendship,
+};
+pub use missed_online::{compose_missed_online, notify_missed_online};
+pub use online_batch::{compose_online_batch, join_names, notify_online_batch};
+pub use quiet_hours::{compose_digest, partition};
+#[cfg(any(test, feature = "test-support"))]
+pub use recording::{RecordedOutput, RecordingBackend};
+pub use scope::NotifierScope;
+pub use session::notify_session_expired;
+pub use template::{render_template, Template, TemplateError, TemplateValues, PLACEHOLDERS};
 use std::collections::HashSet;
-use std::path::PathBuf;
 use std::path::Path;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager, Runtime};
+
+pub fn register_pipeline_handlers<R: Runtime>(
+    app: &AppHandle<R>,
+    dispatcher: &PipelineDispatcher,
+    scope: &NotifierScope,
+) {
+    friend_online::register_pipeline_handlers(app, dispatcher, scope);
+}
 
-pub async fn preview_sound(app: &AppHandle, sound: Option<String>) -> Option<u64> {
+pub fn register_roster_listeners<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &FriendRoster,
+    scope: &NotifierScope,
+) {
+    friend_offline::register_roster_listeners(app, roster, scope);
+    friend_location::register_roster_listeners(app, roster, scope);
+    friend_profile::register_roster_listeners(app, roster, scope);
+    friendship::register_roster_listeners(app, roster, scope);
+    missed_online::register_roster_listeners(app, roster, scope);
+}
+
+pub async fn preview_sound<R: Runtime>(app: &AppHandle<R>, sound: Option<String>) -> Option<u64> {
+    let notifier = app.state::<NotifierState>();
     let Some(sound_path) = sound else {
-        let _ = windows_os::show_notification_sound_preview(app);
+        let _ = notifier.backend().preview_sound();
         return None;
     };
     let trimmed_path = sound_path.trim();
@@ -27,7 +84,7 @@ pub async fn preview_sound(app: &AppHandle, sound: Option<String>) -> Option<u64
         return None;
     }
     let duration = sound_duration_ms(Path::new(trimmed_path)).ok();
-    play_custom_sound(PathBuf::from(trimmed_path));
+    notifier.backend().play_sound(Path::new(trimmed_path));
     duration
 }
 
@@ -67,8 +124,16 @@ fn collect_referenced_sound_files(app: &AppHandle, settings: &AppSettings) -> Ha
     };
 
     push_path(&settings.default_sound);
+    push_path(&settings.default_offline_sound);
     for friend_settings in settings.friend_settings.values() {
         push_path(&friend_settings.sound_override);
+        push_path(&friend_settings.offline_sound_override);
+        push_path(&friend_settings.location_sound_override);
+    }
+    for archived in settings.archived_friend_settings.values() {
+        push_path(&archived.settings.sound_override);
+        push_path(&archived.settings.offline_sound_override);
+        push_path(&archived.settings.location_sound_override);
     }
     files
 }
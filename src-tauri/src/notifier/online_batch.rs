@@ -0,0 +1,173 @@
+use crate::config::{AppSettings, SettingsStore};
+use crate::notifier::friend_online::{
+    compose_friend_online, notify_friend_online, record_filtered, resolve_world_names, subject_of,
+};
+use crate::notifier::template::{render_template, TemplateValues};
+use crate::notifier::{icon, quiet_hours, Notification, NotifierScope, NotifierState};
+use crate::vrchat_utils;
+use crate::vrchat_utils::AppResult;
+use crate::websocket::FriendOnlineEvent;
+use log::error;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Names listed in a batched notification before the rest is only counted.
+const BATCH_NAME_LIMIT: usize = 3;
+/// The summary of friends that came online together. `{names}` lists them.
+const BATCH_TITLE: &str = "Friends online";
+const BATCH_MESSAGE: &str = "{names} are now online!";
+
+/// Collects online events that arrive within the batching window, so logging in at peak time
+/// shows one summary instead of a toast and a sound per friend.
+#[derive(Default)]
+pub(super) struct OnlineBatcher {
+    pending: Mutex<Option<Vec<FriendOnlineEvent>>>,
+}
+
+impl OnlineBatcher {
+    /// Queues the event, or notifies right away when it should not be batched.
+    pub fn push<R: Runtime>(
+        self: &Arc<Self>,
+        app: &AppHandle<R>,
+        scope: &NotifierScope,
+        event: FriendOnlineEvent,
+    ) {
+        let app_settings = app.state::<SettingsStore>().snapshot();
+        if !is_batched(&app_settings, &event) {
+            let app = app.clone();
+            let scope = scope.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = notify_friend_online(&app, &scope, event).await {
+                    error!("Failed to notify friend online: {}", err);
+                }
+            });
+            return;
+        }
+
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(events) = pending.as_mut() {
+            events.push(event);
+            return;
+        }
+        *pending = Some(vec![event]);
+
+        // The window starts with the first event, so a steady stream still gets notified.
+        let window = Duration::from_millis(app_settings.online_batch_window_ms);
+        let batcher = Arc::clone(self);
+        let app = app.clone();
+        let scope = scope.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(window).await;
+            let events = batcher
+                .pending
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .take()
+                .unwrap_or_default();
+            if let Err(err) = notify_online_batch(&app, &scope, events).await {
+                error!("Failed to notify friends online: {}", err);
+            }
+        });
+    }
+}
+
+fn is_batched(app_settings: &AppSettings, event: &FriendOnlineEvent) -> bool {
+    if app_settings.online_batch_window_ms == 0 {
+        return false;
+    }
+    let has_override = app_settings
+        .friend_settings_of(&event.user_id)
+        .is_some_and(|settings| settings.use_override);
+    !(has_override && app_settings.online_batch_keeps_overrides)
+}
+
+pub async fn notify_online_batch<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    events: Vec<FriendOnlineEvent>,
+) -> AppResult<()> {
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+
+    let world_names = resolve_world_names(app, &app_settings, &events).await;
+
+    // Friends that bypass quiet hours get their own notification, so the rest can be held back.
+    for group in quiet_hours::partition(&app_settings, events, |event| &event.user_id) {
+        record_filtered(app, scope, &app_settings, &group);
+        let Some((mut notification, included)) =
+            compose_online_batch(&app_settings, &group, &world_names, silent_mode)
+        else {
+            continue;
+        };
+        let icon_url = match included.as_slice() {
+            [event] => vrchat_utils::resolve_user_icon_url(&event.user),
+            _ => None,
+        };
+        notification.icon_src = icon::fetch_icon_src(app, icon_url).await?;
+
+        let subjects: Vec<_> = included.into_iter().map(subject_of).collect();
+        quiet_hours::deliver(app, scope, &app_settings, notification, &subjects)?;
+    }
+    Ok(())
+}
+
+/// Renders one notification for the friends that came online within a batching window, with
+/// the events it covers. A single friend gets the usual online notification.
+pub fn compose_online_batch<'a>(
+    app_settings: &AppSettings,
+    events: &'a [FriendOnlineEvent],
+    world_names: &HashMap<String, String>,
+    silent_mode: bool,
+) -> Option<(Notification, Vec<&'a FriendOnlineEvent>)> {
+    let mut included: Vec<_> = events
+        .iter()
+        .filter_map(|event| {
+            compose_friend_online(app_settings, event, world_names, silent_mode)
+                .map(|notification| (notification, event))
+        })
+        .collect();
+
+    if included.len() <= 1 {
+        return included
+            .pop()
+            .map(|(notification, event)| (notification, vec![event]));
+    }
+
+    let included: Vec<&FriendOnlineEvent> = included.into_iter().map(|(_, event)| event).collect();
+    let names: Vec<&str> = included
+        .iter()
+        .map(|event| event.user.display_name.as_str())
+        .collect();
+    let values = TemplateValues::default().with("names", join_names(&names));
+    let notification = Notification::new(
+        render_template(BATCH_TITLE, &values),
+        render_template(BATCH_MESSAGE, &values),
+        app_settings.default_sound.as_deref(),
+        silent_mode,
+    );
+    Some((notification, included))
+}
+
+/// Lists up to `BATCH_NAME_LIMIT` names, e.g. "Alice, Bob, Carol and 4 others".
+pub fn join_names(names: &[&str]) -> String {
+    match names.len() {
+        0 => String::new(),
+        1 => names[0].to_string(),
+        count if count <= BATCH_NAME_LIMIT => {
+            format!("{} and {}", names[..count - 1].join(", "), names[count - 1])
+        }
+        count => {
+            let others = count - BATCH_NAME_LIMIT;
+            format!(
+                "{} and {others} {}",
+                names[..BATCH_NAME_LIMIT].join(", "),
+                if others == 1 { "other" } else { "others" }
+            )
+        }
+    }
+}
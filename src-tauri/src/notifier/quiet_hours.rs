@@ -0,0 +1,164 @@
+use crate::config::{AppSettings, QuietMode, SettingsStore};
+use crate::history::{Decision, Subject, SuppressionReason};
+use crate::notifier::template::{render_template, TemplateValues};
+use crate::notifier::{Notification, NotifierScope, NotifierState};
+use crate::vrchat_utils::AppResult;
+use chrono::Local;
+use log::warn;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// How often a pending digest checks whether quiet hours are over.
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Notifications listed in a digest before the rest is only counted.
+const DIGEST_LINE_LIMIT: usize = 5;
+const DIGEST_TITLE: &str = "During quiet hours";
+/// The last line of a digest with more notifications than it lists.
+const DIGEST_MORE_LINE: &str = "and {count} more";
+
+/// Holds the notifications deferred during quiet hours in digest mode, with what they are about.
+#[derive(Default)]
+pub struct QuietHoursState {
+    digest: Mutex<Vec<(Notification, Vec<Subject>)>>,
+}
+
+impl QuietHoursState {
+    /// Adds the notification to the digest. Returns whether it is the first one, so the digest
+    /// still has to be scheduled.
+    fn defer(&self, notification: Notification, subjects: &[Subject]) -> bool {
+        let mut digest = self
+            .digest
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        digest.push((notification, subjects.to_vec()));
+        digest.len() == 1
+    }
+
+    fn take(&self) -> Vec<(Notification, Vec<Subject>)> {
+        std::mem::take(
+            &mut *self
+                .digest
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+}
+
+/// Delivers a notification about `subjects`, unless quiet hours hold it back, and records what
+/// became of it. Any friend bypassing quiet hours lets the whole notification through, so groups
+/// of friends should be [`partition`]ed first.
+pub(super) fn deliver<R: Runtime>(
+    app: &AppHandle<R>,
+    scope: &NotifierScope,
+    app_settings: &AppSettings,
+    mut notification: Notification,
+    subjects: &[Subject],
+) -> AppResult<()> {
+    let is_quiet = app_settings
+        .quiet_hours
+        .is_quiet_at(Local::now().naive_local())
+        && !subjects
+            .iter()
+            .any(|subject| app_settings.bypasses_quiet_hours(&subject.friend_id));
+    if !is_quiet {
+        app.state::<NotifierState>().deliver(&notification)?;
+        scope.record_shown(app, subjects);
+        return Ok(());
+    }
+
+    match app_settings.quiet_hours.mode {
+        QuietMode::Silent => {
+            notification.sound = None;
+            notification.silent = true;
+            app.state::<NotifierState>().deliver(&notification)?;
+            scope.record_shown(app, subjects);
+        }
+        QuietMode::Drop => {
+            scope.record_suppressed(app, subjects, SuppressionReason::QuietHours);
+        }
+        QuietMode::Digest => {
+            if scope.quiet_hours().defer(notification, subjects) {
+                let app = app.clone();
+                let scope = scope.clone();
+                tauri::async_runtime::spawn(async move {
+                    flush_digest_when_over(&app, &scope).await;
+                });
+            }
+            scope.record(app, subjects, Decision::Deferred, None);
+        }
+    }
+    Ok(())
+}
+
+/// Splits `items` into the ones to show right away and the ones quiet hours hold back.
+pub fn partition<T>(
+    app_settings: &AppSettings,
+    items: Vec<T>,
+    friend_id: impl Fn(&T) -> &str,
+) -> [Vec<T>; 2] {
+    let now = Local::now().naive_local();
+    let (held, shown) = items
+        .into_iter()
+        .partition(|item| app_settings.is_quiet_for(friend_id(item), now));
+    [shown, held]
+}
+
+async fn flush_digest_when_over<R: Runtime>(app: &AppHandle<R>, scope: &NotifierScope) {
+    loop {
+        tokio::time::sleep(DIGEST_CHECK_INTERVAL).await;
+        let app_settings = app.state::<SettingsStore>().snapshot();
+        if !app_settings
+            .quiet_hours
+            .is_quiet_at(Local::now().naive_local())
+        {
+            break;
+        }
+    }
+
+    let app_settings = app.state::<SettingsStore>().snapshot();
+    let silent_mode = app.state::<NotifierState>().backend().is_silent_mode();
+    let (deferred, subjects): (Vec<_>, Vec<_>) = scope.quiet_hours().take().into_iter().unzip();
+    let Some(notification) = compose_digest(&app_settings, &deferred, silent_mode) else {
+        return;
+    };
+    if let Err(err) = app.state::<NotifierState>().deliver(&notification) {
+        warn!("Failed to show the quiet hours digest: {err}");
+        return;
+    }
+    scope.record_shown(app, &subjects.concat());
+}
+
+/// Renders the summary of the notifications deferred during quiet hours. A single notification
+/// is shown as it was.
+pub fn compose_digest(
+    app_settings: &AppSettings,
+    deferred: &[Notification],
+    silent_mode: bool,
+) -> Option<Notification> {
+    match deferred {
+        [] => None,
+        [notification] => Some(notification.clone()),
+        _ => {
+            let mut lines: Vec<&str> = deferred
+                .iter()
+                .take(DIGEST_LINE_LIMIT)
+                .map(|notification| notification.body.as_str())
+                .collect();
+            let more = deferred.len().saturating_sub(DIGEST_LINE_LIMIT);
+            let more_line = render_template(
+                DIGEST_MORE_LINE,
+                &TemplateValues::default().with("count", more.to_string()),
+            );
+            if more > 0 {
+                lines.push(&more_line);
+            }
+            Some(Notification::new(
+                render_template(DIGEST_TITLE, &TemplateValues::default()),
+                lines.join("\n"),
+                app_settings.default_sound.as_deref(),
+                silent_mode,
+            ))
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::notifier::backend::{Notification, NotificationBackend};
+use crate::vrchat_utils::AppResult;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedOutput {
+    Shown(Notification),
+    SoundPlayed(PathBuf),
+    SoundPreviewed,
+}
+
+/// Keeps everything the notifier asks for in memory instead of displaying it.
+#[derive(Default)]
+pub struct RecordingBackend {
+    silent_mode: AtomicBool,
+    outputs: Mutex<Vec<RecordedOutput>>,
+}
+
+#[cfg(feature = "test-support")]
+impl RecordingBackend {
+    pub fn set_silent_mode(&self, silent_mode: bool) {
+        self.silent_mode.store(silent_mode, Ordering::SeqCst);
+    }
+
+    pub fn outputs(&self) -> Vec<RecordedOutput> {
+        self.lock().clone()
+    }
+
+    pub fn shown(&self) -> Vec<Notification> {
+        self.lock()
+            .iter()
+            .filter_map(|output| match output {
+                RecordedOutput::Shown(notification) => Some(notification.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+impl RecordingBackend {
+    fn record(&self, output: RecordedOutput) {
+        self.lock().push(output);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<RecordedOutput>> {
+        self.outputs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl NotificationBackend for RecordingBackend {
+    fn show(&self, notification: &Notification) -> AppResult<()> {
+        self.record(RecordedOutput::Shown(notification.clone()));
+        Ok(())
+    }
+
+    fn is_silent_mode(&self) -> bool {
+        self.silent_mode.load(Ordering::SeqCst)
+    }
+
+    fn preview_sound(&self) -> AppResult<()> {
+        self.record(RecordedOutput::SoundPreviewed);
+        Ok(())
+    }
+
+    fn play_sound(&self, path: &Path) {
+        self.record(RecordedOutput::SoundPlayed(path.to_path_buf()));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::history::{self, Decision, Subject, SuppressionReason};
+use crate::notifier::flap_guard::FlapGuard;
+use crate::notifier::quiet_hours::QuietHoursState;
+use std::sync::Arc;
+use tauri::{AppHandle, Runtime};
+
+/// What the notifier decides with and whether it records its decisions. The app manages the live
+/// scope. A replay brings a detached one, so it neither changes live decisions nor the history.
+#[derive(Clone)]
+pub struct NotifierScope {
+    flap_guard: Arc<FlapGuard>,
+    quiet_hours: Arc<QuietHoursState>,
+    records_history: bool,
+}
+
+impl NotifierScope {
+    pub fn live() -> Self {
+        Self {
+            flap_guard: Arc::default(),
+            quiet_hours: Arc::default(),
+            records_history: true,
+        }
+    }
+
+    /// Starts without any recent presence or deferred notifications, and records nothing.
+    pub fn detached() -> Self {
+        Self {
+            records_history: false,
+            ..Self::live()
+        }
+    }
+
+    pub fn flap_guard(&self) -> &FlapGuard {
+        &self.flap_guard
+    }
+
+    pub(super) fn quiet_hours(&self) -> &QuietHoursState {
+        &self.quiet_hours
+    }
+
+    pub fn record<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        subjects: &[Subject],
+        decision: Decision,
+        reason: Option<SuppressionReason>,
+    ) {
+        if self.records_history {
+            history::record(app, subjects, decision, reason);
+        }
+    }
+
+    pub fn record_shown<R: Runtime>(&self, app: &AppHandle<R>, subjects: &[Subject]) {
+        if self.records_history {
+            history::record_shown(app, subjects);
+        }
+    }
+
+    pub fn record_suppressed<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        subjects: &[Subject],
+        reason: SuppressionReason,
+    ) {
+        if self.records_history {
+            history::record_suppressed(app, subjects, reason);
+        }
+    }
+}
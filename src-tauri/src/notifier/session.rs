@@ -0,0 +1,21 @@
+use crate::notifier::{Notification, NotifierState};
+use log::warn;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Tells the user that notifications have stopped until they log in again.
+pub fn notify_session_expired<R: Runtime>(app: &AppHandle<R>) {
+    let Some(notifier) = app.try_state::<NotifierState>() else {
+        return;
+    };
+    let notification = Notification::new(
+        String::from("vfriends"),
+        String::from(
+            "Your VRChat session has expired. Log in again to keep receiving notifications.",
+        ),
+        None,
+        notifier.backend().is_silent_mode(),
+    );
+    if let Err(err) = notifier.deliver(&notification) {
+        warn!("Failed to notify the expired session: {err}");
+    }
+}
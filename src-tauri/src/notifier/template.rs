@@ -0,0 +1,347 @@
+use crate::config::AppSettings;
+use crate::roster::RosterEntry;
+use crate::vrchat_utils::{Instance, Location};
+use chrono::Local;
+use log::warn;
+use std::collections::HashMap;
+use std::fmt;
+use vrchatapi::models;
+
+/// Every placeholder a template may use. Not every notification provides all of them; missing
+/// values render as empty text.
+pub const PLACEHOLDERS: &[&str] = &[
+    "displayName",
+    "status",
+    "statusDescription",
+    "platform",
+    "worldName",
+    "instanceType",
+    "region",
+    "time",
+    "onlineFor",
+    "world",
+    "names",
+    "count",
+];
+
+/// The display name placeholder from before templates, which stays valid in saved messages.
+const LEGACY_DISPLAY_NAME: &str = "%s";
+
+/// A notification title or message, e.g. `{displayName} joined{#worldName} {worldName}{/worldName}`.
+///
+/// - `{name}` is replaced with the value of the placeholder.
+/// - `{name|text}` falls back to `text` when the value is empty.
+/// - `{#name}...{/name}` is only shown when the value is not empty.
+/// - `{{` and `}}` are literal braces.
+/// - `%s` is `{displayName}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Placeholder {
+        name: String,
+        fallback: Option<String>,
+    },
+    Section {
+        name: String,
+        segments: Vec<Segment>,
+    },
+}
+
+/// Why a template could not be parsed. `position` counts characters from the start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// The values of the placeholders for one notification.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateValues {
+    values: HashMap<&'static str, String>,
+}
+
+impl TemplateValues {
+    /// The values every notification about a friend has.
+    pub fn for_friend(
+        display_name: &str,
+        status: models::UserStatus,
+        status_description: &str,
+        platform: &str,
+    ) -> Self {
+        Self::default()
+            .with("displayName", display_name)
+            .with("status", status_label(status))
+            .with("statusDescription", status_description)
+            .with("platform", platform_label(platform))
+            .with("time", Local::now().format("%H:%M").to_string())
+    }
+
+    pub fn for_entry(entry: &RosterEntry) -> Self {
+        Self::for_friend(
+            entry.display_name(),
+            entry.friend.status,
+            &entry.friend.status_description,
+            &entry.friend.platform,
+        )
+    }
+
+    /// Adds the values describing `location`. `world_name` is `None` when it has not been
+    /// looked up.
+    pub fn with_location(self, location: &str, world_name: Option<&str>) -> Self {
+        let location = Location::parse(location).ok();
+        let instance = location.as_ref().and_then(Location::instance);
+        let instance_type = instance.map(|instance| instance.access_type().label());
+        let world = match (instance_type, world_name) {
+            (Some(instance_type), Some(world_name)) => format!("{world_name} ({instance_type})"),
+            (Some(_), None) => String::from("another world"),
+            _ if location == Some(Location::Private) => String::from("a private world"),
+            _ => String::new(),
+        };
+        self.with("worldName", world_name.unwrap_or_default())
+            .with("instanceType", instance_type.unwrap_or_default())
+            .with("region", instance.map(Instance::region).unwrap_or_default())
+            .with("world", world)
+    }
+
+    pub fn with(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.values.insert(name, value.into());
+        self
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut open_sections: Vec<(String, usize, Vec<Segment>)> = Vec::new();
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut index = 0;
+
+        while index < chars.len() {
+            let next = chars.get(index + 1).copied();
+            if chars[index] == '%' && next == Some('s') {
+                flush_text(&mut text, &mut segments);
+                segments.push(Segment::Placeholder {
+                    name: String::from("displayName"),
+                    fallback: None,
+                });
+                index += 2;
+                continue;
+            }
+
+            match chars[index] {
+                '{' if next == Some('{') => {
+                    text.push('{');
+                    index += 2;
+                }
+                '}' if next == Some('}') => {
+                    text.push('}');
+                    index += 2;
+                }
+                '}' => {
+                    return Err(TemplateError {
+                        position: index,
+                        message: String::from("Unexpected '}'. Write '}}' for a literal brace."),
+                    });
+                }
+                '{' => {
+                    let Some(length) = chars[index + 1..].iter().position(|&c| c == '}') else {
+                        return Err(TemplateError {
+                            position: index,
+                            message: String::from(
+                                "'{' is never closed. Write '{{' for a literal brace.",
+                            ),
+                        });
+                    };
+                    let tag: String = chars[index + 1..index + 1 + length].iter().collect();
+                    flush_text(&mut text, &mut segments);
+
+                    if let Some(name) = tag.strip_prefix('#') {
+                        validate_name(name, index + 2)?;
+                        let outer = std::mem::take(&mut segments);
+                        open_sections.push((name.to_string(), index, outer));
+                    } else if let Some(name) = tag.strip_prefix('/') {
+                        let Some((open_name, _, outer)) = open_sections
+                            .pop()
+                            .filter(|(open_name, _, _)| open_name == name)
+                        else {
+                            return Err(TemplateError {
+                                position: index,
+                                message: format!("'{{/{name}}}' does not close an open section."),
+                            });
+                        };
+                        let inner = std::mem::replace(&mut segments, outer);
+                        segments.push(Segment::Section {
+                            name: open_name,
+                            segments: inner,
+                        });
+                    } else {
+                        let (name, fallback) = match tag.split_once('|') {
+                            Some((name, fallback)) => (name, Some(fallback.to_string())),
+                            None => (tag.as_str(), None),
+                        };
+                        validate_name(name, index + 1)?;
+                        segments.push(Segment::Placeholder {
+                            name: name.to_string(),
+                            fallback,
+                        });
+                    }
+                    index += length + 2;
+                }
+                c => {
+                    text.push(c);
+                    index += 1;
+                }
+            }
+        }
+
+        if let Some((name, position, _)) = open_sections.pop() {
+            return Err(TemplateError {
+                position,
+                message: format!("'{{#{name}}}' is never closed with '{{/{name}}}'."),
+            });
+        }
+        flush_text(&mut text, &mut segments);
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, values: &TemplateValues) -> String {
+        let mut output = String::new();
+        render_segments(&self.segments, values, &mut output);
+        output
+    }
+
+    /// Whether the template shows `name` anywhere, so values that are expensive to look up can
+    /// be skipped otherwise.
+    pub fn uses(&self, name: &str) -> bool {
+        uses(&self.segments, name)
+    }
+}
+
+/// Renders a saved template. Templates are validated when they are saved, so one that does not
+/// parse was edited by hand and is shown as it is, apart from `%s`.
+pub fn render_template(source: &str, values: &TemplateValues) -> String {
+    match Template::parse(source) {
+        Ok(template) => template.render(values),
+        Err(err) => {
+            warn!("Failed to parse the template '{source}': {err}");
+            source.replace(
+                LEGACY_DISPLAY_NAME,
+                values.get("displayName").unwrap_or_default(),
+            )
+        }
+    }
+}
+
+/// Whether the saved template `source` uses `name`.
+pub fn template_uses(source: &str, name: &str) -> bool {
+    Template::parse(source).is_ok_and(|template| template.uses(name))
+}
+
+/// Renders the title of a notification about the friend, which shows the display name unless
+/// the user changed it.
+pub fn render_title(
+    app_settings: &AppSettings,
+    friend_id: &str,
+    values: &TemplateValues,
+) -> String {
+    render_template(title_of(app_settings, friend_id), values)
+}
+
+pub(super) fn title_of<'a>(app_settings: &'a AppSettings, friend_id: &str) -> &'a str {
+    app_settings
+        .friend_settings_of(friend_id)
+        .filter(|settings| settings.use_override)
+        .and_then(|settings| settings.title_override.as_deref())
+        .unwrap_or(&app_settings.default_title)
+}
+
+pub(super) fn status_label(status: models::UserStatus) -> &'static str {
+    match status {
+        models::UserStatus::Active => "Online",
+        models::UserStatus::JoinMe => "Join Me",
+        models::UserStatus::AskMe => "Ask Me",
+        models::UserStatus::Busy => "Busy",
+        models::UserStatus::Offline => "Offline",
+    }
+}
+
+fn platform_label(platform: &str) -> &str {
+    match platform {
+        "standalonewindows" => "PC",
+        "android" => "Android",
+        "ios" => "iOS",
+        "web" => "Web",
+        platform => platform,
+    }
+}
+
+fn validate_name(name: &str, position: usize) -> Result<(), TemplateError> {
+    if PLACEHOLDERS.contains(&name) {
+        return Ok(());
+    }
+    let message = if name.is_empty() {
+        String::from("A placeholder name is missing.")
+    } else {
+        format!(
+            "'{name}' is not a placeholder. Use one of {}.",
+            PLACEHOLDERS.join(", ")
+        )
+    };
+    Err(TemplateError { position, message })
+}
+
+fn flush_text(text: &mut String, segments: &mut Vec<Segment>) {
+    if !text.is_empty() {
+        segments.push(Segment::Text(std::mem::take(text)));
+    }
+}
+
+fn render_segments(segments: &[Segment], values: &TemplateValues, output: &mut String) {
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Placeholder { name, fallback } => {
+                if let Some(value) = values.get(name).or(fallback.as_deref()) {
+                    output.push_str(value);
+                }
+            }
+            Segment::Section { name, segments } => {
+                if values.get(name).is_some() {
+                    render_segments(segments, values, output);
+                }
+            }
+        }
+    }
+}
+
+fn uses(segments: &[Segment], name: &str) -> bool {
+    segments.iter().any(|segment| match segment {
+        Segment::Text(_) => false,
+        Segment::Placeholder { name: used, .. } => used == name,
+        Segment::Section {
+            name: used,
+            segments,
+        } => used == name || uses(segments, name),
+    })
+}
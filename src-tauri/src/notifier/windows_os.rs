@@ -1,5 +1,8 @@
+use crate::notifier::aumid::ensure_app_user_model_id;
+use crate::notifier::backend::{Notification, NotificationBackend};
+use crate::vrchat_utils::{AppError, AppResult};
 use std::time::Duration;
-use tauri::AppHandle;
+use tauri::{AppHandle, Runtime};
 use windows::core::{Interface, Result, HSTRING};
 use windows::Data::Xml::Dom::{XmlDocument, XmlElement};
 use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
@@ -8,11 +11,48 @@ use windows::UI::Notifications::{
 };
 use windows::UI::Notifications::{ToastNotificationMode, ToastNotifier};
 
-pub fn show_notification(
-    app: &AppHandle,
+pub struct WindowsToastBackend {
+    app_id: String,
+}
+
+impl WindowsToastBackend {
+    pub fn new<R: Runtime>(app: &AppHandle<R>) -> Self {
+        if let Err(err) = ensure_app_user_model_id(app) {
+            log::warn!("Failed to prepare AppUserModelID: {err}");
+        }
+        Self {
+            app_id: app.config().identifier.clone(),
+        }
+    }
+}
+
+impl NotificationBackend for WindowsToastBackend {
+    fn show(&self, notification: &Notification) -> AppResult<()> {
+        show_notification(
+            &self.app_id,
+            &notification.title,
+            &notification.body,
+            notification.icon_src.as_deref(),
+            notification.silent,
+        )
+        .map_err(|err| AppError::notification(err.to_string()))
+    }
+
+    fn is_silent_mode(&self) -> bool {
+        is_silent_mode(&self.app_id).unwrap_or(false)
+    }
+
+    fn preview_sound(&self) -> AppResult<()> {
+        show_notification_sound_preview(&self.app_id)
+            .map_err(|err| AppError::notification(err.to_string()))
+    }
+}
+
+fn show_notification(
+    app_id: &str,
     title: &str,
     body: &str,
-    icon_src: Option<String>,
+    icon_src: Option<&str>,
     silent: bool,
 ) -> Result<()> {
     unsafe {
@@ -23,14 +63,13 @@ pub fn show_notification(
     set_text(&xml, title, body)?;
 
     if let Some(icon_src) = icon_src {
-        set_circle_circle(&xml, &icon_src)?;
+        set_circle_circle(&xml, icon_src)?;
     }
 
     if silent {
         set_silent(&xml)?;
     }
 
-    let app_id = app.config().identifier.clone();
     let notifier = ToastNotificationManager::CreateToastNotifierWithId(&app_id.into())?;
     let toast = ToastNotification::CreateToastNotification(&xml)?;
 
@@ -45,7 +84,7 @@ pub fn show_notification(
     Ok(())
 }
 
-pub fn show_notification_sound_preview(app: &AppHandle) -> Result<()> {
+fn show_notification_sound_preview(app_id: &str) -> Result<()> {
     unsafe {
         let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
     }
@@ -53,7 +92,6 @@ pub fn show_notification_sound_preview(app: &AppHandle) -> Result<()> {
     let xml = ToastNotificationManager::GetTemplateContent(ToastTemplateType::ToastText02)?;
     set_text(&xml, "", "The notification sound looks like this")?;
 
-    let app_id = app.config().identifier.clone();
     let notifier = ToastNotificationManager::CreateToastNotifierWithId(&app_id.into())?;
     let toast = ToastNotification::CreateToastNotification(&xml)?;
 
@@ -68,7 +106,7 @@ pub fn show_notification_sound_preview(app: &AppHandle) -> Result<()> {
     Ok(())
 }
 
-pub fn is_silent_mode(app: &AppHandle) -> Result<bool> {
+fn is_silent_mode(app_id: &str) -> Result<bool> {
     let do_not_disturb = matches!(
         ToastNotificationManager::GetDefault()?.NotificationMode(),
         Ok(mode) if mode != ToastNotificationMode::Unrestricted
@@ -78,7 +116,6 @@ pub fn is_silent_mode(app: &AppHandle) -> Result<bool> {
         return Ok(true);
     }
 
-    let app_id = app.config().identifier.clone();
     let notifier = ToastNotificationManager::CreateToastNotifierWithId(&app_id.into())?;
     Ok(!matches!(notifier.Setting()?, NotificationSetting::Enabled))
 }
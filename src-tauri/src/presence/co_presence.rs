@@ -0,0 +1,134 @@
+use crate::presence::store::{PresenceStore, SharedTime};
+use crate::vrchat_utils::AppResult;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Meetings shorter than this are friends passing each other, not playing together.
+const MIN_MEETING_MILLIS: i64 = 2 * 60 * 1000;
+
+/// Who played with whom between two points in time. Edges are weighted by the time spent in
+/// the same instance.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CoPresenceGraph {
+    pub nodes: Vec<CoPresenceNode>,
+    /// Heaviest first.
+    pub edges: Vec<CoPresenceEdge>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CoPresenceNode {
+    pub friend_id: String,
+    pub display_name: Option<String>,
+    /// The weight of every edge of the friend added up.
+    pub together_secs: u64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CoPresenceEdge {
+    pub source: String,
+    pub target: String,
+    pub together_secs: u64,
+    pub encounters: u32,
+}
+
+/// A friend that was often in the same instance as another.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Companion {
+    pub friend_id: String,
+    pub display_name: Option<String>,
+    pub together_secs: u64,
+    pub encounters: u32,
+}
+
+/// Timestamps are Unix milliseconds.
+pub fn co_presence_graph(
+    store: &PresenceStore,
+    since: i64,
+    until: i64,
+) -> AppResult<CoPresenceGraph> {
+    let shared = sorted_shared_time(store, since, until)?;
+    let display_names = store.display_names()?;
+
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for pair in &shared {
+        for friend_id in &pair.friend_ids {
+            *totals.entry(friend_id.as_str()).or_default() += secs(pair.millis);
+        }
+    }
+    let mut nodes: Vec<_> = totals
+        .into_iter()
+        .map(|(friend_id, together_secs)| CoPresenceNode {
+            friend_id: friend_id.to_string(),
+            display_name: display_names.get(friend_id).cloned(),
+            together_secs,
+        })
+        .collect();
+    nodes.sort_by(|a, b| {
+        b.together_secs
+            .cmp(&a.together_secs)
+            .then_with(|| a.friend_id.cmp(&b.friend_id))
+    });
+
+    let edges = shared
+        .iter()
+        .map(|pair| CoPresenceEdge {
+            source: pair.friend_ids[0].clone(),
+            target: pair.friend_ids[1].clone(),
+            together_secs: secs(pair.millis),
+            encounters: pair.encounters,
+        })
+        .collect();
+
+    Ok(CoPresenceGraph { nodes, edges })
+}
+
+/// The friends that spent the most time in the same instance as `friend_id`, at most `limit`.
+pub fn top_companions(
+    store: &PresenceStore,
+    friend_id: &str,
+    since: i64,
+    until: i64,
+    limit: usize,
+) -> AppResult<Vec<Companion>> {
+    let display_names = store.display_names()?;
+    let companions = sorted_shared_time(store, since, until)?
+        .into_iter()
+        .filter_map(|pair| {
+            let [first, second] = pair.friend_ids;
+            let companion = if first == friend_id {
+                second
+            } else if second == friend_id {
+                first
+            } else {
+                return None;
+            };
+            Some(Companion {
+                display_name: display_names.get(&companion).cloned(),
+                friend_id: companion,
+                together_secs: secs(pair.millis),
+                encounters: pair.encounters,
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(companions)
+}
+
+/// Heaviest pairs first.
+fn sorted_shared_time(store: &PresenceStore, since: i64, until: i64) -> AppResult<Vec<SharedTime>> {
+    let mut shared = store.shared_time(since, until, MIN_MEETING_MILLIS)?;
+    shared.sort_by(|a, b| {
+        b.millis
+            .cmp(&a.millis)
+            .then_with(|| a.friend_ids.cmp(&b.friend_ids))
+    });
+    Ok(shared)
+}
+
+fn secs(millis: i64) -> u64 {
+    (millis.max(0) / 1000) as u64
+}
@@ -0,0 +1,102 @@
+mod co_presence;
+mod stats;
+mod store;
+
+use crate::roster::{ChangeCause, FriendRoster, RosterChange, RosterEntry};
+use chrono::Local;
+pub use co_presence::{
+    co_presence_graph, top_companions, CoPresenceEdge, CoPresenceGraph, CoPresenceNode, Companion,
+};
+pub use stats::{heatmap, online_millis, presence_stats, PresenceStats, DEFAULT_HEATMAP_WEEKS};
+pub use store::{Observation, PresenceStore, Session, SharedTime};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// How often the store is told that the app is still watching.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Records every presence change of the roster. Pipeline events are written one by one, while
+/// a resync, including the first download after logging in, is written at once.
+pub fn register_roster_listeners<R: Runtime>(app: &AppHandle<R>, roster: &FriendRoster) {
+    let pipeline_app = app.clone();
+    roster.subscribe(move |change| {
+        if matches!(change.cause, ChangeCause::Pipeline(_)) {
+            let at = Local::now().timestamp_millis();
+            observe(&pipeline_app, observation_of(change, at).as_slice());
+        }
+    });
+
+    // Sessions are closed whenever the pipeline stops, so every friend is observed again rather
+    // than only the ones that changed.
+    let resync_app = app.clone();
+    roster.subscribe_resync(move |_, changes| {
+        let at = Local::now().timestamp_millis();
+        let entries = resync_app.state::<FriendRoster>().entries();
+        let removed = changes.iter().filter(|change| change.current.is_none());
+        let observations: Vec<_> = entries
+            .iter()
+            .map(|entry| observation_of_entry(entry, at, false))
+            .chain(removed.filter_map(|change| observation_of(change, at)))
+            .collect();
+        observe(&resync_app, &observations);
+    });
+}
+
+/// Ends the open sessions when nothing tells anymore whether friends are online, i.e. after
+/// logging out or when the pipeline disconnects. The next resync opens them again.
+pub fn stop_watching<R: Runtime>(app: &AppHandle<R>) {
+    let Some(store) = app.try_state::<PresenceStore>() else {
+        return;
+    };
+    if let Err(err) = store.close_open_sessions(Local::now().timestamp_millis()) {
+        log::warn!("Failed to close the open presence sessions: {err}");
+    }
+}
+
+/// Keeps the last time the app was running, so sessions left open by quitting can be closed.
+pub fn start_checkpoints<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(store) = app.try_state::<PresenceStore>() {
+                if let Err(err) = store.checkpoint(Local::now().timestamp_millis()) {
+                    log::warn!("Failed to checkpoint the presence history: {err}");
+                }
+            }
+            tokio::time::sleep(CHECKPOINT_INTERVAL).await;
+        }
+    });
+}
+
+fn observe<R: Runtime>(app: &AppHandle<R>, observations: &[Observation<'_>]) {
+    let Some(store) = app.try_state::<PresenceStore>() else {
+        return;
+    };
+    if let Err(err) = store.observe_all(observations) {
+        log::warn!("Failed to record friend presence: {err}");
+    }
+}
+
+/// Removed friends count as offline.
+fn observation_of(change: &RosterChange, at: i64) -> Option<Observation<'_>> {
+    let exact = matches!(change.cause, ChangeCause::Pipeline(_));
+    match (change.current.as_ref(), change.previous.as_ref()) {
+        (Some(current), _) => Some(observation_of_entry(current, at, exact)),
+        (None, Some(previous)) => Some(Observation {
+            location: None,
+            ..observation_of_entry(previous, at, exact)
+        }),
+        (None, None) => None,
+    }
+}
+
+fn observation_of_entry(entry: &RosterEntry, at: i64, exact: bool) -> Observation<'_> {
+    Observation {
+        friend_id: &entry.friend.id,
+        display_name: entry.display_name(),
+        location: entry.is_online().then(|| entry.location()),
+        platform: &entry.friend.platform,
+        at,
+        exact,
+    }
+}
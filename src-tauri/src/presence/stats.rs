@@ -0,0 +1,109 @@
+use crate::presence::store::{PresenceStore, Session};
+use crate::vrchat_utils::AppResult;
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Timelike};
+use serde::Serialize;
+
+/// How many weeks back the heatmap covers unless asked otherwise.
+pub const DEFAULT_HEATMAP_WEEKS: u32 = 4;
+const MAX_HEATMAP_WEEKS: u32 = 52;
+
+/// Aggregated presence of one friend. Timestamps are Unix milliseconds.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenceStats {
+    pub friend_id: String,
+    pub display_name: Option<String>,
+    pub online: bool,
+    /// When the friend was last online. The current time while they are.
+    pub last_seen: Option<i64>,
+    /// Only sessions observed from start to end count.
+    pub average_session_secs: Option<u64>,
+    pub session_count: u32,
+    /// Time online since Monday 00:00, local time.
+    pub week_total_secs: u64,
+    /// Minutes online per local hour, `heatmap[weekday][hour]` with Monday first.
+    pub heatmap: Vec<Vec<u32>>,
+    pub heatmap_weeks: u32,
+}
+
+pub fn presence_stats(
+    store: &PresenceStore,
+    friend_id: &str,
+    heatmap_weeks: u32,
+    now: DateTime<Local>,
+) -> AppResult<PresenceStats> {
+    let heatmap_weeks = heatmap_weeks.clamp(1, MAX_HEATMAP_WEEKS);
+    let now_millis = now.timestamp_millis();
+    let week_start = start_of_week(now).timestamp_millis();
+    let heatmap_start = (now - Duration::weeks(i64::from(heatmap_weeks))).timestamp_millis();
+
+    let sessions = store.sessions_since(friend_id, heatmap_start.min(week_start))?;
+    let online = sessions.iter().any(|session| session.ended_at.is_none());
+    let last_seen = if online {
+        Some(now_millis)
+    } else {
+        store.last_ended_at(friend_id)?
+    };
+    let (average_millis, session_count) = store.average_session(friend_id)?;
+
+    Ok(PresenceStats {
+        friend_id: friend_id.to_string(),
+        display_name: store.display_name_of(friend_id)?,
+        online,
+        last_seen,
+        average_session_secs: average_millis.map(|millis| (millis.max(0) / 1000) as u64),
+        session_count,
+        week_total_secs: (online_millis(&sessions, week_start, now_millis) / 1000) as u64,
+        heatmap: heatmap(&sessions, heatmap_start, now_millis),
+        heatmap_weeks,
+    })
+}
+
+/// The time the sessions spent between `from` and `to`, in milliseconds.
+pub fn online_millis(sessions: &[Session], from: i64, to: i64) -> i64 {
+    sessions
+        .iter()
+        .map(|session| {
+            let start = session.started_at.max(from);
+            let end = session.ended_at.unwrap_or(to).min(to);
+            (end - start).max(0)
+        })
+        .sum()
+}
+
+/// Spreads the sessions between `from` and `to` over the local hours of the week.
+pub fn heatmap(sessions: &[Session], from: i64, to: i64) -> Vec<Vec<u32>> {
+    let mut millis = vec![vec![0_i64; 24]; 7];
+    for session in sessions {
+        let mut cursor = session.started_at.max(from);
+        let end = session.ended_at.unwrap_or(to).min(to);
+        while cursor < end {
+            let Some(local) = Local.timestamp_millis_opt(cursor).single() else {
+                break;
+            };
+            let into_hour = i64::from(local.minute() * 60 + local.second()) * 1000
+                + local.timestamp_subsec_millis() as i64;
+            let next = (cursor + 3_600_000 - into_hour).min(end);
+            let weekday = local.weekday().num_days_from_monday() as usize;
+            millis[weekday][local.hour() as usize] += next - cursor;
+            cursor = next;
+        }
+    }
+    millis
+        .into_iter()
+        .map(|day| {
+            day.into_iter()
+                .map(|millis| (millis / 60_000) as u32)
+                .collect()
+        })
+        .collect()
+}
+
+fn start_of_week(now: DateTime<Local>) -> DateTime<Local> {
+    let days_since_monday = i64::from(now.weekday().num_days_from_monday());
+    let monday = now.date_naive() - Duration::days(days_since_monday);
+    monday
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .unwrap_or(now)
+}
@@ -0,0 +1,339 @@
+use crate::database::Database;
+use crate::vrchat_utils::{AppError, AppResult};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, Runtime};
+
+const FILE_NAME: &str = "PresenceHistory.sqlite3";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS friends (
+    friend_id TEXT PRIMARY KEY,
+    display_name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    friend_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER,
+    exact INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_friend ON sessions (friend_id, started_at);
+CREATE INDEX IF NOT EXISTS sessions_open ON sessions (ended_at) WHERE ended_at IS NULL;
+CREATE TABLE IF NOT EXISTS locations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions (id),
+    location TEXT NOT NULL,
+    entered_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS locations_session ON locations (session_id, entered_at);
+CREATE INDEX IF NOT EXISTS locations_location ON locations (location, entered_at);
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    observed_at INTEGER NOT NULL
+);
+";
+
+/// One stretch of time a friend was online. Timestamps are Unix milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub started_at: i64,
+    /// `None` while the friend is still online.
+    pub ended_at: Option<i64>,
+    /// Whether both ends were observed as they happened. Sessions that were already running when
+    /// the app started, or that ended while it was not watching, are not.
+    pub exact: bool,
+}
+
+/// How much time two friends spent in the same instance. `friend_ids` is sorted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedTime {
+    pub friend_ids: [String; 2],
+    pub millis: i64,
+    /// How many times they met, counting each stay in an instance they shared.
+    pub encounters: u32,
+}
+
+/// What the roster says about a friend at one point in time.
+#[derive(Debug, Clone)]
+pub struct Observation<'a> {
+    pub friend_id: &'a str,
+    pub display_name: &'a str,
+    /// `None` while the friend is offline.
+    pub location: Option<&'a str>,
+    pub platform: &'a str,
+    /// Unix milliseconds.
+    pub at: i64,
+    /// Whether the change happened at `at`, rather than being found out later by a resync.
+    pub exact: bool,
+}
+
+/// Keeps the online sessions and locations of friends in a local SQLite database.
+pub struct PresenceStore {
+    database: Database,
+}
+
+impl PresenceStore {
+    pub fn open(path: &Path) -> AppResult<Self> {
+        let database = Database::open(path, init)?;
+        Ok(Self { database })
+    }
+
+    /// A store that is gone when the app exits.
+    pub fn in_memory() -> AppResult<Self> {
+        let database = Database::in_memory(init)?;
+        Ok(Self { database })
+    }
+
+    /// Opens the database in the app data directory. Falls back to memory if that fails.
+    pub fn for_app<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let database = Database::for_app(app, FILE_NAME, "presence history", init);
+        Self { database }
+    }
+
+    /// Brings the sessions of friends in line with `observations`, in one transaction.
+    pub fn observe_all(&self, observations: &[Observation<'_>]) -> AppResult<()> {
+        let mut connection = self.database.lock();
+        let transaction = connection.transaction()?;
+        for observation in observations {
+            observe(&transaction, observation)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Ends every open session at `at`. The ends were not observed, so the sessions are not
+    /// exact.
+    pub fn close_open_sessions(&self, at: i64) -> AppResult<()> {
+        self.database.lock().execute(
+            "UPDATE sessions SET ended_at = MAX(started_at, ?1), exact = 0
+             WHERE ended_at IS NULL",
+            params![at],
+        )?;
+        Ok(())
+    }
+
+    /// Remembers that the app was watching at `at`, so sessions left open by quitting can be
+    /// closed at that time on the next start.
+    pub fn checkpoint(&self, at: i64) -> AppResult<()> {
+        self.database.lock().execute(
+            "INSERT INTO checkpoint (id, observed_at) VALUES (0, ?1)
+             ON CONFLICT (id) DO UPDATE SET observed_at = excluded.observed_at",
+            params![at],
+        )?;
+        Ok(())
+    }
+
+    pub fn display_name_of(&self, friend_id: &str) -> AppResult<Option<String>> {
+        self.database
+            .lock()
+            .query_row(
+                "SELECT display_name FROM friends WHERE friend_id = ?1",
+                params![friend_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(AppError::from)
+    }
+
+    /// The IDs of every friend with a recorded session.
+    pub fn friend_ids(&self) -> AppResult<Vec<String>> {
+        let connection = self.database.lock();
+        let mut statement = connection.prepare("SELECT DISTINCT friend_id FROM sessions")?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// The sessions of a friend that were still running at or after `since`, oldest first.
+    pub fn sessions_since(&self, friend_id: &str, since: i64) -> AppResult<Vec<Session>> {
+        let connection = self.database.lock();
+        let mut statement = connection.prepare(
+            "SELECT started_at, ended_at, exact FROM sessions
+             WHERE friend_id = ?1 AND (ended_at IS NULL OR ended_at >= ?2)
+             ORDER BY started_at, id",
+        )?;
+        let rows = statement.query_map(params![friend_id, since], read_session)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// The end of the last finished session of a friend.
+    pub fn last_ended_at(&self, friend_id: &str) -> AppResult<Option<i64>> {
+        self.database
+            .lock()
+            .query_row(
+                "SELECT MAX(ended_at) FROM sessions WHERE friend_id = ?1",
+                params![friend_id],
+                |row| row.get(0),
+            )
+            .map_err(AppError::from)
+    }
+
+    /// The average length of the sessions of a friend whose ends were both observed, with how
+    /// many there were.
+    pub fn average_session(&self, friend_id: &str) -> AppResult<(Option<i64>, u32)> {
+        self.database
+            .lock()
+            .query_row(
+                "SELECT CAST(AVG(ended_at - started_at) AS INTEGER), COUNT(*) FROM sessions
+                 WHERE friend_id = ?1 AND ended_at IS NOT NULL AND exact",
+                params![friend_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(AppError::from)
+    }
+
+    /// The time friends spent in the same instance between `since` and `until`, per pair.
+    /// Meetings shorter than `min_millis` are left out, so passing through does not count.
+    pub fn shared_time(
+        &self,
+        since: i64,
+        until: i64,
+        min_millis: i64,
+    ) -> AppResult<Vec<SharedTime>> {
+        let connection = self.database.lock();
+        let mut statement = connection.prepare(
+            "WITH stays AS (
+                SELECT sessions.friend_id, locations.location, locations.entered_at AS start_at,
+                    COALESCE(
+                        LEAD(locations.entered_at) OVER (
+                            PARTITION BY locations.session_id
+                            ORDER BY locations.entered_at, locations.id
+                        ),
+                        sessions.ended_at,
+                        ?2
+                    ) AS end_at
+                FROM locations JOIN sessions ON sessions.id = locations.session_id
+                WHERE (sessions.ended_at IS NULL OR sessions.ended_at > ?1)
+                    AND locations.entered_at < ?2
+            ),
+            meetings AS (
+                SELECT a.friend_id AS friend_a, b.friend_id AS friend_b,
+                    MIN(a.end_at, b.end_at, ?2) - MAX(a.start_at, b.start_at, ?1) AS millis
+                FROM stays AS a JOIN stays AS b
+                    ON a.location = b.location AND a.friend_id < b.friend_id
+                    AND a.start_at < b.end_at AND b.start_at < a.end_at
+                -- Only instances can be shared. Private, offline and traveling are not.
+                WHERE substr(a.location, 1, 5) = 'wrld_'
+            )
+            SELECT friend_a, friend_b, SUM(millis), COUNT(*) FROM meetings
+            WHERE millis >= ?3
+            GROUP BY friend_a, friend_b",
+        )?;
+        let rows = statement.query_map(params![since, until, min_millis.max(1)], |row| {
+            Ok(SharedTime {
+                friend_ids: [row.get(0)?, row.get(1)?],
+                millis: row.get(2)?,
+                encounters: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    pub fn display_names(&self) -> AppResult<HashMap<String, String>> {
+        let connection = self.database.lock();
+        let mut statement = connection.prepare("SELECT friend_id, display_name FROM friends")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(AppError::from)
+    }
+}
+
+fn init(connection: &Connection) -> AppResult<()> {
+    // Every pipeline event may write, so fewer syncs matter more than surviving a power loss.
+    connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    connection.pragma_update(None, "synchronous", "NORMAL")?;
+    connection.execute_batch(SCHEMA)?;
+    close_dangling_sessions(connection)
+}
+
+/// Sessions left open when the app quit ended at the last checkpoint at the latest.
+fn close_dangling_sessions(connection: &Connection) -> AppResult<()> {
+    connection.execute(
+        "UPDATE sessions
+         SET ended_at = MAX(started_at, COALESCE((SELECT observed_at FROM checkpoint), 0)),
+             exact = 0
+         WHERE ended_at IS NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Opens a session when the friend is online without one, closes it when they are offline, and
+/// records a new location.
+fn observe(connection: &Connection, observation: &Observation<'_>) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO friends (friend_id, display_name) VALUES (?1, ?2)
+         ON CONFLICT (friend_id) DO UPDATE SET display_name = excluded.display_name",
+        params![observation.friend_id, observation.display_name],
+    )?;
+
+    let open_session: Option<i64> = connection
+        .query_row(
+            "SELECT id FROM sessions WHERE friend_id = ?1 AND ended_at IS NULL",
+            params![observation.friend_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match (open_session, observation.location) {
+        (None, Some(location)) => {
+            connection.execute(
+                "INSERT INTO sessions (friend_id, platform, started_at, exact)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    observation.friend_id,
+                    observation.platform,
+                    observation.at,
+                    observation.exact
+                ],
+            )?;
+            let session_id = connection.last_insert_rowid();
+            insert_location(connection, session_id, location, observation.at)?;
+        }
+        (Some(session_id), Some(location)) => {
+            let last_location: Option<String> = connection
+                .query_row(
+                    "SELECT location FROM locations WHERE session_id = ?1
+                     ORDER BY entered_at DESC, id DESC LIMIT 1",
+                    params![session_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if last_location.as_deref() != Some(location) {
+                insert_location(connection, session_id, location, observation.at)?;
+            }
+        }
+        (Some(session_id), None) => {
+            connection.execute(
+                "UPDATE sessions SET ended_at = MAX(started_at, ?2), exact = exact AND ?3
+                 WHERE id = ?1",
+                params![session_id, observation.at, observation.exact],
+            )?;
+        }
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+fn insert_location(
+    connection: &Connection,
+    session_id: i64,
+    location: &str,
+    at: i64,
+) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO locations (session_id, location, entered_at) VALUES (?1, ?2, ?3)",
+        params![session_id, location, at],
+    )?;
+    Ok(())
+}
+
+fn read_session(row: &Row<'_>) -> rusqlite::Result<Session> {
+    Ok(Session {
+        started_at: row.get(0)?,
+        ended_at: row.get(1)?,
+        exact: row.get(2)?,
+    })
+}
@@ -0,0 +1,68 @@
+use vrchatapi::models;
+
+/// One profile field of a friend that differs between two known states.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileChange {
+    Status {
+        from: models::UserStatus,
+        to: models::UserStatus,
+    },
+    StatusDescription {
+        from: String,
+        to: String,
+    },
+    Bio {
+        from: String,
+        to: String,
+    },
+    Avatar {
+        thumbnail_url: Option<String>,
+    },
+}
+
+/// Compares the profile fields users care about.
+/// Switching from or to offline is a presence change and is not reported here.
+pub fn profile_changes(
+    previous: &models::LimitedUserFriend,
+    current: &models::LimitedUserFriend,
+) -> Vec<ProfileChange> {
+    let mut changes = Vec::new();
+
+    let is_presence_change = previous.status == models::UserStatus::Offline
+        || current.status == models::UserStatus::Offline;
+    if previous.status != current.status && !is_presence_change {
+        changes.push(ProfileChange::Status {
+            from: previous.status,
+            to: current.status,
+        });
+    }
+
+    if previous.status_description != current.status_description {
+        changes.push(ProfileChange::StatusDescription {
+            from: previous.status_description.clone(),
+            to: current.status_description.clone(),
+        });
+    }
+
+    let previous_bio = previous.bio.as_deref().unwrap_or_default();
+    let current_bio = current.bio.as_deref().unwrap_or_default();
+    if previous_bio != current_bio {
+        changes.push(ProfileChange::Bio {
+            from: previous_bio.to_string(),
+            to: current_bio.to_string(),
+        });
+    }
+
+    // An empty image URL only means the payload did not include it.
+    let current_avatar = current
+        .current_avatar_image_url
+        .as_deref()
+        .filter(|url| !url.is_empty());
+    if current_avatar.is_some() && previous.current_avatar_image_url.as_deref() != current_avatar {
+        changes.push(ProfileChange::Avatar {
+            thumbnail_url: current.current_avatar_thumbnail_image_url.clone(),
+        });
+    }
+
+    changes
+}
@@ -0,0 +1,14 @@
+mod diff;
+mod patch;
+mod state;
+mod sync;
+
+pub use diff::{profile_changes, ProfileChange};
+pub use state::*;
+pub use sync::{
+    register_frontend_updates,
+    register_pipeline_handlers,
+    resync,
+    spawn_resync,
+    start_periodic_resync,
+};
@@ -0,0 +1,175 @@
+use crate::roster::state::RosterEntry;
+use crate::vrchat_utils::Location;
+use crate::websocket::PipelineEvent;
+use chrono::DateTime;
+use serde_json::{Map, Value};
+use std::time::SystemTime;
+use vrchatapi::models;
+
+/// User payloads often carry stale or empty values for these, so only the event itself may set them.
+const EVENT_OWNED_KEYS: [&str; 2] = ["location", "platform"];
+
+pub enum Outcome {
+    Updated(RosterEntry),
+    Removed,
+    Unchanged,
+    Failed,
+}
+
+pub fn user_id_of(event: &PipelineEvent) -> Option<&str> {
+    match event {
+        PipelineEvent::FriendAdd(event) => Some(&event.user_id),
+        PipelineEvent::FriendDelete(event) => Some(&event.user_id),
+        PipelineEvent::FriendActive(event) => Some(&event.user_id),
+        PipelineEvent::FriendOnline(event) => Some(&event.user_id),
+        PipelineEvent::FriendOffline(event) => Some(&event.user_id),
+        PipelineEvent::FriendUpdate(event) => Some(&event.user_id),
+        PipelineEvent::FriendLocation(event) => Some(&event.user_id),
+        _ => None,
+    }
+}
+
+pub fn location_of(friend: &models::LimitedUserFriend) -> &str {
+    friend
+        .location
+        .as_deref()
+        .unwrap_or(Location::Offline.kind())
+}
+
+/// Friends are online while they are in-game. Website activity does not count.
+pub fn is_online(friend: &models::LimitedUserFriend) -> bool {
+    let location = location_of(friend);
+    !location.is_empty() && !matches!(Location::parse(location), Ok(Location::Offline))
+}
+
+/// Keeps what the API does not know about (e.g. when the session started) across a resync.
+pub fn carry_over(previous: &RosterEntry, friend: models::LimitedUserFriend) -> RosterEntry {
+    let online_since = match (previous.is_online(), is_online(&friend)) {
+        (true, true) => previous.online_since,
+        (_, true) => Some(SystemTime::now()),
+        (_, false) => None,
+    };
+    RosterEntry {
+        friend,
+        online_since,
+    }
+}
+
+/// When a friend that is online in a downloaded friend list came online, as far as the API
+/// tells, i.e. their last login.
+pub fn online_since_of(friend: &models::LimitedUserFriend) -> Option<SystemTime> {
+    if !is_online(friend) {
+        return None;
+    }
+    let friend = serde_json::to_value(friend).ok()?;
+    let last_login = friend.get("last_login")?.as_str()?;
+    let last_login = DateTime::parse_from_rfc3339(last_login).ok()?;
+    Some(SystemTime::from(last_login))
+}
+
+pub fn apply(previous: Option<&RosterEntry>, event: &PipelineEvent) -> Outcome {
+    match (event, previous) {
+        (PipelineEvent::FriendDelete(_), Some(_)) => Outcome::Removed,
+        (PipelineEvent::FriendDelete(_), None) => Outcome::Unchanged,
+        (PipelineEvent::FriendAdd(event), previous) => {
+            let fields = [("location", Value::from(Location::Offline.kind()))];
+            patch_entry(previous, &event.user, &fields)
+        }
+        (PipelineEvent::FriendOnline(event), previous) => {
+            let fields = [
+                ("location", Value::from(event.location.clone())),
+                ("platform", Value::from(event.platform.clone())),
+            ];
+            patch_entry(previous, &event.user, &fields)
+        }
+        (PipelineEvent::FriendActive(event), previous) => {
+            let fields = [
+                ("location", Value::from(Location::Offline.kind())),
+                ("platform", Value::from(event.platform.clone().unwrap_or_default())),
+            ];
+            patch_entry(previous, &event.user, &fields)
+        }
+        (PipelineEvent::FriendUpdate(event), previous) => patch_entry(previous, &event.user, &[]),
+        (PipelineEvent::FriendLocation(event), previous) => {
+            let fields = [("location", Value::from(event.location.clone()))];
+            patch_entry(previous, &event.user, &fields)
+        }
+        (PipelineEvent::FriendOffline(_), Some(previous)) => {
+            let fields = [
+                ("location", Value::from(Location::Offline.kind())),
+                ("status", Value::from("offline")),
+            ];
+            match patch_fields(&previous.friend, None, &fields) {
+                Some(friend) => Outcome::Updated(carry_over(previous, friend)),
+                None => Outcome::Failed,
+            }
+        }
+        (PipelineEvent::FriendOffline(_), None) => Outcome::Failed,
+        _ => Outcome::Unchanged,
+    }
+}
+
+fn patch_entry(
+    previous: Option<&RosterEntry>,
+    user: &models::User,
+    fields: &[(&str, Value)],
+) -> Outcome {
+    let friend = match previous {
+        Some(previous) => patch_fields(&previous.friend, Some(user), fields),
+        None => from_user(user, fields),
+    };
+
+    match (friend, previous) {
+        (Some(friend), Some(previous)) => Outcome::Updated(carry_over(previous, friend)),
+        (Some(friend), None) => {
+            let online_since = is_online(&friend).then(SystemTime::now);
+            Outcome::Updated(RosterEntry::new(friend, online_since))
+        }
+        (None, _) => Outcome::Failed,
+    }
+}
+
+/// Overwrites the fields of `friend` that are also present on `user`, then the given `fields`.
+/// Merging the JSON representations lets the user payloads of the pipeline patch a friend without
+/// mapping every field by hand.
+fn patch_fields(
+    friend: &models::LimitedUserFriend,
+    user: Option<&models::User>,
+    fields: &[(&str, Value)],
+) -> Option<models::LimitedUserFriend> {
+    let Value::Object(mut target) = serde_json::to_value(friend).ok()? else {
+        return None;
+    };
+
+    if let Some(user) = user {
+        let Value::Object(source) = serde_json::to_value(user).ok()? else {
+            return None;
+        };
+        for (key, value) in source {
+            if target.contains_key(&key)
+                && !value.is_null()
+                && !EVENT_OWNED_KEYS.contains(&key.as_str())
+            {
+                target.insert(key, value);
+            }
+        }
+    }
+
+    set_fields(&mut target, fields);
+    serde_json::from_value(Value::Object(target)).ok()
+}
+
+fn from_user(user: &models::User, fields: &[(&str, Value)]) -> Option<models::LimitedUserFriend> {
+    let Value::Object(mut target) = serde_json::to_value(user).ok()? else {
+        return None;
+    };
+    target.insert(String::from("isFriend"), Value::from(true));
+    set_fields(&mut target, fields);
+    serde_json::from_value(Value::Object(target)).ok()
+}
+
+fn set_fields(target: &mut Map<String, Value>, fields: &[(&str, Value)]) {
+    for (key, value) in fields {
+        target.insert(key.to_string(), value.clone());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::roster::patch;
+use crate::websocket::PipelineEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+use vrchatapi::models;
+
+type RosterListener = Arc<dyn Fn(&RosterChange) + Send + Sync>;
+type ResyncListener = Arc<dyn Fn(ResyncReason, &[RosterChange]) + Send + Sync>;
+
+/// The last known state of one friend.
+#[derive(Debug, Clone)]
+pub struct RosterEntry {
+    pub friend: models::LimitedUserFriend,
+    /// When the friend came online, if that has been observed. Friends that were already online
+    /// when the roster was downloaded start at their last login, if the API tells it.
+    pub online_since: Option<SystemTime>,
+}
+
+impl RosterEntry {
+    pub fn new(friend: models::LimitedUserFriend, online_since: Option<SystemTime>) -> Self {
+        Self {
+            friend,
+            online_since,
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        patch::is_online(&self.friend)
+    }
+
+    pub fn location(&self) -> &str {
+        patch::location_of(&self.friend)
+    }
+
+    pub fn display_name(&self) -> &str {
+        &self.friend.display_name
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResyncReason {
+    /// The first full download after logging in.
+    Seed,
+    /// The pipeline connection has been re-established after a failure.
+    Reconnect,
+    /// The roster is refreshed from time to time to correct drift.
+    Periodic,
+    /// A pipeline event did not fit the last known state.
+    Drift,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChangeCause {
+    Pipeline(PipelineEvent),
+    Resync(ResyncReason),
+}
+
+/// Describes how one friend has changed. `previous` is `None` for new friends and `current` is
+/// `None` for removed ones.
+#[derive(Debug, Clone)]
+pub struct RosterChange {
+    pub user_id: String,
+    pub previous: Option<RosterEntry>,
+    pub current: Option<RosterEntry>,
+    pub cause: ChangeCause,
+}
+
+#[derive(Default, Clone)]
+struct RosterInner {
+    seeded: bool,
+    entries: HashMap<String, RosterEntry>,
+}
+
+/// The friend list kept up to date from pipeline events, so it only has to be downloaded once.
+#[derive(Default)]
+pub struct FriendRoster {
+    inner: Mutex<RosterInner>,
+    listeners: RwLock<Vec<RosterListener>>,
+    resync_listeners: RwLock<Vec<ResyncListener>>,
+}
+
+impl FriendRoster {
+    pub fn subscribe(&self, listener: impl Fn(&RosterChange) + Send + Sync + 'static) {
+        self.listeners
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Arc::new(listener));
+    }
+
+    /// Listens for all changes of one resync at once, after they have been published one by one.
+    pub fn subscribe_resync(
+        &self,
+        listener: impl Fn(ResyncReason, &[RosterChange]) + Send + Sync + 'static,
+    ) {
+        self.resync_listeners
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Arc::new(listener));
+    }
+
+    pub fn is_seeded(&self) -> bool {
+        self.lock().seeded
+    }
+
+    pub fn get(&self, user_id: &str) -> Option<RosterEntry> {
+        self.lock().entries.get(user_id).cloned()
+    }
+
+    pub fn entries(&self) -> Vec<RosterEntry> {
+        self.lock().entries.values().cloned().collect()
+    }
+
+    pub fn friends(&self) -> Vec<models::LimitedUserFriend> {
+        self.lock()
+            .entries
+            .values()
+            .map(|entry| entry.friend.clone())
+            .collect()
+    }
+
+    /// A copy of the current state without any listeners, e.g. to replay recorded traffic on.
+    pub fn fork(&self) -> FriendRoster {
+        FriendRoster {
+            inner: Mutex::new(self.lock().clone()),
+            ..FriendRoster::default()
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.lock() = RosterInner::default();
+    }
+
+    /// Replaces the whole roster with a freshly downloaded friend list and notifies listeners
+    /// about every friend that differs from the last known state.
+    /// Until the roster has been seeded, every resync is reported as [`ResyncReason::Seed`], since
+    /// there is no known state to differ from, e.g. when a periodic resync wins the race after
+    /// logging in again.
+    pub fn replace_all(&self, friends: Vec<models::LimitedUserFriend>, reason: ResyncReason) {
+        let (changes, reason) = {
+            let mut inner = self.lock();
+            let reason = if inner.seeded {
+                reason
+            } else {
+                ResyncReason::Seed
+            };
+            let mut previous_entries = std::mem::take(&mut inner.entries);
+            let mut changes = Vec::new();
+
+            for friend in friends {
+                let user_id = friend.id.clone();
+                let previous = previous_entries.remove(&user_id);
+                let current = match previous.as_ref() {
+                    Some(previous) => patch::carry_over(previous, friend),
+                    None => {
+                        let online_since = patch::online_since_of(&friend);
+                        RosterEntry::new(friend, online_since)
+                    }
+                };
+                if previous.as_ref().map(|entry| &entry.friend) != Some(&current.friend) {
+                    changes.push(RosterChange {
+                        user_id: user_id.clone(),
+                        previous,
+                        current: Some(current.clone()),
+                        cause: ChangeCause::Resync(reason),
+                    });
+                }
+                inner.entries.insert(user_id, current);
+            }
+
+            for (user_id, previous) in previous_entries {
+                changes.push(RosterChange {
+                    user_id,
+                    previous: Some(previous),
+                    current: None,
+                    cause: ChangeCause::Resync(reason),
+                });
+            }
+
+            inner.seeded = true;
+            (changes, reason)
+        };
+
+        for change in &changes {
+            self.publish(change);
+        }
+
+        let resync_listeners = self
+            .resync_listeners
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        for listener in resync_listeners {
+            listener(reason, &changes);
+        }
+    }
+
+    /// Patches the roster from a pipeline event.
+    /// Returns `false` when the event could not be applied and the roster should be resynced.
+    pub fn apply(&self, event: &PipelineEvent) -> bool {
+        let change = {
+            let mut inner = self.lock();
+            if !inner.seeded {
+                return true;
+            }
+
+            let Some(user_id) = patch::user_id_of(event) else {
+                return true;
+            };
+            let previous = inner.entries.get(user_id).cloned();
+
+            let current = match patch::apply(previous.as_ref(), event) {
+                patch::Outcome::Updated(entry) => Some(entry),
+                patch::Outcome::Removed => None,
+                patch::Outcome::Unchanged => return true,
+                patch::Outcome::Failed => return false,
+            };
+
+            match current.as_ref() {
+                Some(entry) => inner.entries.insert(user_id.to_string(), entry.clone()),
+                None => inner.entries.remove(user_id),
+            };
+
+            RosterChange {
+                user_id: user_id.to_string(),
+                previous,
+                current,
+                cause: ChangeCause::Pipeline(event.clone()),
+            }
+        };
+
+        self.publish(&change);
+        true
+    }
+
+    fn publish(&self, change: &RosterChange) {
+        let listeners = self
+            .listeners
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        for listener in listeners {
+            listener(change);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RosterInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
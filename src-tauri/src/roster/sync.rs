@@ -0,0 +1,102 @@
+use crate::auth::AuthState;
+use crate::roster::state::{ChangeCause, FriendRoster, ResyncReason};
+use crate::vrchat_utils;
+use crate::vrchat_utils::{ApiService, AppResult};
+use crate::websocket::{PipelineDispatcher, PipelineEventKind};
+use log::{debug, error, warn};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use vrchatapi::models;
+
+const RESYNC_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FriendRemoved<'a> {
+    user_id: &'a str,
+}
+
+/// Patches the roster from friend events, falling back to a full resync when an event does not
+/// fit the last known state.
+pub fn register_pipeline_handlers<R: Runtime>(app: &AppHandle<R>, dispatcher: &PipelineDispatcher) {
+    let app = app.clone();
+    dispatcher.subscribe_all(&PipelineEventKind::FRIEND_EVENTS, move |event| {
+        let roster = app.state::<FriendRoster>();
+        if !roster.apply(event) {
+            warn!("Friend roster drifted from the pipeline. Resyncing. (type={:?})", event.kind());
+            spawn_resync(&app, ResyncReason::Drift);
+        }
+    });
+}
+
+/// Sends every change made by the pipeline to the front end as a per-friend delta.
+/// Resyncs send the whole list through `vrc:friends-refresh` instead.
+pub fn register_frontend_updates<R: Runtime>(app: &AppHandle<R>, roster: &FriendRoster) {
+    let app = app.clone();
+    roster.subscribe(move |change| {
+        if !matches!(change.cause, ChangeCause::Pipeline(_)) {
+            return;
+        }
+
+        let result = match change.current.as_ref() {
+            Some(entry) => app.emit("vrc:friend-upsert", &entry.friend),
+            None => app.emit(
+                "vrc:friend-remove",
+                FriendRemoved {
+                    user_id: &change.user_id,
+                },
+            ),
+        };
+        if let Err(err) = result {
+            warn!("Failed to emit friend update: {err}");
+        }
+    });
+}
+
+pub async fn resync<R: Runtime>(
+    app: &AppHandle<R>,
+    reason: ResyncReason,
+) -> AppResult<Vec<models::LimitedUserFriend>> {
+    let auth_state = app.state::<AuthState>();
+    let api = app.state::<ApiService>();
+    let friends = vrchat_utils::fetch_all_friends(&api, &auth_state).await?;
+
+    let roster = app.state::<FriendRoster>();
+    roster.replace_all(friends, reason);
+
+    let friends = roster.friends();
+    app.emit("vrc:friends-refresh", &friends)?;
+    Ok(friends)
+}
+
+pub fn spawn_resync<R: Runtime>(app: &AppHandle<R>, reason: ResyncReason) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = resync(&app, reason).await {
+            error!("Failed to resync friends ({reason:?}): {err}");
+            if err.is_unauthorized() {
+                app.state::<AuthState>().recover_session(&app).await;
+            }
+        }
+    });
+}
+
+pub fn start_periodic_resync<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(RESYNC_INTERVAL).await;
+            if !app.state::<FriendRoster>().is_seeded() {
+                debug!("Periodic friend resync skipped. Roster is not seeded.");
+                continue;
+            }
+            if let Err(err) = resync(&app, ResyncReason::Periodic).await {
+                warn!("Periodic friend resync failed: {err}");
+                if err.is_unauthorized() {
+                    app.state::<AuthState>().recover_session(&app).await;
+                }
+            }
+        }
+    });
+}
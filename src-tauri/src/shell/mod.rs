@@ -1,4 +1,5 @@
-use tauri::{AppHandle, Window, WindowEvent};
+use crate::websocket::ConnectionState;
+use tauri::{AppHandle, Runtime, Window, WindowEvent};
 mod startup;
 mod tray;
 
@@ -18,3 +19,8 @@ pub fn handle_window_event(window: &Window, event: &WindowEvent) {
     #[cfg(desktop)]
     tray::handle_window_event(window, event);
 }
+
+pub fn show_connection_state<R: Runtime>(app: &AppHandle<R>, state: &ConnectionState) {
+    #[cfg(desktop)]
+    tray::show_connection_state(app, state);
+}
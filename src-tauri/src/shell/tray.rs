@@ -1,7 +1,9 @@
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::{AppHandle, Manager, Window, WindowEvent};
+use crate::websocket::ConnectionState;
+use tauri::{AppHandle, Manager, Runtime, Window, WindowEvent};
 
+const TRAY_ID: &str = "main";
 const TRAY_OPEN_ID: &str = "tray-open";
 const TRAY_QUIT_ID: &str = "tray-quit";
 
@@ -11,7 +13,7 @@ pub fn setup(app: &AppHandle) -> tauri::Result<()> {
     let quit = MenuItem::with_id(app, TRAY_QUIT_ID, "Quit", true, None::<&str>)?;
     menu.append_items(&[&open, &quit])?;
 
-    let mut builder = TrayIconBuilder::with_id("main")
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
         .tooltip("vfriends")
         .show_menu_on_left_click(false)
@@ -50,6 +52,22 @@ pub fn handle_window_event(window: &Window, event: &WindowEvent) {
     }
 }
 
+pub fn show_connection_state<R: Runtime>(app: &AppHandle<R>, state: &ConnectionState) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let status = match state {
+        ConnectionState::Connecting => String::from("connecting..."),
+        ConnectionState::Connected { .. } => String::from("notifications are live"),
+        ConnectionState::Reconnecting { .. } => String::from("reconnecting..."),
+        ConnectionState::Disconnected { reason: None } => String::from("disconnected"),
+        ConnectionState::Disconnected {
+            reason: Some(reason),
+        } => format!("disconnected: {reason}"),
+    };
+    let _ = tray.set_tooltip(Some(format!("vfriends ({status})")));
+}
+
 pub(crate) fn hide_main_window(app: &AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.hide();
@@ -0,0 +1,529 @@
+use crate::config::Endpoints;
+use crate::websocket::WebsocketMessage;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::header::{AUTHORIZATION, COOKIE, RETRY_AFTER, SET_COOKIE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use vrchatapi::models;
+
+const AUTH_COOKIE: &str = "auth";
+const TWO_FACTOR_COOKIE: &str = "twoFactorAuth";
+
+/// The account the mock server accepts.
+#[derive(Debug, Clone)]
+pub struct MockAccount {
+    pub username: String,
+    pub password: String,
+    /// 2FA methods offered after the password check, e.g. `totp`, `otp` or `emailOtp`.
+    /// Empty when the account has no 2FA.
+    pub two_factor_methods: Vec<String>,
+    pub totp_code: String,
+    pub email_code: String,
+    pub recovery_code: String,
+    pub user: models::CurrentUser,
+}
+
+impl Default for MockAccount {
+    fn default() -> Self {
+        Self {
+            username: String::from("mock-user"),
+            password: String::from("mock-password"),
+            two_factor_methods: Vec::new(),
+            totp_code: String::from("123456"),
+            email_code: String::from("654321"),
+            recovery_code: String::from("abcd-efgh"),
+            user: models::CurrentUser {
+                id: String::from("usr_mock"),
+                display_name: String::from("Mock User"),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl MockAccount {
+    pub fn with_two_factor(mut self, methods: &[&str]) -> Self {
+        self.two_factor_methods = methods.iter().map(|method| method.to_string()).collect();
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PipelineCommand {
+    Send(String),
+    Disconnect,
+}
+
+#[derive(Default)]
+struct Session {
+    two_factor_verified: bool,
+}
+
+struct MockState {
+    account: MockAccount,
+    sessions: HashMap<String, Session>,
+    next_session: u64,
+    friends: Vec<models::LimitedUserFriend>,
+    worlds: HashMap<String, models::World>,
+    pending_pipeline_messages: Vec<String>,
+    pipeline_connections: usize,
+    /// Pipeline connections turned away with 401 although the API accepts the session.
+    rejected_pipeline_connections: usize,
+    requests: Vec<String>,
+    /// World requests answered with 429 before the API recovers.
+    rate_limited_requests: usize,
+    retry_after_secs: u64,
+}
+
+#[derive(Clone)]
+struct Shared {
+    state: Arc<Mutex<MockState>>,
+    pipeline: broadcast::Sender<PipelineCommand>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A local stand-in for the parts of the VRChat API and pipeline that vfriends uses.
+/// The server stops when it is dropped.
+pub struct MockServer {
+    address: std::net::SocketAddr,
+    shared: Shared,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start(account: MockAccount) -> Self {
+        let (pipeline, _) = broadcast::channel(64);
+        let shared = Shared {
+            state: Arc::new(Mutex::new(MockState {
+                account,
+                sessions: HashMap::new(),
+                next_session: 0,
+                friends: Vec::new(),
+                worlds: HashMap::new(),
+                pending_pipeline_messages: Vec::new(),
+                pipeline_connections: 0,
+                rejected_pipeline_connections: 0,
+                requests: Vec::new(),
+                rate_limited_requests: 0,
+                retry_after_secs: 0,
+            })),
+            pipeline,
+        };
+
+        let router = Router::new()
+            .route("/api/1/auth/user", get(current_user))
+            .route("/api/1/auth/twofactorauth/{method}/verify", post(verify))
+            .route("/api/1/auth/user/friends", get(friends))
+            .route("/api/1/worlds/{world_id}", get(world))
+            .route("/pipeline", get(pipeline))
+            .with_state(shared.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind the mock server.");
+        let address = listener
+            .local_addr()
+            .expect("Mock server has no local address.");
+        let task = tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, router).await {
+                log::error!("Mock server stopped: {err}");
+            }
+        });
+
+        Self {
+            address,
+            shared,
+            task,
+        }
+    }
+
+    pub fn endpoints(&self) -> Endpoints {
+        Endpoints {
+            api_base_path: format!("http://{}/api/1", self.address),
+            pipeline_url: format!("ws://{}/pipeline", self.address),
+        }
+    }
+
+    pub fn set_friends(&self, friends: Vec<models::LimitedUserFriend>) {
+        self.shared.lock().friends = friends;
+    }
+
+    pub fn add_world(&self, world: models::World) {
+        self.shared.lock().worlds.insert(world.id.clone(), world);
+    }
+
+    /// Answers the next `count` world requests with 429 and a Retry-After header.
+    pub fn rate_limit_next(&self, count: usize, retry_after_secs: u64) {
+        let mut state = self.shared.lock();
+        state.rate_limited_requests = count;
+        state.retry_after_secs = retry_after_secs;
+    }
+
+    /// Forgets every session, as if all auth cookies had expired.
+    pub fn expire_sessions(&self) {
+        self.shared.lock().sessions.clear();
+    }
+
+    /// Turns away the next `count` pipeline connections with 401, while the API still accepts
+    /// the session.
+    pub fn reject_pipeline_next(&self, count: usize) {
+        self.shared.lock().rejected_pipeline_connections = count;
+    }
+
+    /// Requests received so far, as `METHOD path?query`.
+    pub fn requests(&self) -> Vec<String> {
+        self.shared.lock().requests.clone()
+    }
+
+    pub fn pipeline_connections(&self) -> usize {
+        self.shared.lock().pipeline_connections
+    }
+
+    /// Sends a pipeline event to every connected client.
+    /// Events sent while nobody is connected are delivered to the next connection.
+    pub fn send_pipeline_event(&self, kind: &str, content: &impl Serialize) {
+        let message = WebsocketMessage {
+            kind: kind.to_string(),
+            content: serde_json::to_string(content).expect("Unserializable pipeline content."),
+        };
+        let message = serde_json::to_string(&message).expect("Unserializable pipeline message.");
+
+        let mut state = self.shared.lock();
+        if self.shared.pipeline.receiver_count() == 0 {
+            state.pending_pipeline_messages.push(message);
+        } else {
+            let _ = self.shared.pipeline.send(PipelineCommand::Send(message));
+        }
+    }
+
+    /// Closes every pipeline connection without a close frame, like a dropped network.
+    pub fn disconnect_pipeline(&self) {
+        let _ = self.shared.pipeline.send(PipelineCommand::Disconnect);
+    }
+
+    /// Waits until the pipeline has been connected to `count` times in total.
+    pub async fn wait_for_pipeline_connections(&self, count: usize, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            if self.pipeline_connections() >= count {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        false
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub fn mock_friend(id: &str, display_name: &str, location: &str) -> models::LimitedUserFriend {
+    let status = if location == "offline" {
+        models::UserStatus::Offline
+    } else {
+        models::UserStatus::Active
+    };
+    models::LimitedUserFriend {
+        id: id.to_string(),
+        display_name: display_name.to_string(),
+        location: Some(location.to_string()),
+        platform: String::from("standalonewindows"),
+        status,
+        is_friend: true,
+        ..Default::default()
+    }
+}
+
+pub fn mock_world(id: &str, name: &str) -> models::World {
+    models::World {
+        id: id.to_string(),
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+#[derive(Deserialize)]
+struct VerifyBody {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct FriendsQuery {
+    offset: Option<usize>,
+    n: Option<usize>,
+    offline: Option<bool>,
+}
+
+async fn current_user(State(shared): State<Shared>, headers: HeaderMap) -> Response {
+    let mut state = shared.lock();
+    state.requests.push(String::from("GET /api/1/auth/user"));
+
+    if let Some(token) = session_token(&headers).filter(|token| state.sessions.contains_key(token))
+    {
+        return current_user_response(&state, &token, None);
+    }
+
+    if !is_valid_basic_auth(&headers, &state.account) {
+        return error_response(
+            StatusCode::UNAUTHORIZED,
+            "Invalid Username/Email or Password",
+        );
+    }
+
+    state.next_session += 1;
+    let token = format!("authcookie_mock_{}", state.next_session);
+    state.sessions.insert(token.clone(), Session::default());
+    let cookie = format!("{AUTH_COOKIE}={token}; Path=/; HttpOnly");
+    current_user_response(&state, &token, Some(cookie))
+}
+
+fn current_user_response(state: &MockState, token: &str, cookie: Option<String>) -> Response {
+    let verified = state
+        .sessions
+        .get(token)
+        .is_some_and(|session| session.two_factor_verified);
+    let body = if state.account.two_factor_methods.is_empty() || verified {
+        serde_json::to_value(&state.account.user).unwrap_or_default()
+    } else {
+        json!({ "requiresTwoFactorAuth": state.account.two_factor_methods })
+    };
+
+    let mut response = Json(body).into_response();
+    if let Some(cookie) = cookie.and_then(|cookie| cookie.parse().ok()) {
+        response.headers_mut().append(SET_COOKIE, cookie);
+    }
+    response
+}
+
+async fn verify(
+    State(shared): State<Shared>,
+    Path(method): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<VerifyBody>,
+) -> Response {
+    let mut state = shared.lock();
+    state
+        .requests
+        .push(format!("POST /api/1/auth/twofactorauth/{method}/verify"));
+
+    let Some(token) = session_token(&headers).filter(|token| state.sessions.contains_key(token))
+    else {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing Credentials");
+    };
+    if let Some(response) = take_rate_limit(&mut state) {
+        return response;
+    }
+
+    let (offered, expected) = match method.as_str() {
+        "totp" => ("totp", &state.account.totp_code),
+        "emailotp" => ("emailOtp", &state.account.email_code),
+        "otp" => ("otp", &state.account.recovery_code),
+        _ => return error_response(StatusCode::NOT_FOUND, "Unknown 2FA method"),
+    };
+    let is_offered = state
+        .account
+        .two_factor_methods
+        .iter()
+        .any(|method| method == offered);
+    if !is_offered || body.code != *expected {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Invalid 2-Factor Authentication Code",
+        );
+    }
+
+    if let Some(session) = state.sessions.get_mut(&token) {
+        session.two_factor_verified = true;
+    }
+    let mut response = Json(json!({ "verified": true })).into_response();
+    let cookie = format!("{TWO_FACTOR_COOKIE}=twofactor_{token}; Path=/; HttpOnly");
+    if let Ok(cookie) = cookie.parse() {
+        response.headers_mut().append(SET_COOKIE, cookie);
+    }
+    response
+}
+
+async fn friends(
+    State(shared): State<Shared>,
+    Query(query): Query<FriendsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = shared.lock();
+    let offset = query.offset.unwrap_or(0);
+    let n = query.n.unwrap_or(60);
+    let offline = query.offline.unwrap_or(false);
+    state.requests.push(format!(
+        "GET /api/1/auth/user/friends?offset={offset}&n={n}&offline={offline}"
+    ));
+
+    if !is_authorized(&state, &headers) {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing Credentials");
+    }
+
+    let page: Vec<_> = state
+        .friends
+        .iter()
+        .filter(|friend| (friend.location.as_deref() == Some("offline")) == offline)
+        .skip(offset)
+        .take(n)
+        .cloned()
+        .collect();
+    Json(page).into_response()
+}
+
+async fn world(
+    State(shared): State<Shared>,
+    Path(world_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = shared.lock();
+    state.requests.push(format!("GET /api/1/worlds/{world_id}"));
+
+    if !is_authorized(&state, &headers) {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing Credentials");
+    }
+    if let Some(response) = take_rate_limit(&mut state) {
+        return response;
+    }
+    match state.worlds.get(&world_id) {
+        Some(world) => Json(world.clone()).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, "World not found"),
+    }
+}
+
+async fn pipeline(
+    State(shared): State<Shared>,
+    Query(query): Query<HashMap<String, String>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let authorized = {
+        let mut state = shared.lock();
+        state.requests.push(String::from("GET /pipeline"));
+        if state.rejected_pipeline_connections > 0 {
+            state.rejected_pipeline_connections -= 1;
+            return error_response(StatusCode::UNAUTHORIZED, "Missing Credentials");
+        }
+        query
+            .get("authToken")
+            .and_then(|token| state.sessions.get(token))
+            .is_some_and(|session| {
+                state.account.two_factor_methods.is_empty() || session.two_factor_verified
+            })
+    };
+    if !authorized {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing Credentials");
+    }
+
+    upgrade.on_upgrade(move |socket| pipeline_session(socket, shared))
+}
+
+async fn pipeline_session(mut socket: WebSocket, shared: Shared) {
+    let mut commands = shared.pipeline.subscribe();
+    let pending = {
+        let mut state = shared.lock();
+        state.pipeline_connections += 1;
+        std::mem::take(&mut state.pending_pipeline_messages)
+    };
+
+    for message in pending {
+        if socket.send(Message::Text(message.into())).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Ok(PipelineCommand::Send(message)) => {
+                    if socket.send(Message::Text(message.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(PipelineCommand::Disconnect) | Err(_) => return,
+            },
+            // Reading keeps ping/pong going and notices clients that have gone away.
+            incoming = socket.recv() => match incoming {
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => return,
+            },
+        }
+    }
+}
+
+fn is_authorized(state: &MockState, headers: &HeaderMap) -> bool {
+    session_token(headers)
+        .and_then(|token| state.sessions.get(&token))
+        .is_some_and(|session| {
+            state.account.two_factor_methods.is_empty() || session.two_factor_verified
+        })
+}
+
+fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == AUTH_COOKIE)
+        .map(|(_, value)| value.to_string())
+}
+
+fn is_valid_basic_auth(headers: &HeaderMap, account: &MockAccount) -> bool {
+    let Some(encoded) = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+    else {
+        return false;
+    };
+    let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(encoded) else {
+        return false;
+    };
+    String::from_utf8(decoded).is_ok_and(|credentials| {
+        credentials == format!("{}:{}", account.username, account.password)
+    })
+}
+
+fn take_rate_limit(state: &mut MockState) -> Option<Response> {
+    if state.rate_limited_requests == 0 {
+        return None;
+    }
+    state.rate_limited_requests -= 1;
+    let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "Too Many Requests");
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, state.retry_after_secs.into());
+    Some(response)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": format!("\"{message}\""),
+            "status_code": status.as_u16(),
+        }
+    });
+    (status, Json::<Value>(body)).into_response()
+}
@@ -0,0 +1,125 @@
+//! Drives vfriends against a local stand-in for the VRChat API.
+//! Only built with the `test-support` feature.
+mod mock_server;
+
+pub use crate::auth::AuthState;
+pub use crate::config::{
+    AppSettings, EndpointSettings, Endpoints, FlapSuppression, FriendSettings, MissedOnlineMode,
+    ProfileSubscription, QuietHours, QuietMode, QuietWindow, SettingsStore,
+};
+pub use crate::history::{
+    export_history, Decision, ExportFormat, HistoryFilter, HistoryQuery, HistoryStore,
+    NotificationKind, Subject, SuppressionReason,
+};
+pub use crate::notifier::{
+    compose_digest, compose_friend_location, compose_friend_offline, compose_friend_online,
+    compose_missed_online, compose_online_batch, compose_profile_change, join_names, partition,
+    render_template, FlapGuard, Notification, NotifierScope, OnlineVerdict, RecordedOutput,
+    RecordingBackend, Template, TemplateError, TemplateValues,
+};
+pub use crate::presence::{
+    co_presence_graph, heatmap, online_millis, presence_stats, top_companions, Observation,
+    PresenceStore, Session,
+};
+pub use crate::roster::{profile_changes, FriendRoster, ProfileChange, ResyncReason, RosterEntry};
+pub use crate::vrchat_utils::{
+    fetch_all_friends, fetch_world, fetch_world_name, AccessType, ApiService, InstanceTag, Location,
+};
+pub use crate::websocket::{
+    replay, ConnectionState, FriendLocationEvent, FriendOnlineEvent, PipelineDispatcher,
+    PipelineEvent, PipelineEventKind, PipelineRecorder, ReplaySpeed, WebsocketMessage,
+    WebsocketState,
+};
+pub use mock_server::{mock_friend, mock_world, MockAccount, MockServer};
+
+use crate::notifier::NotifierState;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::test::MockRuntime;
+use tauri::{App, AppHandle, Listener, Manager, Runtime};
+
+/// Builds an app on the mock runtime with the state that auth, the pipeline, the roster and the
+/// notifier need. Sessions are kept out of the keychain, settings out of the settings file, the
+/// histories in memory, and notifications end up in the managed `Arc<RecordingBackend>`.
+pub fn mock_app(endpoints: Endpoints) -> App<MockRuntime> {
+    let notifications = Arc::new(RecordingBackend::default());
+    let app = tauri::test::mock_builder()
+        .manage(AuthState::ephemeral(endpoints))
+        .manage(WebsocketState::default())
+        .manage(PipelineDispatcher::default())
+        .manage(PipelineRecorder::new(
+            std::env::temp_dir().join("vfriends-test-pipeline"),
+            false,
+        ))
+        .manage(FriendRoster::default())
+        .manage(ApiService::default())
+        .manage(SettingsStore::ephemeral(AppSettings::default()))
+        .manage(NotifierState::new(notifications.clone()))
+        .manage(notifications)
+        .manage(NotifierScope::live())
+        .manage(HistoryStore::in_memory().expect("Failed to open an in-memory history."))
+        .manage(PresenceStore::in_memory().expect("Failed to open an in-memory presence history."))
+        .build(tauri::test::mock_context(tauri::test::noop_assets()))
+        .expect("Failed to build the mock app.");
+
+    let dispatcher = app.state::<PipelineDispatcher>();
+    let scope = app.state::<NotifierScope>();
+    crate::notifier::register_pipeline_handlers(app.handle(), &dispatcher, &scope);
+    crate::roster::register_pipeline_handlers(app.handle(), &dispatcher);
+    let roster = app.state::<FriendRoster>();
+    crate::notifier::register_roster_listeners(app.handle(), &roster, &scope);
+    crate::notifier::register_settings_archive(app.handle(), &roster);
+    crate::presence::register_roster_listeners(app.handle(), &roster);
+    app
+}
+
+/// Collects the payloads of one event emitted by the backend.
+#[derive(Clone, Default)]
+pub struct EventLog {
+    payloads: Arc<Mutex<Vec<Value>>>,
+}
+
+impl EventLog {
+    pub fn listen<R: Runtime>(app: &AppHandle<R>, event: &str) -> Self {
+        let log = Self::default();
+        let payloads = log.payloads.clone();
+        app.listen_any(event.to_string(), move |event| {
+            let payload = serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+            payloads
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(payload);
+        });
+        log
+    }
+
+    pub fn payloads(&self) -> Vec<Value> {
+        self.payloads
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Waits for a payload matching `predicate`, including ones received before the call.
+    pub async fn wait_for(
+        &self,
+        predicate: impl Fn(&Value) -> bool,
+        timeout: Duration,
+    ) -> Option<Value> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(payload) = self
+                .payloads()
+                .into_iter()
+                .find(|payload| predicate(payload))
+            {
+                return Some(payload);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
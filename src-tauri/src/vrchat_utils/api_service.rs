@@ -0,0 +1,234 @@
+use crate::vrchat_utils::rate_limiter::RateLimiter;
+use crate::vrchat_utils::{AppError, AppResult};
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use reqwest::header::{RETRY_AFTER, USER_AGENT};
+use serde::de::DeserializeOwned;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use vrchatapi::apis::configuration::Configuration;
+
+/// Stays well below the limits VRChat asks API clients to respect.
+const REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+const REQUEST_BURST: u32 = 10;
+const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// World names kept in the cache before the oldest are dropped.
+const WORLD_NAME_CAPACITY: usize = 512;
+
+type SharedRequest<T> = Shared<BoxFuture<'static, AppResult<T>>>;
+
+/// Every call to the VRChat API goes through here, so bursts of pipeline events cannot flood it.
+#[derive(Clone)]
+pub struct ApiService {
+    inner: Arc<ApiInner>,
+}
+
+struct ApiInner {
+    limiter: RateLimiter,
+    in_flight: Mutex<HashMap<String, Box<dyn Any + Send>>>,
+    world_names: Mutex<WorldNames>,
+}
+
+/// World names by ID, with the IDs in the order they were added.
+#[derive(Default)]
+struct WorldNames {
+    names: HashMap<String, String>,
+    order: VecDeque<String>,
+}
+
+impl Default for ApiService {
+    fn default() -> Self {
+        Self::new(REQUEST_INTERVAL, REQUEST_BURST)
+    }
+}
+
+impl ApiService {
+    pub fn new(interval: Duration, burst: u32) -> Self {
+        Self {
+            inner: Arc::new(ApiInner {
+                limiter: RateLimiter::new(interval, burst),
+                in_flight: Mutex::new(HashMap::new()),
+                world_names: Mutex::new(WorldNames::default()),
+            }),
+        }
+    }
+
+    pub fn cached_world_name(&self, world_id: &str) -> Option<String> {
+        self.inner.lock_world_names().names.get(world_id).cloned()
+    }
+
+    /// World names rarely change, so they are cached until logging out. Only the last
+    /// `WORLD_NAME_CAPACITY` worlds are kept.
+    pub fn cache_world_name(&self, world_id: &str, name: &str) {
+        let mut world_names = self.inner.lock_world_names();
+        let previous = world_names
+            .names
+            .insert(world_id.to_string(), name.to_string());
+        if previous.is_some() {
+            return;
+        }
+        world_names.order.push_back(world_id.to_string());
+        if world_names.order.len() > WORLD_NAME_CAPACITY {
+            if let Some(oldest) = world_names.order.pop_front() {
+                world_names.names.remove(&oldest);
+            }
+        }
+    }
+
+    /// Forgets what has been cached for the account, e.g. after logging out. Requests still in
+    /// flight are no longer shared, so the next session sends its own.
+    pub fn clear_cache(&self) {
+        *self.inner.lock_world_names() = WorldNames::default();
+        self.inner.lock_in_flight().clear();
+    }
+
+    /// Sends a request once the rate limiter allows it. Rate limited, failed and 5xx requests are
+    /// sent again with a growing delay, or after the delay the API asked for, so `request` must
+    /// be safe to repeat.
+    pub async fn send<T, F, Fut>(&self, request: F) -> AppResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        self.send_with_retries(request, MAX_RETRIES).await
+    }
+
+    /// Sends a request that must not be repeated, e.g. verifying a 2FA code, once the rate
+    /// limiter allows it.
+    pub async fn send_once<T, F, Fut>(&self, request: F) -> AppResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        self.send_with_retries(request, 0).await
+    }
+
+    async fn send_with_retries<T, F, Fut>(&self, request: F, max_retries: u32) -> AppResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let mut backoff = BASE_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            self.inner.limiter.acquire().await;
+            let err = match request().await {
+                Ok(value) => return Ok(value),
+                Err(err) if err.is_transient() && attempt < max_retries => err,
+                Err(err) => return Err(err),
+            };
+
+            attempt += 1;
+            let delay = match &err {
+                AppError::RateLimited {
+                    retry_after: Some(seconds),
+                } => {
+                    let delay = Duration::from_secs(*seconds);
+                    self.inner.limiter.pause_until(Instant::now() + delay);
+                    delay
+                }
+                _ => backoff,
+            };
+            log::warn!(
+                "API request failed. Retrying in {} seconds. ({attempt}/{max_retries}): {err}",
+                delay.as_secs_f32()
+            );
+            tokio::time::sleep(delay).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Lets concurrent callers with the same `key` share one request instead of sending their own.
+    pub async fn dedupe<T>(
+        &self,
+        key: String,
+        request: impl Future<Output = AppResult<T>> + Send + 'static,
+    ) -> AppResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.inner.lock_in_flight();
+            let existing = in_flight
+                .get(&key)
+                .and_then(|request| request.downcast_ref::<SharedRequest<T>>())
+                .cloned();
+            match existing {
+                Some(shared) => shared,
+                None => {
+                    let inner = Arc::clone(&self.inner);
+                    let finished_key = key.clone();
+                    let shared = async move {
+                        let result = request.await;
+                        inner.lock_in_flight().remove(&finished_key);
+                        result
+                    }
+                    .boxed()
+                    .shared();
+                    in_flight.insert(key, Box::new(shared.clone()));
+                    shared
+                }
+            }
+        };
+        shared.await
+    }
+
+    /// Sends a GET request with the session's client. Unlike the generated client, this keeps
+    /// the Retry-After header of rate limited responses.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        config: &Configuration,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> AppResult<T> {
+        self.send(|| async move {
+            let mut request = config
+                .client
+                .get(format!("{}{path}", config.base_path))
+                .query(query);
+            if let Some(user_agent) = config.user_agent.as_deref() {
+                request = request.header(USER_AGENT, user_agent);
+            }
+
+            let response = request.send().await?;
+            let status = response.status();
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok());
+            let content = response.text().await?;
+
+            if !status.is_success() {
+                return Err(AppError::from_response(
+                    status.as_u16(),
+                    &content,
+                    retry_after,
+                ));
+            }
+            serde_json::from_str(&content).map_err(|err| {
+                AppError::internal(format!("Unexpected response from '{path}': {err}"))
+            })
+        })
+        .await
+    }
+}
+
+impl ApiInner {
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashMap<String, Box<dyn Any + Send>>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_world_names(&self) -> std::sync::MutexGuard<'_, WorldNames> {
+        self.world_names
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
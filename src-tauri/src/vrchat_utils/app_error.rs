@@ -0,0 +1,246 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use vrchatapi::apis::Error as ApiError;
+
+/// Errors returned to the front end. They are serialized as `{ code, message, ... }` so the
+/// front end can react to `code` and fall back to `message` for display.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// The API rejected the credentials or the session.
+    Unauthorized {
+        message: String,
+    },
+    /// The session has expired and could not be restored.
+    SessionExpired,
+    /// Seconds to wait before trying again, if the API said so.
+    RateLimited {
+        retry_after: Option<u64>,
+    },
+    NotFound {
+        message: String,
+    },
+    /// Any other error response of the API.
+    Api {
+        status: u16,
+        message: String,
+    },
+    /// The API could not be reached.
+    Network {
+        message: String,
+    },
+    InvalidInput {
+        message: String,
+    },
+    InvalidSound {
+        message: String,
+    },
+    /// A message or title template does not parse. `position` counts characters in `field`.
+    InvalidTemplate {
+        field: String,
+        position: usize,
+        message: String,
+    },
+    /// The notification could not be shown.
+    Notification {
+        message: String,
+    },
+    Io {
+        message: String,
+    },
+    Settings {
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Unauthorized { .. } => "unauthorized",
+            AppError::SessionExpired => "sessionExpired",
+            AppError::RateLimited { .. } => "rateLimited",
+            AppError::NotFound { .. } => "notFound",
+            AppError::Api { .. } => "api",
+            AppError::Network { .. } => "network",
+            AppError::InvalidInput { .. } => "invalidInput",
+            AppError::InvalidSound { .. } => "invalidSound",
+            AppError::InvalidTemplate { .. } => "invalidTemplate",
+            AppError::Notification { .. } => "notification",
+            AppError::Io { .. } => "io",
+            AppError::Settings { .. } => "settings",
+            AppError::Internal { .. } => "internal",
+        }
+    }
+
+    /// Maps an error response of the API. Only 401 means the session is gone; a 403 refuses
+    /// one request and is an [`AppError::Api`] like any other status.
+    pub fn from_response(status: u16, content: &str, retry_after: Option<u64>) -> Self {
+        let message = response_message(content);
+        match status {
+            401 => AppError::Unauthorized { message },
+            404 => AppError::NotFound { message },
+            429 => AppError::RateLimited { retry_after },
+            status => AppError::Api { status, message },
+        }
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, AppError::Unauthorized { .. })
+    }
+
+    /// Whether sending the same request again later may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AppError::RateLimited { .. } | AppError::Network { .. } => true,
+            AppError::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        AppError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_sound(message: impl Into<String>) -> Self {
+        AppError::InvalidSound {
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_template(
+        field: impl Into<String>,
+        position: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        AppError::InvalidTemplate {
+            field: field.into(),
+            position,
+            message: message.into(),
+        }
+    }
+
+    pub fn notification(message: impl Into<String>) -> Self {
+        AppError::Notification {
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Unauthorized { message } => write!(f, "Unauthorized: {message}"),
+            AppError::SessionExpired => {
+                write!(f, "Your session has expired. Please log in again.")
+            }
+            AppError::RateLimited {
+                retry_after: Some(seconds),
+            } => write!(f, "Too many requests. Try again in {seconds} seconds."),
+            AppError::RateLimited { retry_after: None } => {
+                write!(f, "Too many requests. Try again later.")
+            }
+            AppError::NotFound { message } => write!(f, "Not found: {message}"),
+            AppError::Api { status, message } => write!(f, "HTTP {status}: {message}"),
+            AppError::Network { message } => write!(f, "Network error: {message}"),
+            AppError::InvalidTemplate {
+                field,
+                position,
+                message,
+            } => write!(f, "{message} ({field}, at character {position})"),
+            AppError::InvalidInput { message }
+            | AppError::InvalidSound { message }
+            | AppError::Notification { message }
+            | AppError::Io { message }
+            | AppError::Settings { message }
+            | AppError::Internal { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        match self {
+            AppError::RateLimited { retry_after } => {
+                state.serialize_field("retryAfter", retry_after)?
+            }
+            AppError::Api { status, .. } => state.serialize_field("status", status)?,
+            AppError::InvalidTemplate {
+                field, position, ..
+            } => {
+                state.serialize_field("field", field)?;
+                state.serialize_field("position", position)?;
+            }
+            _ => state.skip_field("details")?,
+        }
+        state.end()
+    }
+}
+
+impl<T: fmt::Debug> From<ApiError<T>> for AppError {
+    fn from(err: ApiError<T>) -> Self {
+        match err {
+            // The generated client drops the response headers, so Retry-After is unknown here.
+            ApiError::ResponseError(response) => {
+                AppError::from_response(response.status.as_u16(), &response.content, None)
+            }
+            ApiError::Reqwest(err) => err.into(),
+            ApiError::Io(err) => err.into(),
+            err => AppError::internal(err.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        AppError::Network {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Io {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        AppError::Io {
+            message: format!("Database error: {err}"),
+        }
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(err: tauri::Error) -> Self {
+        AppError::internal(err.to_string())
+    }
+}
+
+/// Error bodies look like `{"error":{"message":"\"Not Found\"","status_code":404}}`.
+fn response_message(content: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|body| body["error"]["message"].as_str().map(str::to_string))
+        .map(|message| message.trim_matches('"').to_string())
+        .unwrap_or_else(|| content.to_string())
+}
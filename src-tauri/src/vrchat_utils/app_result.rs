@@ -1 +1,3 @@
-pub type AppResult<T> = Result<T, String>;
+use super::app_error::AppError;
+
+pub type AppResult<T> = Result<T, AppError>;
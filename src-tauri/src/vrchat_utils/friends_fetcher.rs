@@ -1,36 +1,46 @@
 use std::collections::HashSet;
 
-use crate::vrchat_utils::AppResult;
+use crate::vrchat_utils::{ApiService, AppResult};
 use vrchatapi::apis::configuration::Configuration;
-use vrchatapi::apis::friends_api;
 use vrchatapi::models;
 use crate::auth;
 
-pub async fn fetch_all_friends(state: &auth::AuthState) -> AppResult<Vec<models::LimitedUserFriend>> {
+/// Concurrent resyncs share one download.
+pub async fn fetch_all_friends(
+    api: &ApiService,
+    state: &auth::AuthState,
+) -> AppResult<Vec<models::LimitedUserFriend>> {
     let config = state.with_session(|session| session.config.clone())?;
 
-    let online_friends = fetch_friend_pages(&config, false).await?;
-    let offline_friends = fetch_friend_pages(&config, true).await?;
-
-    Ok(merge_friends_by_id(online_friends, offline_friends))
+    let service = api.clone();
+    api.dedupe(String::from("friends"), async move {
+        let online_friends = fetch_friend_pages(&service, &config, false).await?;
+        let offline_friends = fetch_friend_pages(&service, &config, true).await?;
+        Ok(merge_friends_by_id(online_friends, offline_friends))
+    })
+    .await
 }
 
 async fn fetch_friend_pages(
+    api: &ApiService,
     config: &Configuration,
     include_offline: bool,
 ) -> AppResult<Vec<models::LimitedUserFriend>> {
-    const PAGE_SIZE: i32 = 100;
+    const PAGE_SIZE: usize = 100;
 
     let mut offset = 0;
     let mut all = Vec::new();
 
     loop {
-        let page =
-            friends_api::get_friends(config, Some(offset), Some(PAGE_SIZE), Some(include_offline))
-                .await
-                .map_err(|err| err.to_string())?;
+        let query = [
+            ("offset", offset.to_string()),
+            ("n", PAGE_SIZE.to_string()),
+            ("offline", include_offline.to_string()),
+        ];
+        let page: Vec<models::LimitedUserFriend> =
+            api.get_json(config, "/auth/user/friends", &query).await?;
 
-        let page_size = page.len() as i32;
+        let page_size = page.len();
         if page_size == 0 {
             break;
         }
@@ -41,6 +41,19 @@ pub fn resolve_user_icon_url(user: &models::User) -> Option<String> {
         .map(|url| url.to_string())
 }
 
+pub fn resolve_friend_icon_url(friend: &models::LimitedUserFriend) -> Option<String> {
+    [
+        friend.profile_pic_override.as_deref(),
+        friend.user_icon.as_deref(),
+        friend.current_avatar_image_url.as_deref(),
+        friend.current_avatar_thumbnail_image_url.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|url| !url.is_empty())
+    .map(|url| url.to_string())
+}
+
 async fn download_bytes(
     url: &str,
     client: &Client,
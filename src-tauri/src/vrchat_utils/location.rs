@@ -0,0 +1,327 @@
+use crate::vrchat_utils::AppError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+const OFFLINE: &str = "offline";
+const PRIVATE: &str = "private";
+const TRAVELING: &str = "traveling";
+/// Instances without a region tag are in the US.
+const DEFAULT_REGION: &str = "us";
+
+/// Where a user is, as the API and the pipeline report it, e.g.
+/// `wrld_…:12345~hidden(usr_…)~region(jp)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Offline,
+    /// In an instance the user does not share.
+    Private,
+    /// Between two instances.
+    Traveling,
+    Instance(Instance),
+}
+
+/// An instance of a world. Tags are kept in their original order, so formatting gives back the
+/// string that was parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    world_id: String,
+    name: String,
+    tags: Vec<InstanceTag>,
+}
+
+/// One `~`-separated part of an instance after its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceTag {
+    /// Friends+, owned by the user.
+    Hidden(String),
+    Friends(String),
+    /// Invite or Invite+, owned by the user.
+    Private(String),
+    Region(String),
+    Group(String),
+    /// `members`, `plus` or `public`.
+    GroupAccessType(String),
+    Nonce(String),
+    CanRequestInvite,
+    Strict,
+    /// A tag this parser does not know, kept as it was.
+    Other(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessType {
+    Public,
+    FriendsPlus,
+    Friends,
+    InvitePlus,
+    Invite,
+    Group,
+    GroupPlus,
+    GroupPublic,
+}
+
+/// Why a location could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationError {
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for LocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid location '{}': {}", self.location, self.message)
+    }
+}
+
+impl std::error::Error for LocationError {}
+
+impl From<LocationError> for AppError {
+    fn from(err: LocationError) -> Self {
+        AppError::invalid_input(err.to_string())
+    }
+}
+
+impl Location {
+    pub fn parse(location: &str) -> Result<Self, LocationError> {
+        let error = |message: &str| LocationError {
+            location: location.to_string(),
+            message: message.to_string(),
+        };
+
+        match location {
+            OFFLINE => return Ok(Location::Offline),
+            PRIVATE => return Ok(Location::Private),
+            TRAVELING => return Ok(Location::Traveling),
+            _ => {}
+        }
+
+        let Some((world_id, instance)) = location.split_once(':') else {
+            return Err(error(
+                "Expected 'offline', 'private', 'traveling' or an instance.",
+            ));
+        };
+        if world_id.len() <= "wrld_".len() || !world_id.starts_with("wrld_") {
+            return Err(error("The world ID does not start with 'wrld_'."));
+        }
+
+        let mut parts = instance.split('~');
+        let name = parts.next().unwrap_or_default();
+        if name.is_empty() {
+            return Err(error("The instance name is missing."));
+        }
+        let tags = parts
+            .map(|tag| {
+                InstanceTag::parse(tag).ok_or_else(|| error(&format!("'{tag}' is not a tag.")))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Location::Instance(Instance {
+            world_id: world_id.to_string(),
+            name: name.to_string(),
+            tags,
+        }))
+    }
+
+    pub fn instance(&self) -> Option<&Instance> {
+        match self {
+            Location::Instance(instance) => Some(instance),
+            _ => None,
+        }
+    }
+
+    pub fn world_id(&self) -> Option<&str> {
+        self.instance().map(Instance::world_id)
+    }
+
+    /// What the API sends for this kind of location, or `instance`.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Location::Offline => OFFLINE,
+            Location::Private => PRIVATE,
+            Location::Traveling => TRAVELING,
+            Location::Instance(_) => "instance",
+        }
+    }
+}
+
+impl Instance {
+    pub fn world_id(&self) -> &str {
+        &self.world_id
+    }
+
+    /// The part after the world ID that tells instances apart, e.g. "12345".
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn tags(&self) -> &[InstanceTag] {
+        &self.tags
+    }
+
+    pub fn access_type(&self) -> AccessType {
+        let can_request_invite = self.can_request_invite();
+        let mut group_access = None;
+        for tag in &self.tags {
+            match tag {
+                InstanceTag::Hidden(_) => return AccessType::FriendsPlus,
+                InstanceTag::Friends(_) => return AccessType::Friends,
+                InstanceTag::Private(_) if can_request_invite => return AccessType::InvitePlus,
+                InstanceTag::Private(_) => return AccessType::Invite,
+                InstanceTag::Group(_) => group_access = group_access.or(Some(AccessType::Group)),
+                InstanceTag::GroupAccessType(access) => {
+                    group_access = match access.as_str() {
+                        "public" => Some(AccessType::GroupPublic),
+                        "plus" => Some(AccessType::GroupPlus),
+                        _ => Some(AccessType::Group),
+                    }
+                }
+                _ => {}
+            }
+        }
+        group_access.unwrap_or(AccessType::Public)
+    }
+
+    /// The user that opened a Friends+, Friends or Invite instance.
+    pub fn owner(&self) -> Option<&str> {
+        self.tags.iter().find_map(|tag| match tag {
+            InstanceTag::Hidden(owner)
+            | InstanceTag::Friends(owner)
+            | InstanceTag::Private(owner) => Some(owner.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The region code, e.g. "jp".
+    pub fn region(&self) -> &str {
+        self.tags
+            .iter()
+            .find_map(|tag| match tag {
+                InstanceTag::Region(region) => Some(region.as_str()),
+                _ => None,
+            })
+            .unwrap_or(DEFAULT_REGION)
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.tags.iter().find_map(|tag| match tag {
+            InstanceTag::Group(group) => Some(group.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn can_request_invite(&self) -> bool {
+        self.tags.contains(&InstanceTag::CanRequestInvite)
+    }
+}
+
+impl InstanceTag {
+    fn parse(tag: &str) -> Option<Self> {
+        let Some((name, rest)) = tag.split_once('(') else {
+            return match tag {
+                "" => None,
+                "canRequestInvite" => Some(InstanceTag::CanRequestInvite),
+                "strict" => Some(InstanceTag::Strict),
+                _ if tag.contains(')') => None,
+                _ => Some(InstanceTag::Other(tag.to_string())),
+            };
+        };
+        let value = rest.strip_suffix(')')?;
+        if name.is_empty() || value.contains(['(', ')']) {
+            return None;
+        }
+
+        let value = value.to_string();
+        Some(match name {
+            "hidden" => InstanceTag::Hidden(value),
+            "friends" => InstanceTag::Friends(value),
+            "private" => InstanceTag::Private(value),
+            "region" => InstanceTag::Region(value),
+            "group" => InstanceTag::Group(value),
+            "groupAccessType" => InstanceTag::GroupAccessType(value),
+            "nonce" => InstanceTag::Nonce(value),
+            _ => InstanceTag::Other(tag.to_string()),
+        })
+    }
+}
+
+impl AccessType {
+    /// How VRChat names the access type, e.g. "Friends+".
+    pub fn label(self) -> &'static str {
+        match self {
+            AccessType::Public => "Public",
+            AccessType::FriendsPlus => "Friends+",
+            AccessType::Friends => "Friends",
+            AccessType::InvitePlus => "Invite+",
+            AccessType::Invite => "Invite",
+            AccessType::Group => "Group",
+            AccessType::GroupPlus => "Group+",
+            AccessType::GroupPublic => "Group Public",
+        }
+    }
+}
+
+impl FromStr for Location {
+    type Err = LocationError;
+
+    fn from_str(location: &str) -> Result<Self, Self::Err> {
+        Location::parse(location)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Instance(instance) => instance.fmt(f),
+            location => f.write_str(location.kind()),
+        }
+    }
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.world_id, self.name)?;
+        for tag in &self.tags {
+            write!(f, "~{tag}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for InstanceTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceTag::Hidden(value) => write!(f, "hidden({value})"),
+            InstanceTag::Friends(value) => write!(f, "friends({value})"),
+            InstanceTag::Private(value) => write!(f, "private({value})"),
+            InstanceTag::Region(value) => write!(f, "region({value})"),
+            InstanceTag::Group(value) => write!(f, "group({value})"),
+            InstanceTag::GroupAccessType(value) => write!(f, "groupAccessType({value})"),
+            InstanceTag::Nonce(value) => write!(f, "nonce({value})"),
+            InstanceTag::CanRequestInvite => f.write_str("canRequestInvite"),
+            InstanceTag::Strict => f.write_str("strict"),
+            InstanceTag::Other(tag) => f.write_str(tag),
+        }
+    }
+}
+
+/// Serialized as `{ kind, location, ... }`, with the details of the instance for `instance`.
+impl Serialize for Location {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Location", 9)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("location", &self.to_string())?;
+        if let Location::Instance(instance) = self {
+            state.serialize_field("worldId", instance.world_id())?;
+            state.serialize_field("instanceName", instance.name())?;
+            state.serialize_field("accessType", &instance.access_type())?;
+            state.serialize_field("owner", &instance.owner())?;
+            state.serialize_field("region", instance.region())?;
+            state.serialize_field("group", &instance.group())?;
+            state.serialize_field("canRequestInvite", &instance.can_request_invite())?;
+        }
+        state.end()
+    }
+}
@@ -1,7 +1,21 @@
+mod api_service;
+mod app_error;
 mod app_result;
 mod friends_fetcher;
 mod icon_fetcher;
+mod location;
+mod rate_limiter;
+mod world_fetcher;
 
+pub use api_service::ApiService;
+pub use app_error::AppError;
 pub use app_result::AppResult;
 pub use friends_fetcher::fetch_all_friends;
-pub use icon_fetcher::{fetch_user_icon_data_uri, fetch_user_icon_file_uri, resolve_user_icon_url};
+pub use icon_fetcher::{
+    fetch_user_icon_data_uri,
+    fetch_user_icon_file_uri,
+    resolve_friend_icon_url,
+    resolve_user_icon_url,
+};
+pub use location::{AccessType, Instance, InstanceTag, Location, LocationError};
+pub use world_fetcher::{fetch_world, fetch_world_name};
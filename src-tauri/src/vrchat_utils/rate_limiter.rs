@@ -0,0 +1,47 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Spaces requests `interval` apart on average while allowing short bursts of `burst` requests.
+pub struct RateLimiter {
+    interval: Duration,
+    tolerance: Duration,
+    /// When the next request would be sent if there had been no burst.
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration, burst: u32) -> Self {
+        Self {
+            interval,
+            tolerance: interval * burst.saturating_sub(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let wait = {
+            let now = Instant::now();
+            let mut next_slot = self.lock();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.interval;
+            slot.checked_duration_since(now + self.tolerance)
+        };
+        if let Some(wait) = wait.filter(|wait| !wait.is_zero()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Holds back every request until `until`, e.g. after the API asked to slow down.
+    pub fn pause_until(&self, until: Instant) {
+        let mut next_slot = self.lock();
+        *next_slot = (*next_slot).max(until + self.tolerance);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Instant> {
+        self.next_slot
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
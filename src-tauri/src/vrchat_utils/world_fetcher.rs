@@ -0,0 +1,34 @@
+use crate::auth;
+use crate::vrchat_utils::{ApiService, AppResult};
+use vrchatapi::models;
+
+/// Fetches a world. Friends joining the same instance at once share one request.
+pub async fn fetch_world(
+    api: &ApiService,
+    state: &auth::AuthState,
+    world_id: &str,
+) -> AppResult<models::World> {
+    let config = state.with_session(|session| session.config.clone())?;
+
+    let service = api.clone();
+    let path = format!("/worlds/{world_id}");
+    api.dedupe(format!("world:{world_id}"), async move {
+        service.get_json(&config, &path, &[]).await
+    })
+    .await
+}
+
+/// Resolves the display name of a world, from the cache of `api` if it has been seen before.
+pub async fn fetch_world_name(
+    api: &ApiService,
+    state: &auth::AuthState,
+    world_id: &str,
+) -> AppResult<String> {
+    if let Some(name) = api.cached_world_name(world_id) {
+        return Ok(name);
+    }
+
+    let world = fetch_world(api, state, world_id).await?;
+    api.cache_world_name(world_id, &world.name);
+    Ok(world.name)
+}
@@ -1,56 +1,96 @@
-use crate::notifier::notify_friend_online;
-use crate::vrchat_utils::{AppResult};
-use crate::websocket::refresh_friends::emit_refresh_friends;
-use crate::websocket::types::{FriendOnlineEvent, WebsocketMessage};
-use futures_util::StreamExt;
-use log::{error, info, warn};
+use crate::websocket::dispatcher::PipelineDispatcher;
+use crate::websocket::recorder::PipelineRecorder;
+use crate::websocket::types::WebsocketMessage;
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
 use reqwest::Url;
-use tauri::{AppHandle};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::http::HeaderValue;
 use tokio_tungstenite::tungstenite::Message;
 
-const SERVER_URL: &str = "wss://pipeline.vrchat.cloud/";
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// A connection that has not delivered anything, not even a pong, for this long is considered dead.
+const STALE_TIMEOUT: Duration = Duration::from_secs(90);
 
-pub async fn listen(app: &AppHandle, auth_token: &str, user_agent: &str) -> AppResult<()> {
-    listen_raw(auth_token, user_agent, |raw| {
-        let message = match WebsocketMessage::from_str(raw) {
-            Some(message) => message,
-            None => return,
-        };
+/// Why listening to the pipeline stopped.
+#[derive(Debug)]
+pub enum ListenError {
+    /// The pipeline rejected the auth token, so reconnecting with it is pointless.
+    Unauthorized,
+    Failed(String),
+}
 
-        // Send online toast notification
-        if message.is_friend_online_message() {
-            if let Some(event) = message.content_as::<FriendOnlineEvent>() {
-                let app = app.clone();
-                tauri::async_runtime::spawn(async move {
-                    if let Err(err) = notify_friend_online(&app, event).await {
-                        error!("Failed to notify friend online: {}", err);
-                    }
-                });
-            }
+impl From<String> for ListenError {
+    fn from(message: String) -> Self {
+        ListenError::Failed(message)
+    }
+}
+
+impl fmt::Display for ListenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenError::Unauthorized => write!(f, "The pipeline rejected the auth token."),
+            ListenError::Failed(message) => write!(f, "{message}"),
         }
+    }
+}
 
-        // Prompts the front end to update friends view
-        if message.is_friend_message() {
-            let app = app.clone();
-            tauri::async_runtime::spawn(async move {
-                if let Err(err) = emit_refresh_friends(&app).await {
-                    error!("Failed to refresh friends: {}", err);
-                }
-            });
+/// Sent instead of events when the auth token is not (or no longer) valid.
+#[derive(Deserialize)]
+struct Rejection {
+    err: String,
+}
+
+pub async fn listen<R: Runtime>(
+    app: &AppHandle<R>,
+    server_url: &str,
+    auth_token: &str,
+    user_agent: &str,
+    on_connected: impl FnOnce() + Send,
+) -> Result<(), ListenError> {
+    let dispatcher = app.state::<PipelineDispatcher>();
+    let recorder = app.state::<PipelineRecorder>();
+    listen_raw(server_url, auth_token, user_agent, on_connected, |raw| {
+        recorder.record_frame(raw);
+        if is_auth_rejection(raw) {
+            return Err(ListenError::Unauthorized);
         }
+        handle_frame(&dispatcher, raw);
+        Ok(())
     })
     .await
 }
 
-async fn listen_raw<L>(auth_token: &str, user_agent: &str, mut listener: L) -> AppResult<()>
+/// Decodes one raw pipeline frame and dispatches it. Replays go through here as well.
+pub fn handle_frame(dispatcher: &PipelineDispatcher, raw: &str) {
+    let Some(message) = WebsocketMessage::from_str(raw) else {
+        return;
+    };
+    if let Some(event) = message.decode() {
+        dispatcher.dispatch(&event);
+    }
+}
+
+async fn listen_raw<C, L>(
+    server_url: &str,
+    auth_token: &str,
+    user_agent: &str,
+    on_connected: C,
+    mut listener: L,
+) -> Result<(), ListenError>
 where
-    L: FnMut(&str) + Send,
+    C: FnOnce() + Send,
+    L: FnMut(&str) -> Result<(), ListenError> + Send,
 {
-    info!("Connecting to '{}'...", SERVER_URL);
+    info!("Connecting to '{}'...", server_url);
 
-    let url = Url::parse_with_params(SERVER_URL, [("authToken", auth_token)])
+    let url = Url::parse_with_params(server_url, [("authToken", auth_token)])
         .map_err(|err| err.to_string())?;
 
     let mut request = url.into_client_request().map_err(|err| err.to_string())?;
@@ -60,24 +100,55 @@ where
         .headers_mut()
         .insert(reqwest::header::USER_AGENT, user_agent_value);
 
-    let (mut stream, _) = tokio_tungstenite::connect_async(request)
-        .await
-        .map_err(|err| err.to_string())?;
+    let (mut stream, _) = match tokio_tungstenite::connect_async(request).await {
+        Ok(connection) => connection,
+        Err(tungstenite::Error::Http(response))
+            if matches!(response.status().as_u16(), 401 | 403) =>
+        {
+            return Err(ListenError::Unauthorized);
+        }
+        Err(err) => return Err(ListenError::Failed(err.to_string())),
+    };
+
+    info!("Connected to '{}'.", server_url);
+    on_connected();
 
-    info!("Connected to '{}'.", SERVER_URL);
+    let mut last_received = Instant::now();
+    let mut next_ping = Instant::now() + PING_INTERVAL;
 
-    while let Some(message) = stream.next().await {
+    loop {
+        let message = match tokio::time::timeout_at(next_ping, stream.next()).await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(_) => {
+                let silence = last_received.elapsed();
+                if silence >= STALE_TIMEOUT {
+                    return Err(ListenError::Failed(format!(
+                        "Connection is stale. (Nothing received for {} seconds)",
+                        silence.as_secs()
+                    )));
+                }
+                stream
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|err| err.to_string())?;
+                next_ping = Instant::now() + PING_INTERVAL;
+                continue;
+            }
+        };
+
+        last_received = Instant::now();
         match message {
             Ok(Message::Text(text)) => {
-                listener(&text);
+                listener(&text)?;
             }
             Ok(Message::Binary(binary)) => {
                 if let Ok(text) = String::from_utf8(binary) {
-                    listener(&text);
+                    listener(&text)?;
                 }
             }
             Ok(_) => {}
-            Err(err) => return Err(err.to_string()),
+            Err(err) => return Err(ListenError::Failed(err.to_string())),
         }
     }
 
@@ -85,3 +156,8 @@ where
 
     Ok(())
 }
+
+fn is_auth_rejection(raw: &str) -> bool {
+    serde_json::from_str::<Rejection>(raw)
+        .is_ok_and(|rejection| rejection.err.contains("authToken"))
+}
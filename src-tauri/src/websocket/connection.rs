@@ -0,0 +1,41 @@
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// Whether pipeline events, and therefore notifications, are currently being received.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ConnectionState {
+    Connecting,
+    Connected {
+        /// Unix timestamp in milliseconds.
+        since: u64,
+    },
+    Reconnecting {
+        delay_secs: u64,
+        reason: String,
+    },
+    Disconnected {
+        reason: Option<String>,
+    },
+}
+
+impl Default for ConnectionState {
+    fn default() -> Self {
+        ConnectionState::Disconnected { reason: None }
+    }
+}
+
+impl ConnectionState {
+    pub fn connected_now() -> Self {
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+        ConnectionState::Connected { since }
+    }
+
+    pub fn emit<R: Runtime>(&self, app: &AppHandle<R>) {
+        let _ = app.emit("vrc:connection-state", self);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::websocket::types::{PipelineEvent, PipelineEventKind};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+type PipelineHandler = Arc<dyn Fn(&PipelineEvent) + Send + Sync>;
+
+/// Routes decoded pipeline events to the handlers subscribed to their variant.
+#[derive(Default)]
+pub struct PipelineDispatcher {
+    handlers: RwLock<HashMap<PipelineEventKind, Vec<PipelineHandler>>>,
+}
+
+impl PipelineDispatcher {
+    pub fn subscribe(
+        &self,
+        kind: PipelineEventKind,
+        handler: impl Fn(&PipelineEvent) + Send + Sync + 'static,
+    ) {
+        self.subscribe_all(&[kind], handler);
+    }
+
+    pub fn subscribe_all(
+        &self,
+        kinds: &[PipelineEventKind],
+        handler: impl Fn(&PipelineEvent) + Send + Sync + 'static,
+    ) {
+        let handler: PipelineHandler = Arc::new(handler);
+        let mut handlers = self
+            .handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for kind in kinds {
+            handlers.entry(*kind).or_default().push(handler.clone());
+        }
+    }
+
+    /// Calls the handlers of the event's variant in the order they were subscribed.
+    pub fn dispatch(&self, event: &PipelineEvent) {
+        let handlers = self
+            .handlers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&event.kind())
+            .cloned()
+            .unwrap_or_default();
+
+        for handler in handlers {
+            handler(event);
+        }
+    }
+}
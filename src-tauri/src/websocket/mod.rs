@@ -1,8 +1,15 @@
 mod types;
 mod client;
+mod connection;
+mod dispatcher;
+mod recorder;
+mod replay;
 mod state;
-mod refresh_friends;
 
-pub use client::listen;
+pub use client::{listen, ListenError};
+pub use connection::ConnectionState;
+pub use dispatcher::PipelineDispatcher;
+pub use recorder::{register_roster_snapshots, PipelineRecorder};
+pub use replay::{replay, ReplaySpeed};
 pub use types::*;
 pub use state::*;
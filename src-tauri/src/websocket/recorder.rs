@@ -0,0 +1,199 @@
+use crate::roster::{FriendRoster, ResyncReason};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+use vrchatapi::models;
+
+const RECORDING_ENV: &str = "VFRIENDS_RECORD_PIPELINE";
+const DIRECTORY_NAME: &str = "pipeline";
+const FILE_STEM: &str = "pipeline";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the current one, as `pipeline.1.jsonl` (newest) and so on.
+const KEPT_ROTATIONS: usize = 4;
+
+/// One line of a recording.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RecordedLine {
+    /// A raw frame as received from the pipeline.
+    Frame { received_at: u64, frame: String },
+    /// The roster after a resync, so a replay starts from the same state.
+    Roster {
+        received_at: u64,
+        reason: ResyncReason,
+        friends: Vec<models::LimitedUserFriend>,
+    },
+}
+
+impl RecordedLine {
+    pub fn received_at(&self) -> u64 {
+        match self {
+            RecordedLine::Frame { received_at, .. } => *received_at,
+            RecordedLine::Roster { received_at, .. } => *received_at,
+        }
+    }
+}
+
+/// Appends pipeline traffic to rotating JSONL files while enabled.
+pub struct PipelineRecorder {
+    directory: PathBuf,
+    max_file_bytes: u64,
+    enabled: AtomicBool,
+    file: Mutex<Option<File>>,
+}
+
+impl PipelineRecorder {
+    pub fn new(directory: PathBuf, enabled: bool) -> Self {
+        Self {
+            directory,
+            max_file_bytes: MAX_FILE_BYTES,
+            enabled: AtomicBool::new(enabled),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Rotates files at `max_file_bytes` instead of 5 MiB.
+    #[cfg(feature = "test-support")]
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// Records into the log directory of the app. `VFRIENDS_RECORD_PIPELINE=1` enables recording
+    /// regardless of the settings.
+    pub fn for_app<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Self {
+        let directory = app
+            .path()
+            .app_log_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("vfriends"))
+            .join(DIRECTORY_NAME);
+        let forced = std::env::var(RECORDING_ENV).is_ok_and(|value| value == "1");
+        Self::new(directory, enabled || forced)
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            *self.lock() = None;
+        }
+    }
+
+    pub fn record_frame(&self, raw: &str) {
+        self.append(&RecordedLine::Frame {
+            received_at: unix_millis(),
+            frame: raw.to_string(),
+        });
+    }
+
+    pub fn record_roster(&self, reason: ResyncReason, friends: Vec<models::LimitedUserFriend>) {
+        self.append(&RecordedLine::Roster {
+            received_at: unix_millis(),
+            reason,
+            friends,
+        });
+    }
+
+    fn append(&self, line: &RecordedLine) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Ok(mut json) = serde_json::to_string(line) else {
+            return;
+        };
+        json.push('\n');
+
+        let mut file = self.lock();
+        if let Err(err) = self.write_line(&mut file, &json) {
+            warn!("Failed to record pipeline traffic: {err}");
+            *file = None;
+        }
+    }
+
+    fn write_line(&self, file: &mut Option<File>, line: &str) -> std::io::Result<()> {
+        let current = self.file_path(0);
+        let is_full = std::fs::metadata(&current)
+            .is_ok_and(|metadata| metadata.len() + line.len() as u64 > self.max_file_bytes);
+        if is_full {
+            *file = None;
+            self.rotate()?;
+        }
+
+        if file.is_none() {
+            std::fs::create_dir_all(&self.directory)?;
+            *file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&current)?,
+            );
+        }
+        if let Some(file) = file.as_mut() {
+            file.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        let oldest = self.file_path(KEPT_ROTATIONS);
+        if oldest.exists() {
+            std::fs::remove_file(oldest)?;
+        }
+        for index in (0..KEPT_ROTATIONS).rev() {
+            let path = self.file_path(index);
+            if path.exists() {
+                std::fs::rename(path, self.file_path(index + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn file_path(&self, rotation: usize) -> PathBuf {
+        let name = match rotation {
+            0 => format!("{FILE_STEM}.jsonl"),
+            rotation => format!("{FILE_STEM}.{rotation}.jsonl"),
+        };
+        self.directory.join(name)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<File>> {
+        self.file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Records the roster after every resync, as replays cannot download it.
+pub fn register_roster_snapshots<R: Runtime>(app: &AppHandle<R>, roster: &FriendRoster) {
+    let app = app.clone();
+    roster.subscribe_resync(move |reason, _| {
+        let recorder = app.state::<PipelineRecorder>();
+        if recorder.is_enabled() {
+            recorder.record_roster(reason, app.state::<FriendRoster>().friends());
+        }
+    });
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,117 @@
+use crate::notifier::{self, NotifierScope};
+use crate::roster::FriendRoster;
+use crate::vrchat_utils::AppResult;
+use crate::websocket::client::handle_frame;
+use crate::websocket::dispatcher::PipelineDispatcher;
+use crate::websocket::recorder::RecordedLine;
+use crate::websocket::types::PipelineEventKind;
+use log::{info, warn};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// How fast recorded traffic is fed back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Without any waiting.
+    Instant,
+    /// Keeps the recorded gaps, divided by the factor. `1.0` is real time.
+    Scaled(f64),
+}
+
+impl ReplaySpeed {
+    pub fn from_factor(factor: Option<f64>) -> Self {
+        match factor {
+            Some(factor) if factor.is_finite() && factor > 0.0 => ReplaySpeed::Scaled(factor),
+            _ => ReplaySpeed::Instant,
+        }
+    }
+
+    fn delay(self, gap_ms: u64) -> Option<Duration> {
+        match self {
+            ReplaySpeed::Instant => None,
+            ReplaySpeed::Scaled(factor) => {
+                Some(Duration::from_secs_f64(gap_ms as f64 / 1000.0 / factor))
+            }
+        }
+    }
+}
+
+/// Feeds a recording through the same frame handler as the live pipeline.
+/// The frames patch a copy of the roster that only the notifier listens to, and the notifier
+/// decides with a [`NotifierScope::detached`] of its own. So a replay shows the notifications
+/// without touching the live roster, the saved settings, the presence or notification history,
+/// or the flap suppression and quiet hours digest of the live pipeline. Roster snapshots in the
+/// recording replace the copy, so notifications that depend on the last known state of a friend
+/// come out the same. The copy and its listeners are dropped when the replay ends.
+/// Returns the number of frames replayed.
+pub async fn replay<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    speed: ReplaySpeed,
+) -> AppResult<usize> {
+    let contents = std::fs::read_to_string(path)?;
+    info!("Replaying pipeline recording '{}'...", path.display());
+
+    let scope = NotifierScope::detached();
+    let roster = Arc::new(app.state::<FriendRoster>().fork());
+    let dispatcher = replay_dispatcher(app, &roster, &scope);
+    notifier::register_roster_listeners(app, &roster, &scope);
+
+    let mut previous_at = None;
+    let mut frames = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line: RecordedLine = match serde_json::from_str(line) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!("Skipped line {} of the recording: {err}", index + 1);
+                continue;
+            }
+        };
+
+        let received_at = line.received_at();
+        let gap = previous_at.map_or(0, |previous| received_at.saturating_sub(previous));
+        previous_at = Some(received_at);
+        if let Some(delay) = speed.delay(gap).filter(|delay| !delay.is_zero()) {
+            tokio::time::sleep(delay).await;
+        }
+
+        match line {
+            RecordedLine::Frame { frame, .. } => {
+                handle_frame(&dispatcher, &frame);
+                frames += 1;
+            }
+            RecordedLine::Roster {
+                reason, friends, ..
+            } => roster.replace_all(friends, reason),
+        }
+    }
+
+    info!("Replayed {frames} pipeline frames.");
+    Ok(frames)
+}
+
+/// A dispatcher with the notifier and a roster patcher for `roster`. A drifted roster is not
+/// resynced, as the recording is all there is.
+fn replay_dispatcher<R: Runtime>(
+    app: &AppHandle<R>,
+    roster: &Arc<FriendRoster>,
+    scope: &NotifierScope,
+) -> PipelineDispatcher {
+    let dispatcher = PipelineDispatcher::default();
+    notifier::register_pipeline_handlers(app, &dispatcher, scope);
+    let roster = Arc::clone(roster);
+    dispatcher.subscribe_all(&PipelineEventKind::FRIEND_EVENTS, move |event| {
+        if !roster.apply(event) {
+            warn!(
+                "Replayed event did not fit the roster. (type={:?})",
+                event.kind()
+            );
+        }
+    });
+    dispatcher
+}
@@ -1,50 +1,111 @@
-use crate::websocket;
+use crate::auth::AuthState;
+use crate::roster::{self, FriendRoster, ResyncReason};
+use crate::websocket::{ConnectionState, ListenError};
+use crate::{presence, shell, websocket};
 use cookie::Cookie;
 use log::{debug, info, warn};
 use std::sync::Mutex;
 use std::time::Duration;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager, Runtime};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+const BASE_DELAY: Duration = Duration::from_secs(5);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// The delay before the `attempt`th retry, counting from zero. Doubles up to `MAX_DELAY`.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    BASE_DELAY
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(MAX_DELAY, |delay| delay.min(MAX_DELAY))
+}
 
 #[derive(Default)]
 pub struct WebsocketState {
     task: Mutex<Option<JoinHandle<()>>>,
+    connection: Mutex<ConnectionState>,
 }
 
 impl WebsocketState {
-    pub fn start(&self, app: &AppHandle, auth_token: String, user_agent: Option<String>) {
-        self.stop();
+    pub fn start<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        server_url: String,
+        auth_token: String,
+        user_agent: Option<String>,
+    ) {
+        self.abort_task();
 
         let app = app.clone();
         let handle = tokio::spawn(async move {
             let user_agent = user_agent.unwrap_or_else(|| app.config().identifier.clone());
 
-            let base_delay = Duration::from_secs(5);
-            let max_delay = Duration::from_secs(60);
-
-            let mut delay = base_delay;
+            let mut delay = BASE_DELAY;
 
             loop {
                 let app = app.clone();
 
-                if delay != base_delay {
+                if delay != BASE_DELAY {
                     info!("Websocket reconnecting...");
                 }
+                set_connection_state(&app, ConnectionState::Connecting);
 
-                let result = websocket::listen(&app, &auth_token, &user_agent).await;
+                // Events sent while disconnected are lost, so the roster is resynced on every connection.
+                // A roster kept from before, e.g. across a restored session, is resynced as a reconnect.
+                let mut connected_at = None;
+                let result = websocket::listen(&app, &server_url, &auth_token, &user_agent, || {
+                    let reason = if app.state::<FriendRoster>().is_seeded() {
+                        ResyncReason::Reconnect
+                    } else {
+                        ResyncReason::Seed
+                    };
+                    connected_at = Some(Instant::now());
+                    set_connection_state(&app, ConnectionState::connected_now());
+                    roster::spawn_resync(&app, reason);
+                })
+                .await;
+                // Whatever happens while disconnected is only found out by the next resync.
+                presence::stop_watching(&app);
 
                 match result {
                     Ok(()) => {
                         debug!("Websocket listen ended without error. Resettings backoff.");
-                        delay = base_delay;
+                        delay = BASE_DELAY;
                     }
-                    Err(err) => {
+                    Err(ListenError::Unauthorized) => {
+                        // Reconnecting with the same token would be rejected forever.
+                        warn!("Websocket rejected the auth token. Restoring the session...");
+                        set_connection_state(
+                            &app,
+                            ConnectionState::Disconnected {
+                                reason: Some(String::from("Session rejected")),
+                            },
+                        );
+                        // A connection that stayed up for a while does not count toward the cap.
+                        let was_stable = connected_at.is_some_and(|at| at.elapsed() >= MAX_DELAY);
+                        let recovery_app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            recovery_app
+                                .state::<AuthState>()
+                                .recover_rejected_session(&recovery_app, was_stable)
+                                .await;
+                        });
+                        break;
+                    }
+                    Err(ListenError::Failed(err)) => {
                         warn!(
                             "Websocket disconnected. (Will try again in {} seconds): {err}",
                             delay.as_secs()
                         );
+                        set_connection_state(
+                            &app,
+                            ConnectionState::Reconnecting {
+                                delay_secs: delay.as_secs(),
+                                reason: err,
+                            },
+                        );
                         tokio::time::sleep(delay).await;
-                        delay = std::cmp::min(delay * 2, max_delay);
+                        delay = std::cmp::min(delay * 2, MAX_DELAY);
                     }
                 }
             }
@@ -54,9 +115,10 @@ impl WebsocketState {
         *guard = Some(handle);
     }
 
-    pub fn start_with_cookie_header(
+    pub fn start_with_cookie_header<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
+        server_url: String,
         cookie_header: Option<String>,
         user_agent: Option<String>,
     ) {
@@ -79,13 +141,42 @@ impl WebsocketState {
             return;
         };
 
-        self.start(app, auth_token, user_agent);
+        self.start(app, server_url, auth_token, user_agent);
     }
 
-    pub fn stop(&self) {
+    pub fn stop<R: Runtime>(&self, app: &AppHandle<R>, reason: Option<String>) {
+        self.abort_task();
+        presence::stop_watching(app);
+        set_connection_state(app, ConnectionState::Disconnected { reason });
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn abort_task(&self) {
         let mut guard = self.task.lock().expect("Websocket task lock poisoned.");
         if let Some(handle) = guard.take() {
             handle.abort();
         }
     }
 }
+
+fn set_connection_state<R: Runtime>(app: &AppHandle<R>, state: ConnectionState) {
+    let websocket = app.state::<WebsocketState>();
+    {
+        let mut current = websocket
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *current == state {
+            return;
+        }
+        *current = state.clone();
+    }
+    state.emit(app);
+    shell::show_connection_state(app, &state);
+}
@@ -9,6 +9,7 @@ use vrchatapi::models;
 pub struct WebsocketMessage {
     #[serde(rename = "type")]
     pub kind: String,
+    #[serde(default)]
     pub content: String,
 }
 
@@ -37,48 +38,166 @@ impl WebsocketMessage {
             .ok()
     }
 
-    pub fn is_friend_add_message(&self) -> bool {
-        self.kind == "friend-add"
-    }
-
-    pub fn is_friend_delete_message(&self) -> bool {
-        self.kind == "friend-delete"
-    }
-
-    pub fn is_friend_active_message(&self) -> bool {
-        self.kind == "friend-active"
-    }
-
-    pub fn is_friend_online_message(&self) -> bool {
-        self.kind == "friend-online"
+    /// Decodes the content into the typed event of this message's type.
+    /// Returns `None` if the content of a known type could not be parsed.
+    pub fn decode(&self) -> Option<PipelineEvent> {
+        let event = match self.kind.as_str() {
+            "friend-add" => PipelineEvent::FriendAdd(self.content_as()?),
+            "friend-delete" => PipelineEvent::FriendDelete(self.content_as()?),
+            "friend-active" => PipelineEvent::FriendActive(self.content_as()?),
+            "friend-online" => PipelineEvent::FriendOnline(self.content_as()?),
+            "friend-offline" => PipelineEvent::FriendOffline(self.content_as()?),
+            "friend-update" => PipelineEvent::FriendUpdate(self.content_as()?),
+            "friend-location" => PipelineEvent::FriendLocation(self.content_as()?),
+            "user-update" => PipelineEvent::UserUpdate(self.content_as()?),
+            "user-location" => PipelineEvent::UserLocation(self.content_as()?),
+            "user-badge-assigned" => PipelineEvent::UserBadgeAssigned(self.content_as()?),
+            "user-badge-unassigned" => PipelineEvent::UserBadgeUnassigned(self.content_as()?),
+            "content-refresh" => PipelineEvent::ContentRefresh(self.content_as()?),
+            "instance-queue-joined" => PipelineEvent::InstanceQueueJoined(self.content_as()?),
+            "instance-queue-ready" => PipelineEvent::InstanceQueueReady(self.content_as()?),
+            "notification" => PipelineEvent::Notification(self.content_as()?),
+            "response-notification" => PipelineEvent::ResponseNotification(self.content_as()?),
+            "see-notification" => PipelineEvent::SeeNotification(self.content.clone()),
+            "hide-notification" => PipelineEvent::HideNotification(self.content.clone()),
+            "clear-notification" => PipelineEvent::ClearNotification,
+            "notification-v2" => PipelineEvent::NotificationV2(self.content_as()?),
+            "notification-v2-update" => PipelineEvent::NotificationV2Update(self.content_as()?),
+            "notification-v2-delete" => PipelineEvent::NotificationV2Delete(self.content_as()?),
+            "group-joined" => PipelineEvent::GroupJoined(self.content_as()?),
+            "group-left" => PipelineEvent::GroupLeft(self.content_as()?),
+            "group-member-updated" => PipelineEvent::GroupMemberUpdated(self.content_as()?),
+            "group-role-updated" => PipelineEvent::GroupRoleUpdated(self.content_as()?),
+            _ => PipelineEvent::Unknown {
+                kind: self.kind.clone(),
+                content: serde_json::from_str(&self.content)
+                    .unwrap_or_else(|_| serde_json::Value::String(self.content.clone())),
+            },
+        };
+        Some(event)
     }
+}
 
-    pub fn is_friend_offline_message(&self) -> bool {
-        self.kind == "friend-offline"
-    }
+/// A decoded pipeline message.
+/// See also, [vrchat.community](https://vrchat.community/websocket).
+#[derive(Debug, Clone)]
+pub enum PipelineEvent {
+    FriendAdd(FriendAddEvent),
+    FriendDelete(FriendDeleteEvent),
+    FriendActive(FriendActiveEvent),
+    FriendOnline(FriendOnlineEvent),
+    FriendOffline(FriendOfflineEvent),
+    FriendUpdate(FriendUpdateEvent),
+    FriendLocation(FriendLocationEvent),
+    UserUpdate(UserUpdateEvent),
+    UserLocation(UserLocationEvent),
+    UserBadgeAssigned(UserBadgeAssignedEvent),
+    UserBadgeUnassigned(UserBadgeUnassignedEvent),
+    ContentRefresh(ContentRefreshEvent),
+    InstanceQueueJoined(InstanceQueueJoinedEvent),
+    InstanceQueueReady(InstanceQueueReadyEvent),
+    Notification(models::Notification),
+    ResponseNotification(ResponseNotificationEvent),
+    /// Carries the id of the notification that has been seen.
+    SeeNotification(String),
+    /// Carries the id of the notification that should be hidden.
+    HideNotification(String),
+    ClearNotification,
+    NotificationV2(serde_json::Value),
+    NotificationV2Update(NotificationV2UpdateEvent),
+    NotificationV2Delete(NotificationV2DeleteEvent),
+    GroupJoined(GroupJoinedEvent),
+    GroupLeft(GroupLeftEvent),
+    GroupMemberUpdated(GroupMemberUpdatedEvent),
+    GroupRoleUpdated(GroupRoleUpdatedEvent),
+    /// A message type this app does not know about, kept as raw JSON.
+    Unknown {
+        kind: String,
+        content: serde_json::Value,
+    },
+}
 
-    pub fn is_friend_update_message(&self) -> bool {
-        self.kind == "friend-update"
-    }
+/// The variant of a [`PipelineEvent`] without its payload, used to subscribe to events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PipelineEventKind {
+    FriendAdd,
+    FriendDelete,
+    FriendActive,
+    FriendOnline,
+    FriendOffline,
+    FriendUpdate,
+    FriendLocation,
+    UserUpdate,
+    UserLocation,
+    UserBadgeAssigned,
+    UserBadgeUnassigned,
+    ContentRefresh,
+    InstanceQueueJoined,
+    InstanceQueueReady,
+    Notification,
+    ResponseNotification,
+    SeeNotification,
+    HideNotification,
+    ClearNotification,
+    NotificationV2,
+    NotificationV2Update,
+    NotificationV2Delete,
+    GroupJoined,
+    GroupLeft,
+    GroupMemberUpdated,
+    GroupRoleUpdated,
+    Unknown,
+}
 
-    pub fn is_friend_location_message(&self) -> bool {
-        self.kind == "friend-location"
-    }
+impl PipelineEventKind {
+    pub const FRIEND_EVENTS: [PipelineEventKind; 7] = [
+        PipelineEventKind::FriendAdd,
+        PipelineEventKind::FriendDelete,
+        PipelineEventKind::FriendActive,
+        PipelineEventKind::FriendOnline,
+        PipelineEventKind::FriendOffline,
+        PipelineEventKind::FriendUpdate,
+        PipelineEventKind::FriendLocation,
+    ];
+}
 
-    pub fn is_friend_message(&self) -> bool {
-        self.is_friend_add_message()
-            || self.is_friend_delete_message()
-            || self.is_friend_active_message()
-            || self.is_friend_online_message()
-            || self.is_friend_offline_message()
-            || self.is_friend_update_message()
-            || self.is_friend_location_message()
+impl PipelineEvent {
+    pub fn kind(&self) -> PipelineEventKind {
+        match self {
+            PipelineEvent::FriendAdd(_) => PipelineEventKind::FriendAdd,
+            PipelineEvent::FriendDelete(_) => PipelineEventKind::FriendDelete,
+            PipelineEvent::FriendActive(_) => PipelineEventKind::FriendActive,
+            PipelineEvent::FriendOnline(_) => PipelineEventKind::FriendOnline,
+            PipelineEvent::FriendOffline(_) => PipelineEventKind::FriendOffline,
+            PipelineEvent::FriendUpdate(_) => PipelineEventKind::FriendUpdate,
+            PipelineEvent::FriendLocation(_) => PipelineEventKind::FriendLocation,
+            PipelineEvent::UserUpdate(_) => PipelineEventKind::UserUpdate,
+            PipelineEvent::UserLocation(_) => PipelineEventKind::UserLocation,
+            PipelineEvent::UserBadgeAssigned(_) => PipelineEventKind::UserBadgeAssigned,
+            PipelineEvent::UserBadgeUnassigned(_) => PipelineEventKind::UserBadgeUnassigned,
+            PipelineEvent::ContentRefresh(_) => PipelineEventKind::ContentRefresh,
+            PipelineEvent::InstanceQueueJoined(_) => PipelineEventKind::InstanceQueueJoined,
+            PipelineEvent::InstanceQueueReady(_) => PipelineEventKind::InstanceQueueReady,
+            PipelineEvent::Notification(_) => PipelineEventKind::Notification,
+            PipelineEvent::ResponseNotification(_) => PipelineEventKind::ResponseNotification,
+            PipelineEvent::SeeNotification(_) => PipelineEventKind::SeeNotification,
+            PipelineEvent::HideNotification(_) => PipelineEventKind::HideNotification,
+            PipelineEvent::ClearNotification => PipelineEventKind::ClearNotification,
+            PipelineEvent::NotificationV2(_) => PipelineEventKind::NotificationV2,
+            PipelineEvent::NotificationV2Update(_) => PipelineEventKind::NotificationV2Update,
+            PipelineEvent::NotificationV2Delete(_) => PipelineEventKind::NotificationV2Delete,
+            PipelineEvent::GroupJoined(_) => PipelineEventKind::GroupJoined,
+            PipelineEvent::GroupLeft(_) => PipelineEventKind::GroupLeft,
+            PipelineEvent::GroupMemberUpdated(_) => PipelineEventKind::GroupMemberUpdated,
+            PipelineEvent::GroupRoleUpdated(_) => PipelineEventKind::GroupRoleUpdated,
+            PipelineEvent::Unknown { .. } => PipelineEventKind::Unknown,
+        }
     }
 }
 
 /// This event is sent when the user has either accepted a friend request, or has had one of their friend requests accepted.
 /// See also, [vrchat.community](https://vrchat.community/websocket#friend-add)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FriendAddEvent {
     pub user_id: String,
@@ -87,7 +206,7 @@ pub struct FriendAddEvent {
 
 /// This event is sent when the user has either been removed as a friend, or has removed someone else as a friend.
 /// See also, [vrchat.community](https://vrchat.community/websocket#friend-delete)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FriendDeleteEvent {
     pub user_id: String,
@@ -95,7 +214,7 @@ pub struct FriendDeleteEvent {
 
 /// This event is sent when one of the user's friends is active on the website.
 /// See also, [vrchat.community](https://vrchat.community/websocket#friend-active)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FriendActiveEvent {
     #[serde(rename = "userid", alias = "userId")]
     pub user_id: String,
@@ -117,7 +236,7 @@ pub struct FriendOnlineEvent {
 
 /// This event is sent when one of the user's friend has gone offline.
 /// See also, [vrchat.community](https://vrchat.community/websocket#friend-offline)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FriendOfflineEvent {
     pub user_id: String,
@@ -126,7 +245,7 @@ pub struct FriendOfflineEvent {
 
 /// This event is sent when something about one of the user's friends profile has changed.
 /// See also, [vrchat.community](https://vrchat.community/websocket#friend-update)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FriendUpdateEvent {
     pub user_id: String,
@@ -135,7 +254,7 @@ pub struct FriendUpdateEvent {
 
 /// This event is sent when one of the user's friends has changed instances.
 /// See also, [vrchat.community](https://vrchat.community/websocket#friend-location)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FriendLocationEvent {
     pub user_id: String,
@@ -145,3 +264,164 @@ pub struct FriendLocationEvent {
     pub can_request_invite: Option<bool>,
     pub user: models::User,
 }
+
+/// This event is sent when something about the user's own profile has changed.
+/// See also, [vrchat.community](https://vrchat.community/websocket#user-update)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserUpdateEvent {
+    pub user_id: String,
+    pub user: UpdatedUser,
+}
+
+/// The subset of the current user that is sent with a `user-update` event.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatedUser {
+    pub id: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub status: Option<models::UserStatus>,
+    #[serde(default)]
+    pub status_description: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub current_avatar: Option<String>,
+    #[serde(default)]
+    pub current_avatar_image_url: Option<String>,
+    #[serde(default)]
+    pub current_avatar_thumbnail_image_url: Option<String>,
+    #[serde(default)]
+    pub profile_pic_override: Option<String>,
+    #[serde(default)]
+    pub user_icon: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// This event is sent when the user has changed instances.
+/// See also, [vrchat.community](https://vrchat.community/websocket#user-location)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserLocationEvent {
+    pub user_id: String,
+    pub location: String,
+    #[serde(default)]
+    pub instance: Option<String>,
+    #[serde(default)]
+    pub world_id: Option<String>,
+    #[serde(default)]
+    pub traveling_to_location: Option<String>,
+}
+
+/// This event is sent when the user has been given a badge.
+/// See also, [vrchat.community](https://vrchat.community/websocket#user-badge-assigned)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserBadgeAssignedEvent {
+    pub badge: serde_json::Value,
+}
+
+/// This event is sent when a badge has been taken away from the user.
+/// See also, [vrchat.community](https://vrchat.community/websocket#user-badge-unassigned)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserBadgeUnassignedEvent {
+    pub badge_id: String,
+}
+
+/// This event is sent when content owned by the user (avatars, worlds, images, ...) has changed.
+/// See also, [vrchat.community](https://vrchat.community/websocket#content-refresh)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRefreshEvent {
+    pub content_type: String,
+    #[serde(default)]
+    pub file_id: Option<String>,
+    #[serde(default)]
+    pub item_id: Option<String>,
+    #[serde(default)]
+    pub item_type: Option<String>,
+    #[serde(default)]
+    pub action_type: Option<String>,
+}
+
+/// This event is sent when the user has joined the queue of an instance.
+/// See also, [vrchat.community](https://vrchat.community/websocket#instance-queue-joined)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceQueueJoinedEvent {
+    pub instance_location: String,
+    pub position: i32,
+}
+
+/// This event is sent when the user can join the instance they have been queued for.
+/// See also, [vrchat.community](https://vrchat.community/websocket#instance-queue-ready)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceQueueReadyEvent {
+    pub instance_location: String,
+    pub expiry: String,
+}
+
+/// This event is sent when someone has responded to one of the user's notifications.
+/// See also, [vrchat.community](https://vrchat.community/websocket#response-notification)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseNotificationEvent {
+    pub notification_id: String,
+    pub receiver_id: String,
+    pub response_id: String,
+}
+
+/// This event is sent when a v2 notification has been updated.
+/// See also, [vrchat.community](https://vrchat.community/websocket#notification-v2-update)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationV2UpdateEvent {
+    pub id: String,
+    pub version: i32,
+    pub updates: serde_json::Value,
+}
+
+/// This event is sent when v2 notifications have been deleted.
+/// See also, [vrchat.community](https://vrchat.community/websocket#notification-v2-delete)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationV2DeleteEvent {
+    pub ids: Vec<String>,
+    pub version: i32,
+}
+
+/// This event is sent when the user has joined a group.
+/// See also, [vrchat.community](https://vrchat.community/websocket#group-joined)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupJoinedEvent {
+    pub group_id: String,
+}
+
+/// This event is sent when the user has left a group.
+/// See also, [vrchat.community](https://vrchat.community/websocket#group-left)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupLeftEvent {
+    pub group_id: String,
+}
+
+/// This event is sent when the user's membership of a group has changed.
+/// See also, [vrchat.community](https://vrchat.community/websocket#group-member-updated)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupMemberUpdatedEvent {
+    pub member: serde_json::Value,
+}
+
+/// This event is sent when a role the user holds in a group has changed.
+/// See also, [vrchat.community](https://vrchat.community/websocket#group-role-updated)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRoleUpdatedEvent {
+    pub role: serde_json::Value,
+}
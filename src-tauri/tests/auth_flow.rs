@@ -0,0 +1,245 @@
+mod common;
+
+use common::{is_auth_event, logged_in_app, TIMEOUT};
+use tauri::Manager;
+use vfriends_lib::test_support::{mock_app, AuthState, EventLog, MockAccount, MockServer};
+
+async fn begin_auth(
+    server: &MockServer,
+    password: &str,
+) -> (tauri::App<tauri::test::MockRuntime>, EventLog) {
+    let app = mock_app(server.endpoints());
+    let auth_events = EventLog::listen(app.handle(), "vrc:auth");
+    app.state::<AuthState>()
+        .begin_auth_flow(
+            app.handle(),
+            String::from("mock-user"),
+            password.to_string(),
+        )
+        .await
+        .expect("Auth flow failed.");
+    (app, auth_events)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn logs_in_without_two_factor() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let (app, auth_events) = begin_auth(&server, "mock-password").await;
+
+    let success = auth_events
+        .wait_for(is_auth_event("success"), TIMEOUT)
+        .await
+        .expect("Login did not succeed.");
+    assert_eq!(success["user"]["id"], "usr_mock");
+
+    let cookie_header = app
+        .state::<AuthState>()
+        .with_session(|session| session.cookie_header())
+        .unwrap();
+    assert!(cookie_header.is_some_and(|header| header.contains("auth=authcookie_mock_")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_a_wrong_password() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let (app, auth_events) = begin_auth(&server, "wrong-password").await;
+
+    let failure = auth_events
+        .wait_for(is_auth_event("failure"), TIMEOUT)
+        .await
+        .expect("Login did not fail.");
+    assert_eq!(failure["code"], "unauthorized");
+    assert_eq!(
+        failure["message"],
+        "Unauthorized: Invalid Username/Email or Password"
+    );
+    let basic_auth = app
+        .state::<AuthState>()
+        .with_session(|session| session.config.basic_auth.clone())
+        .unwrap();
+    assert!(basic_auth.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verifies_a_totp_code() {
+    let account = MockAccount::default().with_two_factor(&["totp", "otp"]);
+    let server = MockServer::start(account).await;
+    let (app, auth_events) = begin_auth(&server, "mock-password").await;
+
+    let challenge = auth_events
+        .wait_for(is_auth_event("twoFactorRequired"), TIMEOUT)
+        .await
+        .expect("2FA was not requested.");
+    assert_eq!(challenge["methods"], serde_json::json!(["totp", "otp"]));
+
+    app.state::<AuthState>()
+        .verify_two_factor(app.handle(), String::from("123456"), String::from("totp"))
+        .await
+        .unwrap();
+    auth_events
+        .wait_for(is_auth_event("success"), TIMEOUT)
+        .await
+        .expect("Login did not succeed.");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verifies_an_email_code() {
+    let account = MockAccount::default().with_two_factor(&["emailOtp"]);
+    let server = MockServer::start(account).await;
+    let (app, auth_events) = begin_auth(&server, "mock-password").await;
+
+    auth_events
+        .wait_for(is_auth_event("twoFactorRequired"), TIMEOUT)
+        .await
+        .expect("2FA was not requested.");
+    app.state::<AuthState>()
+        .verify_two_factor(
+            app.handle(),
+            String::from("654321"),
+            String::from("emailOtp"),
+        )
+        .await
+        .unwrap();
+
+    auth_events
+        .wait_for(is_auth_event("success"), TIMEOUT)
+        .await
+        .expect("Login did not succeed.");
+    assert!(server.requests().contains(&String::from(
+        "POST /api/1/auth/twofactorauth/emailotp/verify"
+    )));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verifies_a_recovery_code() {
+    let account = MockAccount::default().with_two_factor(&["totp", "otp"]);
+    let server = MockServer::start(account).await;
+    let (app, auth_events) = begin_auth(&server, "mock-password").await;
+
+    auth_events
+        .wait_for(is_auth_event("twoFactorRequired"), TIMEOUT)
+        .await
+        .expect("2FA was not requested.");
+    app.state::<AuthState>()
+        .verify_two_factor(app.handle(), String::from("abcd-efgh"), String::from("otp"))
+        .await
+        .unwrap();
+
+    auth_events
+        .wait_for(is_auth_event("success"), TIMEOUT)
+        .await
+        .expect("Login did not succeed.");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_a_wrong_two_factor_code() {
+    let account = MockAccount::default().with_two_factor(&["totp"]);
+    let server = MockServer::start(account).await;
+    let (app, auth_events) = begin_auth(&server, "mock-password").await;
+
+    auth_events
+        .wait_for(is_auth_event("twoFactorRequired"), TIMEOUT)
+        .await
+        .expect("2FA was not requested.");
+    app.state::<AuthState>()
+        .verify_two_factor(app.handle(), String::from("000000"), String::from("totp"))
+        .await
+        .unwrap();
+
+    auth_events
+        .wait_for(is_auth_event("failure"), TIMEOUT)
+        .await
+        .expect("Wrong code was accepted.");
+    assert!(auth_events
+        .payloads()
+        .iter()
+        .all(|payload| payload["type"] != "success"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn does_not_resend_a_rate_limited_two_factor_code() {
+    let account = MockAccount::default().with_two_factor(&["totp"]);
+    let server = MockServer::start(account).await;
+    let (app, auth_events) = begin_auth(&server, "mock-password").await;
+
+    auth_events
+        .wait_for(is_auth_event("twoFactorRequired"), TIMEOUT)
+        .await
+        .expect("2FA was not requested.");
+    server.rate_limit_next(1, 1);
+    app.state::<AuthState>()
+        .verify_two_factor(app.handle(), String::from("123456"), String::from("totp"))
+        .await
+        .unwrap();
+
+    let failure = auth_events
+        .wait_for(is_auth_event("failure"), TIMEOUT)
+        .await
+        .expect("Rate limited code was accepted.");
+    assert_eq!(failure["code"], "rateLimited");
+    let verifications = server
+        .requests()
+        .into_iter()
+        .filter(|request| request == "POST /api/1/auth/twofactorauth/totp/verify")
+        .count();
+    assert_eq!(verifications, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verifying_without_a_pending_challenge_fails() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+    let auth_events = EventLog::listen(app.handle(), "vrc:auth");
+
+    app.state::<AuthState>()
+        .verify_two_factor(app.handle(), String::from("123456"), String::from("totp"))
+        .await
+        .unwrap();
+
+    auth_events
+        .wait_for(is_auth_event("failure"), TIMEOUT)
+        .await
+        .expect("Verification without a challenge did not fail.");
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restores_a_live_session() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = logged_in_app(&server).await;
+
+    let user = app
+        .state::<AuthState>()
+        .restore_session(app.handle())
+        .await
+        .unwrap();
+    assert_eq!(user.map(|user| user.id), Some(String::from("usr_mock")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn does_not_restore_an_expired_session() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = logged_in_app(&server).await;
+    server.expire_sessions();
+
+    let user = app
+        .state::<AuthState>()
+        .restore_session(app.handle())
+        .await
+        .unwrap();
+    assert!(user.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn does_not_restore_without_cookies() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+
+    let user = app
+        .state::<AuthState>()
+        .restore_session(app.handle())
+        .await
+        .unwrap();
+    assert!(user.is_none());
+    assert!(server.requests().is_empty());
+}
@@ -0,0 +1,35 @@
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::time::Duration;
+use tauri::test::MockRuntime;
+use tauri::{App, Manager};
+use vfriends_lib::test_support::{mock_app, AuthState, EventLog, MockServer};
+
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+/// The pipeline waits 5 seconds before its first reconnect attempt.
+pub const RECONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub fn is_auth_event(kind: &'static str) -> impl Fn(&Value) -> bool {
+    move |payload| payload["type"] == kind
+}
+
+/// Logs in with the default mock account, which has no 2FA.
+pub async fn logged_in_app(server: &MockServer) -> App<MockRuntime> {
+    let app = mock_app(server.endpoints());
+    let auth_events = EventLog::listen(app.handle(), "vrc:auth");
+
+    app.state::<AuthState>()
+        .begin_auth_flow(
+            app.handle(),
+            String::from("mock-user"),
+            String::from("mock-password"),
+        )
+        .await
+        .expect("Auth flow failed.");
+    auth_events
+        .wait_for(is_auth_event("success"), TIMEOUT)
+        .await
+        .expect("Login did not succeed.");
+    app
+}
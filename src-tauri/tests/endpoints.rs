@@ -0,0 +1,84 @@
+use vfriends_lib::test_support::{EndpointSettings, Endpoints};
+
+const API_BASE_PATH_ENV: &str = "VFRIENDS_API_BASE_PATH";
+const PIPELINE_URL_ENV: &str = "VFRIENDS_PIPELINE_URL";
+
+fn configured(api_base_path: &str, pipeline_url: &str) -> EndpointSettings {
+    EndpointSettings {
+        api_base_path: Some(api_base_path.to_string()),
+        pipeline_url: Some(pipeline_url.to_string()),
+    }
+}
+
+fn endpoints(api_base_path: &str) -> Endpoints {
+    Endpoints {
+        api_base_path: api_base_path.to_string(),
+        ..Endpoints::default()
+    }
+}
+
+/// The environment is shared by every test in the process, so everything that resolves the
+/// endpoints runs in this one test.
+#[test]
+fn resolves_endpoints_from_the_environment_then_the_settings_then_the_defaults() {
+    std::env::remove_var(API_BASE_PATH_ENV);
+    std::env::remove_var(PIPELINE_URL_ENV);
+
+    assert_eq!(
+        Endpoints::resolve(&EndpointSettings::default()),
+        Endpoints::default()
+    );
+
+    let settings = configured(
+        "http://127.0.0.1:8080/api/1/",
+        "ws://127.0.0.1:8080/pipeline",
+    );
+    let resolved = Endpoints::resolve(&settings);
+    assert_eq!(resolved.api_base_path, "http://127.0.0.1:8080/api/1");
+    assert_eq!(resolved.pipeline_url, "ws://127.0.0.1:8080/pipeline");
+
+    // The API only speaks HTTP and the pipeline only websockets.
+    let swapped = configured(
+        "ws://127.0.0.1:8080/api/1",
+        "https://127.0.0.1:8080/pipeline",
+    );
+    assert_eq!(Endpoints::resolve(&swapped), Endpoints::default());
+    let invalid = configured("not a url", "  ");
+    assert_eq!(Endpoints::resolve(&invalid), Endpoints::default());
+
+    std::env::set_var(API_BASE_PATH_ENV, "https://staging.example.com/api/1");
+    std::env::set_var(PIPELINE_URL_ENV, "wss://staging.example.com/");
+    let resolved = Endpoints::resolve(&settings);
+    assert_eq!(resolved.api_base_path, "https://staging.example.com/api/1");
+    assert_eq!(resolved.pipeline_url, "wss://staging.example.com/");
+
+    // An invalid environment variable falls back to the settings.
+    std::env::set_var(API_BASE_PATH_ENV, "ftp://staging.example.com/api/1");
+    std::env::remove_var(PIPELINE_URL_ENV);
+    let resolved = Endpoints::resolve(&settings);
+    assert_eq!(resolved.api_base_path, "http://127.0.0.1:8080/api/1");
+    assert_eq!(resolved.pipeline_url, "ws://127.0.0.1:8080/pipeline");
+
+    std::env::remove_var(API_BASE_PATH_ENV);
+}
+
+#[test]
+fn tells_the_api_host_with_a_port_only_when_one_is_given() {
+    assert_eq!(
+        Endpoints::default().api_host().as_deref(),
+        Some("api.vrchat.cloud")
+    );
+    assert_eq!(
+        endpoints("http://127.0.0.1:8080/api/1")
+            .api_host()
+            .as_deref(),
+        Some("127.0.0.1:8080")
+    );
+    assert_eq!(
+        endpoints("https://api.vrchat.cloud:443/api/1")
+            .api_host()
+            .as_deref(),
+        Some("api.vrchat.cloud")
+    );
+    assert_eq!(endpoints("not a url").api_host(), None);
+}
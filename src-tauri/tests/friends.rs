@@ -0,0 +1,186 @@
+mod common;
+
+use common::logged_in_app;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use vfriends_lib::test_support::{
+    fetch_all_friends, fetch_world, fetch_world_name, mock_friend, mock_world, ApiService,
+    AuthState, MockAccount, MockServer,
+};
+
+fn friends(
+    prefix: &str,
+    count: usize,
+    location: &str,
+) -> Vec<vrchatapi::models::LimitedUserFriend> {
+    (0..count)
+        .map(|index| {
+            mock_friend(
+                &format!("usr_{prefix}_{index}"),
+                &format!("{prefix} {index}"),
+                location,
+            )
+        })
+        .collect()
+}
+
+fn friend_requests(server: &MockServer) -> Vec<String> {
+    server
+        .requests()
+        .into_iter()
+        .filter(|request| request.starts_with("GET /api/1/auth/user/friends"))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fetches_every_page_of_online_and_offline_friends() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let mut all = friends("online", 250, "wrld_home:1~friends(usr_x)");
+    all.extend(friends("offline", 120, "offline"));
+    server.set_friends(all);
+    let app = logged_in_app(&server).await;
+
+    let fetched = fetch_all_friends(&app.state::<ApiService>(), &app.state::<AuthState>())
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.len(), 370);
+    assert_eq!(fetched[0].id, "usr_online_0");
+    assert_eq!(fetched[369].id, "usr_offline_119");
+    let requests = friend_requests(&server);
+    for expected in [
+        "GET /api/1/auth/user/friends?offset=0&n=100&offline=false",
+        "GET /api/1/auth/user/friends?offset=200&n=100&offline=false",
+        "GET /api/1/auth/user/friends?offset=100&n=100&offline=true",
+    ] {
+        assert!(
+            requests.contains(&expected.to_string()),
+            "missing {expected}"
+        );
+    }
+    assert!(!requests.contains(&String::from(
+        "GET /api/1/auth/user/friends?offset=300&n=100&offline=false"
+    )));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stops_after_an_empty_page() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(friends("online", 200, "wrld_home:1"));
+    let app = logged_in_app(&server).await;
+
+    let fetched = fetch_all_friends(&app.state::<ApiService>(), &app.state::<AuthState>())
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.len(), 200);
+    let online_requests: Vec<_> = friend_requests(&server)
+        .into_iter()
+        .filter(|request| request.ends_with("offline=false"))
+        .collect();
+    assert_eq!(online_requests.len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_without_a_session() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(friends("online", 3, "wrld_home:1"));
+    let app = vfriends_lib::test_support::mock_app(server.endpoints());
+
+    let result = fetch_all_friends(&app.state::<ApiService>(), &app.state::<AuthState>()).await;
+    assert!(result.unwrap_err().is_unauthorized());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resolves_world_names() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.add_world(mock_world("wrld_mock_test", "The Great Pug"));
+    let app = logged_in_app(&server).await;
+
+    let name = fetch_world_name(
+        &app.state::<ApiService>(),
+        &app.state::<AuthState>(),
+        "wrld_mock_test",
+    )
+    .await
+    .unwrap();
+    assert_eq!(name, "The Great Pug");
+}
+
+fn world_requests(server: &MockServer, world_id: &str) -> usize {
+    let expected = format!("GET /api/1/worlds/{world_id}");
+    server
+        .requests()
+        .iter()
+        .filter(|request| **request == expected)
+        .count()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn forgets_world_names_after_logging_out() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.add_world(mock_world("wrld_mock_test", "The Great Pug"));
+    let app = logged_in_app(&server).await;
+    let api = app.state::<ApiService>();
+    let auth = app.state::<AuthState>();
+
+    for _ in 0..2 {
+        fetch_world_name(&api, &auth, "wrld_mock_test")
+            .await
+            .unwrap();
+    }
+    assert_eq!(world_requests(&server, "wrld_mock_test"), 1);
+
+    auth.logout(app.handle()).unwrap();
+    assert_eq!(api.cached_world_name("wrld_mock_test"), None);
+}
+
+#[test]
+fn keeps_only_the_latest_world_names() {
+    let api = ApiService::default();
+    for index in 0..=512 {
+        api.cache_world_name(&format!("wrld_{index}"), "World");
+    }
+    assert_eq!(api.cached_world_name("wrld_0"), None);
+    assert_eq!(api.cached_world_name("wrld_1").as_deref(), Some("World"));
+    assert_eq!(api.cached_world_name("wrld_512").as_deref(), Some("World"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn waits_as_long_as_the_api_asks_after_a_rate_limit() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.add_world(mock_world("wrld_busy", "Busy World"));
+    let app = logged_in_app(&server).await;
+    server.rate_limit_next(1, 1);
+
+    let started = Instant::now();
+    let world = fetch_world(
+        &app.state::<ApiService>(),
+        &app.state::<AuthState>(),
+        "wrld_busy",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(world.name, "Busy World");
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(world_requests(&server, "wrld_busy"), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shares_concurrent_requests_for_the_same_world() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.add_world(mock_world("wrld_popular", "Popular World"));
+    let app = logged_in_app(&server).await;
+    let api = app.state::<ApiService>();
+    let auth = app.state::<AuthState>();
+
+    let (first, second) = tokio::join!(
+        fetch_world(&api, &auth, "wrld_popular"),
+        fetch_world(&api, &auth, "wrld_popular"),
+    );
+
+    assert_eq!(first.unwrap().id, "wrld_popular");
+    assert_eq!(second.unwrap().id, "wrld_popular");
+    assert_eq!(world_requests(&server, "wrld_popular"), 1);
+}
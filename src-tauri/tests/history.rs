@@ -0,0 +1,254 @@
+mod common;
+
+use common::{logged_in_app, TIMEOUT};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::Manager;
+use vfriends_lib::test_support::{
+    export_history, mock_friend, Decision, ExportFormat, FriendRoster, HistoryFilter, HistoryQuery,
+    HistoryStore, MockAccount, MockServer, NotificationKind, SettingsStore, Subject,
+    SuppressionReason,
+};
+use vrchatapi::models;
+
+fn subject(kind: NotificationKind, friend_id: &str, display_name: &str) -> Subject {
+    Subject::new(kind, friend_id, display_name, Some("wrld_home:1"))
+}
+
+fn seeded_store() -> HistoryStore {
+    let store = HistoryStore::in_memory().expect("Failed to open the history.");
+    let records = [
+        (
+            NotificationKind::Online,
+            "usr_alice",
+            "Alice",
+            Decision::Shown,
+            None,
+        ),
+        (
+            NotificationKind::Online,
+            "usr_bob",
+            "Bob",
+            Decision::Suppressed,
+            Some(SuppressionReason::QuietHours),
+        ),
+        (
+            NotificationKind::Offline,
+            "usr_alice",
+            "Alice",
+            Decision::Shown,
+            None,
+        ),
+        (
+            NotificationKind::Online,
+            "usr_carol",
+            "Carol, \"C\"",
+            Decision::Batched,
+            None,
+        ),
+    ];
+    for (kind, friend_id, display_name, decision, reason) in records {
+        store
+            .record(&subject(kind, friend_id, display_name), decision, reason)
+            .expect("Failed to record.");
+    }
+    store
+}
+
+#[test]
+fn pages_newest_first_with_the_total() {
+    let store = seeded_store();
+
+    let page = store
+        .page(&HistoryQuery {
+            limit: 2,
+            ..HistoryQuery::default()
+        })
+        .unwrap();
+    assert_eq!(page.total, 4);
+    let names: Vec<_> = page
+        .records
+        .iter()
+        .map(|r| r.display_name.as_str())
+        .collect();
+    assert_eq!(names, ["Carol, \"C\"", "Alice"]);
+
+    let page = store
+        .page(&HistoryQuery {
+            offset: 2,
+            limit: 2,
+            ..HistoryQuery::default()
+        })
+        .unwrap();
+    let friend_ids: Vec<_> = page.records.iter().map(|r| r.friend_id.as_str()).collect();
+    assert_eq!(friend_ids, ["usr_bob", "usr_alice"]);
+}
+
+#[test]
+fn filters_by_friend_kind_and_decision() {
+    let store = seeded_store();
+
+    let by_friend = store
+        .all(&HistoryFilter {
+            friend_id: Some(String::from("usr_alice")),
+            kinds: vec![NotificationKind::Offline],
+            ..HistoryFilter::default()
+        })
+        .unwrap();
+    assert_eq!(by_friend.len(), 1);
+    assert_eq!(by_friend[0].kind, NotificationKind::Offline);
+
+    let suppressed = store
+        .all(&HistoryFilter {
+            decisions: vec![Decision::Suppressed],
+            ..HistoryFilter::default()
+        })
+        .unwrap();
+    assert_eq!(suppressed.len(), 1);
+    assert_eq!(suppressed[0].reason, Some(SuppressionReason::QuietHours));
+    assert_eq!(suppressed[0].location.as_deref(), Some("wrld_home:1"));
+
+    let by_name = store
+        .all(&HistoryFilter {
+            display_name: Some(String::from("caROL")),
+            ..HistoryFilter::default()
+        })
+        .unwrap();
+    assert_eq!(by_name.len(), 1);
+}
+
+#[test]
+fn clear_removes_everything() {
+    let store = seeded_store();
+    store.clear().unwrap();
+    assert_eq!(store.page(&HistoryQuery::default()).unwrap().total, 0);
+}
+
+#[test]
+fn exports_csv_and_json() {
+    let store = seeded_store();
+    let records = store.all(&HistoryFilter::default()).unwrap();
+    let directory = std::env::temp_dir().join(format!("vfriends-history-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let csv_path = directory.join("history.csv");
+    assert_eq!(
+        export_history(&records, ExportFormat::Csv, &csv_path).unwrap(),
+        4
+    );
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("recordedAt,friendId"));
+    assert!(lines[2].ends_with(",online,wrld_home:1,suppressed,quietHours"));
+    assert!(lines[4].contains(",\"Carol, \"\"C\"\"\",online,"));
+
+    let json_path = directory.join("history.json");
+    export_history(&records, ExportFormat::Json, &json_path).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(json[1]["reason"], "quietHours");
+    assert_eq!(json[3]["decision"], "batched");
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+fn friend_online(user_id: &str, display_name: &str, platform: &str) -> Value {
+    json!({
+        "userId": user_id,
+        "platform": platform,
+        "location": "wrld_home:1",
+        "canRequestInvite": true,
+        "user": models::User {
+            id: user_id.to_string(),
+            display_name: display_name.to_string(),
+            ..Default::default()
+        },
+    })
+}
+
+/// The decisions recorded so far, once there are `count` of them.
+async fn wait_for_decisions(
+    store: &HistoryStore,
+    count: usize,
+) -> Vec<(String, Decision, Option<SuppressionReason>)> {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    loop {
+        let records = store.all(&HistoryFilter::default()).unwrap();
+        if records.len() >= count || tokio::time::Instant::now() >= deadline {
+            return records
+                .into_iter()
+                .map(|record| (record.friend_id, record.decision, record.reason))
+                .collect();
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn decision(
+    friend_id: &str,
+    decision: Decision,
+    reason: Option<SuppressionReason>,
+) -> (String, Decision, Option<SuppressionReason>) {
+    (friend_id.to_string(), decision, reason)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn records_what_became_of_online_notifications() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(
+        ["Alice", "Bob", "Carol", "Dave"]
+            .iter()
+            .map(|name| mock_friend(&format!("usr_{}", name.to_lowercase()), name, "offline"))
+            .collect(),
+    );
+    let app = logged_in_app(&server).await;
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while !app.state::<FriendRoster>().is_seeded() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let settings = app.state::<SettingsStore>();
+    settings
+        .consume(|settings| {
+            settings.online_batch_window_ms = 0;
+            settings.platform_filter.android = false;
+        })
+        .unwrap();
+    let store = app.state::<HistoryStore>();
+
+    server.send_pipeline_event(
+        "friend-online",
+        &friend_online("usr_alice", "Alice", "standalonewindows"),
+    );
+    wait_for_decisions(&store, 1).await;
+    server.send_pipeline_event("friend-online", &friend_online("usr_bob", "Bob", "android"));
+    wait_for_decisions(&store, 2).await;
+
+    settings
+        .consume(|settings| settings.online_batch_window_ms = 500)
+        .unwrap();
+    server.send_pipeline_event(
+        "friend-online",
+        &friend_online("usr_carol", "Carol", "standalonewindows"),
+    );
+    server.send_pipeline_event(
+        "friend-online",
+        &friend_online("usr_dave", "Dave", "standalonewindows"),
+    );
+
+    let mut decisions = wait_for_decisions(&store, 4).await;
+    decisions[2..].sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        decisions,
+        [
+            decision("usr_alice", Decision::Shown, None),
+            decision(
+                "usr_bob",
+                Decision::Suppressed,
+                Some(SuppressionReason::Platform)
+            ),
+            decision("usr_carol", Decision::Batched, None),
+            decision("usr_dave", Decision::Batched, None),
+        ]
+    );
+}
@@ -0,0 +1,224 @@
+use serde_json::json;
+use vfriends_lib::test_support::{AccessType, InstanceTag, Location};
+
+const WORLD: &str = "wrld_4cf554b4-430c-4f8f-b53e-1f294eed230b";
+const OWNER: &str = "usr_c1644b5b-3ca4-45b4-97c6-a2a0de70d469";
+const GROUP: &str = "grp_0f2b9b3c-2f1e-4a6e-9d0c-3f8a1b2c4d5e";
+
+fn parse(location: &str) -> Location {
+    Location::parse(location).unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[test]
+fn parses_special_values() {
+    assert_eq!(parse("offline"), Location::Offline);
+    assert_eq!(parse("private"), Location::Private);
+    assert_eq!(parse("traveling"), Location::Traveling);
+    assert_eq!(parse("offline").world_id(), None);
+    assert!(parse("private").instance().is_none());
+}
+
+#[test]
+fn parses_every_part_of_an_instance() {
+    let location = format!(
+        "{WORLD}:12345~hidden({OWNER})~region(jp)~canRequestInvite~group({GROUP})~groupAccessType(plus)"
+    );
+    let parsed = parse(&location);
+    let instance = parsed.instance().expect("Expected an instance.");
+
+    assert_eq!(parsed.world_id(), Some(WORLD));
+    assert_eq!(instance.world_id(), WORLD);
+    assert_eq!(instance.name(), "12345");
+    assert_eq!(instance.owner(), Some(OWNER));
+    assert_eq!(instance.region(), "jp");
+    assert_eq!(instance.group(), Some(GROUP));
+    assert!(instance.can_request_invite());
+    assert_eq!(instance.access_type(), AccessType::FriendsPlus);
+    assert_eq!(
+        instance.tags(),
+        [
+            InstanceTag::Hidden(OWNER.to_string()),
+            InstanceTag::Region("jp".to_string()),
+            InstanceTag::CanRequestInvite,
+            InstanceTag::Group(GROUP.to_string()),
+            InstanceTag::GroupAccessType("plus".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn tells_access_types_apart() {
+    let cases = [
+        (format!("{WORLD}:1"), AccessType::Public, "Public"),
+        (
+            format!("{WORLD}:1~region(eu)"),
+            AccessType::Public,
+            "Public",
+        ),
+        (
+            format!("{WORLD}:1~hidden({OWNER})~region(us)"),
+            AccessType::FriendsPlus,
+            "Friends+",
+        ),
+        (
+            format!("{WORLD}:1~friends({OWNER})"),
+            AccessType::Friends,
+            "Friends",
+        ),
+        (
+            format!("{WORLD}:1~private({OWNER})~canRequestInvite~region(use)"),
+            AccessType::InvitePlus,
+            "Invite+",
+        ),
+        (
+            format!("{WORLD}:1~private({OWNER})"),
+            AccessType::Invite,
+            "Invite",
+        ),
+        (
+            format!("{WORLD}:1~group({GROUP})~groupAccessType(members)"),
+            AccessType::Group,
+            "Group",
+        ),
+        (
+            format!("{WORLD}:1~group({GROUP})"),
+            AccessType::Group,
+            "Group",
+        ),
+        (
+            format!("{WORLD}:1~group({GROUP})~groupAccessType(plus)"),
+            AccessType::GroupPlus,
+            "Group+",
+        ),
+        (
+            format!("{WORLD}:1~group({GROUP})~groupAccessType(public)~region(jp)"),
+            AccessType::GroupPublic,
+            "Group Public",
+        ),
+    ];
+
+    for (location, access_type, label) in cases {
+        let parsed = parse(&location);
+        let instance = parsed.instance().expect("Expected an instance.");
+        assert_eq!(instance.access_type(), access_type, "{location}");
+        assert_eq!(instance.access_type().label(), label, "{location}");
+    }
+}
+
+#[test]
+fn defaults_to_the_us_region() {
+    let parsed = parse(&format!("{WORLD}:67890~friends({OWNER})"));
+    let instance = parsed.instance().expect("Expected an instance.");
+    assert_eq!(instance.region(), "us");
+    assert_eq!(instance.group(), None);
+    assert!(!instance.can_request_invite());
+}
+
+#[test]
+fn public_and_group_instances_have_no_owner() {
+    for location in [
+        format!("{WORLD}:1~region(jp)"),
+        format!("{WORLD}:1~group({GROUP})~groupAccessType(public)"),
+    ] {
+        let parsed = parse(&location);
+        assert_eq!(
+            parsed.instance().and_then(|instance| instance.owner()),
+            None
+        );
+    }
+}
+
+#[test]
+fn formats_back_to_the_parsed_string() {
+    let corpus = [
+        "offline".to_string(),
+        "private".to_string(),
+        "traveling".to_string(),
+        format!("{WORLD}:12345"),
+        format!("{WORLD}:MyRoom"),
+        format!("{WORLD}:12345~region(jp)"),
+        format!("{WORLD}:12345~hidden({OWNER})~region(eu)~nonce(abc-123)"),
+        format!("{WORLD}:12345~friends({OWNER})~region(use)"),
+        format!("{WORLD}:12345~private({OWNER})~canRequestInvite~region(us)~nonce(n)"),
+        format!("{WORLD}:12345~region(jp)~private({OWNER})"),
+        format!("{WORLD}:12345~group({GROUP})~groupAccessType(plus)~strict"),
+        format!(
+            "{WORLD}:12345~hidden({OWNER})~region(jp)~canRequestInvite~group({GROUP})~groupAccessType(plus)"
+        ),
+        format!("{WORLD}:12345~ageGate~region(jp)"),
+        format!("{WORLD}:12345~futureTag(value)"),
+    ];
+
+    for location in corpus {
+        let parsed = parse(&location);
+        assert_eq!(parsed.to_string(), location);
+        assert_eq!(location.parse::<Location>(), Ok(parsed));
+    }
+}
+
+#[test]
+fn keeps_unknown_tags() {
+    let parsed = parse(&format!("{WORLD}:1~ageGate~futureTag(value)~region(jp)"));
+    let instance = parsed.instance().expect("Expected an instance.");
+    assert_eq!(
+        instance.tags(),
+        [
+            InstanceTag::Other("ageGate".to_string()),
+            InstanceTag::Other("futureTag(value)".to_string()),
+            InstanceTag::Region("jp".to_string()),
+        ]
+    );
+    assert_eq!(instance.region(), "jp");
+    assert_eq!(instance.access_type(), AccessType::Public);
+}
+
+#[test]
+fn rejects_malformed_locations() {
+    let corpus = [
+        String::new(),
+        "web".to_string(),
+        "Offline".to_string(),
+        WORLD.to_string(),
+        format!("{WORLD}:"),
+        format!("{WORLD}:~region(jp)"),
+        "wrld_:12345".to_string(),
+        "usr_abc:12345".to_string(),
+        ":12345~region(jp)".to_string(),
+        format!("{WORLD}:12345~"),
+        format!("{WORLD}:12345~~region(jp)"),
+        format!("{WORLD}:12345~region(jp"),
+        format!("{WORLD}:12345~region)jp("),
+        format!("{WORLD}:12345~(jp)"),
+        format!("{WORLD}:12345~region(j(p))"),
+        format!("{WORLD}:12345~hidden({OWNER})x"),
+    ];
+
+    for location in corpus {
+        let err = Location::parse(&location).expect_err(&location);
+        assert_eq!(err.location, location);
+        assert!(err.to_string().contains("Invalid location"), "{err}");
+    }
+}
+
+#[test]
+fn serializes_the_parts_for_the_frontend() {
+    let location = format!("{WORLD}:12345~private({OWNER})~canRequestInvite~region(jp)");
+    assert_eq!(
+        serde_json::to_value(parse(&location)).expect("Failed to serialize."),
+        json!({
+            "kind": "instance",
+            "location": location,
+            "worldId": WORLD,
+            "instanceName": "12345",
+            "accessType": "invitePlus",
+            "owner": OWNER,
+            "region": "jp",
+            "group": null,
+            "canRequestInvite": true,
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(parse("traveling")).expect("Failed to serialize."),
+        json!({"kind": "traveling", "location": "traveling"})
+    );
+}
@@ -0,0 +1,674 @@
+mod common;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use common::TIMEOUT;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tauri::Manager;
+use vfriends_lib::test_support::{
+    compose_digest, compose_friend_location, compose_friend_offline, compose_friend_online,
+    compose_missed_online, compose_online_batch, join_names, mock_app, mock_friend, partition,
+    render_template, AppSettings, Decision, FlapGuard, FlapSuppression, FriendOnlineEvent,
+    FriendRoster, FriendSettings, HistoryFilter, HistoryStore, MissedOnlineMode, MockAccount,
+    MockServer, Notification, OnlineVerdict, QuietHours, QuietMode, QuietWindow, RecordingBackend,
+    ResyncReason, RosterEntry, SettingsStore, Template, TemplateValues,
+};
+use vrchatapi::models;
+
+fn alice() -> TemplateValues {
+    TemplateValues::default()
+        .with("displayName", "Alice")
+        .with("status", "Busy")
+        .with("onlineFor", "2 hours")
+        .with("world", "The Great Pug (Public)")
+}
+
+fn render(source: &str, values: &TemplateValues) -> String {
+    Template::parse(source)
+        .expect("The template did not parse.")
+        .render(values)
+}
+
+/// Where parsing `source` fails.
+fn error_position(source: &str) -> usize {
+    Template::parse(source)
+        .expect_err("The template parsed.")
+        .position
+}
+
+async fn wait_for_shown(notifications: &RecordingBackend, count: usize) -> bool {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if notifications.shown().len() >= count {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn adds_friends_only_from_resyncs_of_a_seeded_roster() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+    app.state::<SettingsStore>()
+        .consume(|settings| {
+            settings
+                .friend_settings
+                .insert(String::from("usr_bob"), FriendSettings::default());
+            settings.archive_friend_settings("usr_bob", "Bob");
+        })
+        .unwrap();
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+    let roster = app.state::<FriendRoster>();
+
+    // A periodic resync that runs before the seed after logging in again.
+    roster.replace_all(
+        vec![mock_friend("usr_bob", "Bob", "offline")],
+        ResyncReason::Periodic,
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(notifications.shown().is_empty());
+    let settings = app.state::<SettingsStore>().snapshot();
+    assert!(settings.archived_friend_settings.contains_key("usr_bob"));
+
+    roster.replace_all(
+        vec![
+            mock_friend("usr_bob", "Bob", "offline"),
+            mock_friend("usr_carol", "Carol", "offline"),
+        ],
+        ResyncReason::Periodic,
+    );
+    assert!(wait_for_shown(&notifications, 1).await);
+    let shown = notifications.shown();
+    assert_eq!(shown.len(), 1);
+    assert_eq!(shown[0].body, "You and Carol are now friends!");
+}
+
+#[test]
+fn renders_placeholders_and_fallbacks() {
+    let values = alice();
+    assert_eq!(
+        render("{displayName} is {status}.", &values),
+        "Alice is Busy."
+    );
+    assert_eq!(render("In {worldName|a world}", &values), "In a world");
+    assert_eq!(
+        render(
+            "In {worldName|a world}",
+            &values.clone().with("worldName", "Home")
+        ),
+        "In Home"
+    );
+    assert_eq!(render("[{statusDescription}]", &values), "[]");
+}
+
+#[test]
+fn shows_sections_only_with_a_value() {
+    let source = "{displayName} joined{#worldName} {worldName}{/worldName}.";
+    assert_eq!(render(source, &alice()), "Alice joined.");
+    assert_eq!(
+        render(source, &alice().with("worldName", "Home")),
+        "Alice joined Home."
+    );
+}
+
+#[test]
+fn keeps_escaped_braces() {
+    assert_eq!(render("{{displayName}}", &alice()), "{displayName}");
+    assert_eq!(render("}}{displayName}{{", &alice()), "}Alice{");
+}
+
+#[test]
+fn replaces_the_legacy_display_name_placeholder() {
+    assert_eq!(
+        render("%s has been online for {onlineFor}, 100%", &alice()),
+        "Alice has been online for 2 hours, 100%"
+    );
+    assert_eq!(render("%d in %w", &alice()), "%d in %w");
+}
+
+#[test]
+fn reports_where_a_template_is_invalid() {
+    assert_eq!(error_position("Hi {displayName"), 3);
+    assert_eq!(error_position("Hi }"), 3);
+    assert_eq!(error_position("Hi {#worldName}{worldName}"), 3);
+    assert_eq!(error_position("{#worldName}x{/world}"), 13);
+    assert_eq!(error_position("{#worldName}{/worldName}{/worldName}"), 24);
+    assert_eq!(error_position("Hi {nickname}"), 4);
+    assert_eq!(error_position("{#nickname}{/nickname}"), 2);
+    assert_eq!(error_position("{}"), 1);
+}
+
+#[test]
+fn renders_the_legacy_placeholder_of_an_invalid_template() {
+    assert_eq!(
+        render_template("%s is online for %d {", &alice()),
+        "Alice is online for %d {"
+    );
+}
+
+#[test]
+fn tells_which_placeholders_are_used() {
+    let template = Template::parse("{#worldName}{world|%s}{/worldName}").unwrap();
+    assert!(template.uses("worldName"));
+    assert!(template.uses("world"));
+    assert!(!template.uses("displayName"));
+}
+
+fn flap_suppression(
+    online_cooldown_secs: u64,
+    min_online_secs: u64,
+    dedup_window_secs: u64,
+) -> FlapSuppression {
+    FlapSuppression {
+        online_cooldown_secs,
+        min_online_secs,
+        dedup_window_secs,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn drops_repeated_online_events_within_the_dedup_window() {
+    let guard = FlapGuard::default();
+    let settings = flap_suppression(0, 0, 10);
+
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Notify
+    );
+    tokio::time::advance(Duration::from_secs(5)).await;
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Duplicate
+    );
+    assert_eq!(
+        guard.on_online("usr_bob", "wrld_home:1", &settings),
+        OnlineVerdict::Notify
+    );
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:2", &settings),
+        OnlineVerdict::Notify
+    );
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:2", &settings),
+        OnlineVerdict::Notify
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn skips_online_events_during_the_cooldown() {
+    let guard = FlapGuard::default();
+    let settings = flap_suppression(60, 0, 0);
+
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Notify
+    );
+    assert!(guard.on_offline("usr_alice"));
+    tokio::time::advance(Duration::from_secs(30)).await;
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Cooldown
+    );
+    // Coming online was not notified, so going offline is not either.
+    assert!(!guard.on_offline("usr_alice"));
+
+    tokio::time::advance(Duration::from_secs(30)).await;
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Notify
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn confirms_friends_that_stay_online_once() {
+    let guard = FlapGuard::default();
+    let settings = flap_suppression(0, 30, 0);
+
+    let verdict = guard.on_online("usr_alice", "wrld_home:1", &settings);
+    assert_eq!(
+        verdict,
+        OnlineVerdict::Confirm {
+            after: Duration::from_secs(30),
+            generation: 0,
+        }
+    );
+    assert_eq!(
+        guard.on_online("usr_alice", "wrld_home:2", &settings),
+        OnlineVerdict::Duplicate
+    );
+
+    assert!(guard.confirm_online("usr_alice", 0));
+    assert!(!guard.confirm_online("usr_alice", 0));
+    assert!(guard.on_offline("usr_alice"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn going_offline_cancels_a_pending_confirmation() {
+    let guard = FlapGuard::default();
+    let settings = flap_suppression(0, 30, 0);
+
+    assert!(matches!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Confirm { generation: 0, .. }
+    ));
+    assert!(!guard.on_offline("usr_alice"));
+    assert!(!guard.confirm_online("usr_alice", 0));
+
+    assert!(matches!(
+        guard.on_online("usr_alice", "wrld_home:1", &settings),
+        OnlineVerdict::Confirm { generation: 1, .. }
+    ));
+    assert!(guard.confirm_online("usr_alice", 1));
+}
+
+#[test]
+fn notifies_going_offline_of_unknown_friends() {
+    assert!(FlapGuard::default().on_offline("usr_alice"));
+}
+
+const EVERY_DAY: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+fn time(time: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+}
+
+fn quiet_window(days: &[Weekday], start: &str, end: &str) -> QuietWindow {
+    QuietWindow {
+        days: days.to_vec(),
+        start: time(start),
+        end: time(end),
+    }
+}
+
+/// `time` on `day` of the week starting on Monday, January 1st 2024.
+fn on(day: Weekday, at: &str) -> NaiveDateTime {
+    let date = NaiveDate::from_ymd_opt(2024, 1, 1 + day.num_days_from_monday()).unwrap();
+    date.and_time(time(at))
+}
+
+/// Quiet around the clock, in `mode`.
+fn always_quiet(mode: QuietMode) -> QuietHours {
+    QuietHours {
+        enabled: true,
+        mode,
+        windows: vec![quiet_window(&EVERY_DAY, "00:00", "00:00")],
+    }
+}
+
+#[test]
+fn quiet_windows_within_a_day() {
+    let window = quiet_window(&[Weekday::Mon], "09:00", "17:00");
+    assert!(window.contains(on(Weekday::Mon, "09:00")));
+    assert!(window.contains(on(Weekday::Mon, "16:59")));
+    assert!(!window.contains(on(Weekday::Mon, "17:00")));
+    assert!(!window.contains(on(Weekday::Mon, "08:59")));
+    assert!(!window.contains(on(Weekday::Tue, "12:00")));
+}
+
+#[test]
+fn quiet_windows_across_midnight_end_on_the_next_day() {
+    let window = quiet_window(&[Weekday::Fri], "22:00", "06:00");
+    assert!(window.contains(on(Weekday::Fri, "23:00")));
+    assert!(window.contains(on(Weekday::Sat, "05:59")));
+    assert!(!window.contains(on(Weekday::Sat, "06:00")));
+    assert!(!window.contains(on(Weekday::Sat, "23:00")));
+    assert!(!window.contains(on(Weekday::Fri, "05:00")));
+
+    // The week wraps around, so a window starting on Sunday ends on Monday.
+    let window = quiet_window(&[Weekday::Sun], "22:00", "06:00");
+    assert!(window.contains(on(Weekday::Mon, "01:00")));
+}
+
+#[test]
+fn quiet_windows_starting_and_ending_at_the_same_time_last_a_day() {
+    let window = quiet_window(&[Weekday::Mon], "08:00", "08:00");
+    assert!(window.contains(on(Weekday::Mon, "08:00")));
+    assert!(window.contains(on(Weekday::Tue, "07:59")));
+    assert!(!window.contains(on(Weekday::Tue, "08:00")));
+    assert!(!window.contains(on(Weekday::Mon, "07:59")));
+}
+
+#[test]
+fn partitions_friends_bypassing_quiet_hours() {
+    let mut settings = AppSettings {
+        quiet_hours: always_quiet(QuietMode::Digest),
+        ..AppSettings::default()
+    };
+    settings.friend_settings.insert(
+        String::from("usr_bob"),
+        FriendSettings {
+            bypass_quiet_hours: true,
+            ..FriendSettings::default()
+        },
+    );
+    let friends = vec!["usr_alice", "usr_bob", "usr_carol"];
+
+    let [shown, held] = partition(&settings, friends.clone(), |friend| *friend);
+    assert_eq!(shown, ["usr_bob"]);
+    assert_eq!(held, ["usr_alice", "usr_carol"]);
+
+    settings.quiet_hours.enabled = false;
+    let [shown, held] = partition(&settings, friends.clone(), |friend| *friend);
+    assert_eq!(shown, friends);
+    assert!(held.is_empty());
+}
+
+#[test]
+fn lists_the_first_deferred_notifications_in_the_digest() {
+    let settings = AppSettings::default();
+    let deferred: Vec<Notification> = (1..=7)
+        .map(|index| {
+            Notification::new(
+                format!("Friend {index}"),
+                format!("Friend {index} is now online!"),
+                None,
+                false,
+            )
+        })
+        .collect();
+
+    assert_eq!(compose_digest(&settings, &[], false), None);
+    assert_eq!(
+        compose_digest(&settings, &deferred[..1], false).as_ref(),
+        deferred.first()
+    );
+
+    let digest = compose_digest(&settings, &deferred, true).unwrap();
+    assert_eq!(digest.title, "During quiet hours");
+    assert!(digest.silent);
+    let lines: Vec<&str> = digest.body.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "Friend 1 is now online!",
+            "Friend 2 is now online!",
+            "Friend 3 is now online!",
+            "Friend 4 is now online!",
+            "Friend 5 is now online!",
+            "and 2 more",
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn records_notifications_held_for_the_digest_as_deferred() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+    app.state::<SettingsStore>()
+        .consume(|settings| settings.quiet_hours = always_quiet(QuietMode::Digest))
+        .unwrap();
+    let roster = app.state::<FriendRoster>();
+    roster.replace_all(Vec::new(), ResyncReason::Seed);
+    roster.replace_all(
+        vec![mock_friend("usr_carol", "Carol", "offline")],
+        ResyncReason::Periodic,
+    );
+
+    let history = app.state::<HistoryStore>();
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    let records = loop {
+        let records = history.all(&HistoryFilter::default()).unwrap();
+        if !records.is_empty() || tokio::time::Instant::now() >= deadline {
+            break records;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    };
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].friend_id, "usr_carol");
+    assert_eq!(records[0].decision, Decision::Deferred);
+    assert!(app.state::<Arc<RecordingBackend>>().shown().is_empty());
+}
+
+#[test]
+fn joins_up_to_three_names() {
+    assert_eq!(join_names(&[]), "");
+    assert_eq!(join_names(&["Alice"]), "Alice");
+    assert_eq!(join_names(&["Alice", "Bob"]), "Alice and Bob");
+    assert_eq!(
+        join_names(&["Alice", "Bob", "Carol"]),
+        "Alice, Bob and Carol"
+    );
+    assert_eq!(
+        join_names(&["Alice", "Bob", "Carol", "Dave"]),
+        "Alice, Bob, Carol and 1 other"
+    );
+    assert_eq!(
+        join_names(&["Alice", "Bob", "Carol", "Dave", "Erin"]),
+        "Alice, Bob, Carol and 2 others"
+    );
+}
+
+fn online_event(display_name: &str) -> FriendOnlineEvent {
+    let user_id = format!("usr_{}", display_name.to_lowercase());
+    FriendOnlineEvent {
+        user_id: user_id.clone(),
+        platform: String::from("standalonewindows"),
+        location: String::from("wrld_home:1"),
+        can_request_invite: true,
+        user: models::User {
+            id: user_id,
+            display_name: display_name.to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn passes_a_single_online_event_through() {
+    let settings = AppSettings::default();
+    let events = [online_event("Alice")];
+    let world_names = HashMap::new();
+
+    let (notification, included) =
+        compose_online_batch(&settings, &events, &world_names, false).unwrap();
+    assert_eq!(
+        Some(notification),
+        compose_friend_online(&settings, &events[0], &world_names, false)
+    );
+    assert_eq!(included.len(), 1);
+    assert_eq!(included[0].user_id, "usr_alice");
+}
+
+#[test]
+fn leaves_filtered_out_events_out_of_the_batch() {
+    let mut settings = AppSettings::default();
+    settings.friend_settings.insert(
+        String::from("usr_bob"),
+        FriendSettings {
+            enabled: false,
+            ..FriendSettings::default()
+        },
+    );
+    let world_names = HashMap::new();
+
+    let events = [online_event("Alice"), online_event("Bob")];
+    let (notification, included) =
+        compose_online_batch(&settings, &events, &world_names, false).unwrap();
+    assert_eq!(notification.body, "Alice is now online!");
+    assert_eq!(included.len(), 1);
+    assert_eq!(included[0].user_id, "usr_alice");
+
+    assert!(compose_online_batch(&settings, &events[1..], &world_names, false).is_none());
+}
+
+#[test]
+fn summarizes_several_online_events() {
+    let settings = AppSettings::default();
+    let events = ["Alice", "Bob", "Carol", "Dave"].map(online_event);
+
+    let (notification, included) =
+        compose_online_batch(&settings, &events, &HashMap::new(), false).unwrap();
+    assert_eq!(notification.title, "Friends online");
+    assert_eq!(
+        notification.body,
+        "Alice, Bob, Carol and 1 other are now online!"
+    );
+    assert_eq!(included.len(), 4);
+}
+
+fn came_online(display_name: &str) -> RosterEntry {
+    let user_id = format!("usr_{}", display_name.to_lowercase());
+    RosterEntry::new(mock_friend(&user_id, display_name, "wrld_home:1"), None)
+}
+
+fn missed_online_bodies(settings: &AppSettings, entries: &[RosterEntry]) -> Vec<String> {
+    compose_missed_online(settings, entries, false)
+        .into_iter()
+        .map(|(notification, _)| notification.body)
+        .collect()
+}
+
+#[test]
+fn summarizes_friends_that_came_online_while_disconnected() {
+    let settings = AppSettings::default();
+    assert_eq!(
+        settings.missed_online_notifications,
+        MissedOnlineMode::Batched
+    );
+    assert!(compose_missed_online(&settings, &[], false).is_empty());
+
+    let entries = [came_online("Carol")];
+    let missed = compose_missed_online(&settings, &entries, false);
+    assert_eq!(missed.len(), 1);
+    assert_eq!(missed[0].0.title, "While you were disconnected");
+    assert_eq!(missed[0].0.body, "Carol came online.");
+
+    let entries = ["Alice", "Bob", "Carol", "Dave"].map(came_online);
+    let missed = compose_missed_online(&settings, &entries, false);
+    assert_eq!(missed.len(), 1);
+    assert_eq!(
+        missed[0].0.body,
+        "Alice, Bob, Carol and 1 other came online."
+    );
+    assert_eq!(missed[0].1.len(), 4);
+}
+
+#[test]
+fn notifies_friends_that_came_online_while_disconnected_as_the_mode_says() {
+    let mut settings = AppSettings::default();
+    settings.friend_settings.insert(
+        String::from("usr_bob"),
+        FriendSettings {
+            enabled: false,
+            ..FriendSettings::default()
+        },
+    );
+    let entries = ["Alice", "Bob", "Carol"].map(came_online);
+
+    assert_eq!(
+        missed_online_bodies(&settings, &entries),
+        ["Alice and Carol came online."]
+    );
+
+    settings.missed_online_notifications = MissedOnlineMode::Individual;
+    assert_eq!(
+        missed_online_bodies(&settings, &entries),
+        ["Alice is now online!", "Carol is now online!"]
+    );
+
+    settings.missed_online_notifications = MissedOnlineMode::Off;
+    assert!(missed_online_bodies(&settings, &entries).is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn notifies_missed_friends_only_after_reconnecting() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+    let roster = app.state::<FriendRoster>();
+
+    roster.replace_all(
+        vec![
+            mock_friend("usr_carol", "Carol", "offline"),
+            mock_friend("usr_dave", "Dave", "offline"),
+        ],
+        ResyncReason::Seed,
+    );
+    roster.replace_all(
+        vec![
+            mock_friend("usr_carol", "Carol", "wrld_home:1"),
+            mock_friend("usr_dave", "Dave", "offline"),
+        ],
+        ResyncReason::Periodic,
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(notifications.shown().is_empty());
+
+    roster.replace_all(
+        vec![
+            mock_friend("usr_carol", "Carol", "wrld_home:1"),
+            mock_friend("usr_dave", "Dave", "wrld_home:1"),
+        ],
+        ResyncReason::Reconnect,
+    );
+    assert!(wait_for_shown(&notifications, 1).await);
+    let bodies: Vec<String> = notifications
+        .shown()
+        .into_iter()
+        .map(|notification| notification.body)
+        .collect();
+    assert_eq!(bodies, ["Dave came online."]);
+}
+
+#[test]
+fn skips_offline_and_location_notifications_of_disabled_friends() {
+    let mut settings = AppSettings {
+        offline_notifications_enabled: true,
+        location_notifications_enabled: true,
+        ..AppSettings::default()
+    };
+    settings.friend_settings.insert(
+        String::from("usr_bob"),
+        FriendSettings {
+            enabled: false,
+            ..FriendSettings::default()
+        },
+    );
+    let alice = RosterEntry::new(mock_friend("usr_alice", "Alice", "wrld_home:1"), None);
+    let bob = RosterEntry::new(mock_friend("usr_bob", "Bob", "wrld_home:1"), None);
+    let now = SystemTime::now();
+
+    assert!(compose_friend_offline(&settings, &alice, now, false).is_some());
+    assert!(compose_friend_offline(&settings, &bob, now, false).is_none());
+    assert!(compose_friend_location(&settings, &alice, None, false).is_some());
+    assert!(compose_friend_location(&settings, &bob, None, false).is_none());
+}
+
+#[test]
+fn friends_online_at_the_seed_are_online_since_their_last_login() {
+    let mut friend =
+        serde_json::to_value(mock_friend("usr_alice", "Alice", "wrld_home:1")).unwrap();
+    friend["last_login"] = serde_json::Value::from("2026-10-18T10:00:00Z");
+    let friend: models::LimitedUserFriend = serde_json::from_value(friend).unwrap();
+    let roster = FriendRoster::default();
+    roster.replace_all(vec![friend], ResyncReason::Seed);
+
+    let entry = roster.get("usr_alice").unwrap();
+    let last_login =
+        SystemTime::from(chrono::DateTime::parse_from_rfc3339("2026-10-18T10:00:00Z").unwrap());
+    assert_eq!(entry.online_since, Some(last_login));
+
+    let settings = AppSettings {
+        offline_notifications_enabled: true,
+        ..AppSettings::default()
+    };
+    let offline_at = last_login + Duration::from_secs(90 * 60);
+    let notification = compose_friend_offline(&settings, &entry, offline_at, false).unwrap();
+    assert_eq!(
+        notification.body,
+        "Alice is now offline. (online for 1h 30m)"
+    );
+}
@@ -0,0 +1,466 @@
+mod common;
+
+use common::{is_auth_event, logged_in_app, RECONNECT_TIMEOUT, TIMEOUT};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::test::MockRuntime;
+use tauri::{App, Manager};
+use vfriends_lib::test_support::{
+    mock_friend, ConnectionState, EventLog, FriendRoster, FriendSettings, MockAccount, MockServer,
+    PipelineEvent, PipelineEventKind, RecordingBackend, SettingsStore, WebsocketMessage,
+    WebsocketState,
+};
+use vrchatapi::models;
+
+async fn wait_until(condition: impl Fn() -> bool, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+fn is_online(app: &App<MockRuntime>, user_id: &str) -> bool {
+    app.state::<FriendRoster>()
+        .get(user_id)
+        .is_some_and(|entry| entry.is_online())
+}
+
+async fn seeded_app(server: &MockServer) -> App<MockRuntime> {
+    let app = logged_in_app(server).await;
+    let roster_seeded = wait_until(|| app.state::<FriendRoster>().is_seeded(), TIMEOUT).await;
+    assert!(roster_seeded, "The roster was not seeded after connecting.");
+    app
+}
+
+fn friend_online_event(user_id: &str, display_name: &str, location: &str) -> serde_json::Value {
+    let user = models::User {
+        id: user_id.to_string(),
+        display_name: display_name.to_string(),
+        ..Default::default()
+    };
+    json!({
+        "userId": user_id,
+        "platform": "standalonewindows",
+        "location": location,
+        "canRequestInvite": true,
+        "user": user,
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connects_after_login_and_seeds_the_roster() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![
+        mock_friend("usr_alice", "Alice", "wrld_home:1"),
+        mock_friend("usr_bob", "Bob", "offline"),
+    ]);
+    let app = seeded_app(&server).await;
+
+    assert!(server.wait_for_pipeline_connections(1, TIMEOUT).await);
+    assert!(matches!(
+        app.state::<WebsocketState>().connection_state(),
+        ConnectionState::Connected { .. }
+    ));
+    assert!(is_online(&app, "usr_alice"));
+    assert!(!is_online(&app, "usr_bob"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn applies_scripted_friend_events() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![mock_friend("usr_bob", "Bob", "offline")]);
+    let app = seeded_app(&server).await;
+
+    server.send_pipeline_event(
+        "friend-online",
+        &friend_online_event("usr_bob", "Bob", "wrld_home:2"),
+    );
+    assert!(wait_until(|| is_online(&app, "usr_bob"), TIMEOUT).await);
+
+    server.send_pipeline_event(
+        "friend-offline",
+        &json!({ "userId": "usr_bob", "platform": "" }),
+    );
+    assert!(wait_until(|| !is_online(&app, "usr_bob"), TIMEOUT).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reconnects_and_resyncs_after_a_dropped_connection() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![mock_friend("usr_carol", "Carol", "offline")]);
+    let app = seeded_app(&server).await;
+    let connection_states = EventLog::listen(app.handle(), "vrc:connection-state");
+    assert!(server.wait_for_pipeline_connections(1, TIMEOUT).await);
+
+    server.disconnect_pipeline();
+    // Carol comes online while the pipeline is down, so only the resync can notice.
+    server.set_friends(vec![mock_friend("usr_carol", "Carol", "wrld_home:3")]);
+
+    connection_states
+        .wait_for(|payload| payload["state"] == "reconnecting", TIMEOUT)
+        .await
+        .expect("The dropped connection was not reported.");
+    assert!(
+        server
+            .wait_for_pipeline_connections(2, RECONNECT_TIMEOUT)
+            .await
+    );
+    connection_states
+        .wait_for(|payload| payload["state"] == "connected", TIMEOUT)
+        .await
+        .expect("The reconnect was not reported.");
+    assert!(wait_until(|| is_online(&app, "usr_carol"), TIMEOUT).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn asks_to_log_in_again_when_the_pipeline_rejects_the_session() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = seeded_app(&server).await;
+    let auth_events = EventLog::listen(app.handle(), "vrc:auth");
+    assert!(server.wait_for_pipeline_connections(1, TIMEOUT).await);
+
+    server.expire_sessions();
+    server.disconnect_pipeline();
+
+    let failure = auth_events
+        .wait_for(is_auth_event("failure"), RECONNECT_TIMEOUT)
+        .await
+        .expect("The expired session was not reported.");
+    assert_eq!(failure["code"], "sessionExpired");
+    assert_eq!(
+        app.state::<WebsocketState>().connection_state(),
+        ConnectionState::Disconnected {
+            reason: Some(String::from("Session expired"))
+        }
+    );
+    assert!(!app.state::<FriendRoster>().is_seeded());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restores_a_session_the_pipeline_rejects_and_notifies_missed_friends() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![mock_friend("usr_carol", "Carol", "offline")]);
+    let app = seeded_app(&server).await;
+    let auth_events = EventLog::listen(app.handle(), "vrc:auth");
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+    assert!(server.wait_for_pipeline_connections(1, TIMEOUT).await);
+
+    // The API still accepts the session, so it is restored and the pipeline connects again.
+    server.reject_pipeline_next(1);
+    server.disconnect_pipeline();
+    server.set_friends(vec![mock_friend("usr_carol", "Carol", "wrld_home:3")]);
+
+    assert!(
+        server
+            .wait_for_pipeline_connections(2, RECONNECT_TIMEOUT)
+            .await
+    );
+    assert!(wait_until(|| !notifications.shown().is_empty(), TIMEOUT).await);
+    let shown = notifications.shown();
+    assert_eq!(shown.len(), 1);
+    assert_eq!(shown[0].title, "While you were disconnected");
+    assert_eq!(shown[0].body, "Carol came online.");
+    assert!(!auth_events
+        .payloads()
+        .iter()
+        .any(|payload| is_auth_event("success")(payload)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gives_up_on_a_session_the_pipeline_keeps_rejecting() {
+    // One reconnect delay, then restores after 0, 5 and 10 seconds.
+    const GIVE_UP_TIMEOUT: Duration = Duration::from_secs(40);
+
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = seeded_app(&server).await;
+    let auth_events = EventLog::listen(app.handle(), "vrc:auth");
+    assert!(server.wait_for_pipeline_connections(1, TIMEOUT).await);
+
+    server.reject_pipeline_next(usize::MAX);
+    server.disconnect_pipeline();
+
+    let failure = auth_events
+        .wait_for(is_auth_event("failure"), GIVE_UP_TIMEOUT)
+        .await
+        .expect("The rejected session was not given up on.");
+    assert_eq!(failure["code"], "sessionExpired");
+    assert!(!auth_events
+        .payloads()
+        .iter()
+        .any(|payload| is_auth_event("success")(payload)));
+    assert_eq!(server.pipeline_connections(), 1);
+    assert!(!app.state::<FriendRoster>().is_seeded());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_disconnected_after_logout() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = seeded_app(&server).await;
+
+    app.state::<vfriends_lib::test_support::AuthState>()
+        .logout(app.handle())
+        .unwrap();
+
+    assert_eq!(
+        app.state::<WebsocketState>().connection_state(),
+        ConnectionState::Disconnected { reason: None }
+    );
+    assert!(!app.state::<FriendRoster>().is_seeded());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shows_a_notification_when_a_friend_comes_online() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![mock_friend("usr_bob", "Bob", "offline")]);
+    let app = seeded_app(&server).await;
+    app.state::<SettingsStore>()
+        .consume(|settings| settings.online_batch_window_ms = 0)
+        .unwrap();
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+
+    server.send_pipeline_event(
+        "friend-online",
+        &friend_online_event("usr_bob", "Bob", "wrld_home:2"),
+    );
+    assert!(wait_until(|| !notifications.shown().is_empty(), TIMEOUT).await);
+
+    let shown = notifications.shown();
+    assert_eq!(shown.len(), 1);
+    assert_eq!(shown[0].title, "Bob");
+    assert_eq!(shown[0].body, "Bob is now online!");
+    assert!(!shown[0].silent);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn does_not_take_events_about_unknown_users_for_new_friends() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = seeded_app(&server).await;
+    app.state::<SettingsStore>()
+        .consume(|settings| {
+            settings.online_batch_window_ms = 0;
+            settings
+                .friend_settings
+                .insert(String::from("usr_bob"), FriendSettings::default());
+            settings.archive_friend_settings("usr_bob", "Bob");
+        })
+        .unwrap();
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+
+    server.send_pipeline_event(
+        "friend-online",
+        &friend_online_event("usr_bob", "Bob", "wrld_home:2"),
+    );
+    assert!(wait_until(|| is_online(&app, "usr_bob"), TIMEOUT).await);
+    assert!(wait_until(|| !notifications.shown().is_empty(), TIMEOUT).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let bodies: Vec<String> = notifications
+        .shown()
+        .into_iter()
+        .map(|notification| notification.body)
+        .collect();
+    assert_eq!(bodies, ["Bob is now online!"]);
+    let settings = app.state::<SettingsStore>().snapshot();
+    assert!(settings.archived_friend_settings.contains_key("usr_bob"));
+    assert!(!settings.friend_settings.contains_key("usr_bob"));
+}
+
+/// Decodes a frame as the pipeline sends it, with the content encoded as a JSON string.
+fn decode(kind: &str, content: &str) -> Option<PipelineEvent> {
+    let frame = json!({ "type": kind, "content": content }).to_string();
+    WebsocketMessage::from_str(&frame)
+        .expect("The frame did not parse.")
+        .decode()
+}
+
+#[test]
+fn decodes_every_type_of_pipeline_event() {
+    let user = serde_json::to_value(models::User {
+        id: String::from("usr_alice"),
+        display_name: String::from("Alice"),
+        ..Default::default()
+    })
+    .unwrap();
+    let notification = serde_json::to_value(models::Notification {
+        id: String::from("not_1"),
+        sender_user_id: String::from("usr_alice"),
+        ..Default::default()
+    })
+    .unwrap();
+    let updated_user = json!({ "id": "usr_me", "displayName": "Me", "status": "busy" });
+
+    let frames = [
+        (
+            "friend-add",
+            json!({ "userId": "usr_alice", "user": user }),
+            PipelineEventKind::FriendAdd,
+        ),
+        (
+            "friend-delete",
+            json!({ "userId": "usr_alice" }),
+            PipelineEventKind::FriendDelete,
+        ),
+        (
+            "friend-active",
+            json!({ "userid": "usr_alice", "platform": "web", "user": user }),
+            PipelineEventKind::FriendActive,
+        ),
+        (
+            "friend-online",
+            friend_online_event("usr_alice", "Alice", "wrld_home:1"),
+            PipelineEventKind::FriendOnline,
+        ),
+        (
+            "friend-offline",
+            json!({ "userId": "usr_alice", "platform": "standalonewindows" }),
+            PipelineEventKind::FriendOffline,
+        ),
+        (
+            "friend-update",
+            json!({ "userId": "usr_alice", "user": user }),
+            PipelineEventKind::FriendUpdate,
+        ),
+        (
+            "friend-location",
+            json!({
+                "userId": "usr_alice",
+                "location": "wrld_away:2",
+                "travelingToLocation": "",
+                "worldId": "wrld_away",
+                "canRequestInvite": true,
+                "user": user,
+            }),
+            PipelineEventKind::FriendLocation,
+        ),
+        (
+            "user-update",
+            json!({ "userId": "usr_me", "user": updated_user }),
+            PipelineEventKind::UserUpdate,
+        ),
+        (
+            "user-location",
+            json!({ "userId": "usr_me", "location": "wrld_home:1", "worldId": "wrld_home" }),
+            PipelineEventKind::UserLocation,
+        ),
+        (
+            "user-badge-assigned",
+            json!({ "badge": { "badgeId": "bdg_1" } }),
+            PipelineEventKind::UserBadgeAssigned,
+        ),
+        (
+            "user-badge-unassigned",
+            json!({ "badgeId": "bdg_1" }),
+            PipelineEventKind::UserBadgeUnassigned,
+        ),
+        (
+            "content-refresh",
+            json!({ "contentType": "avatar", "itemId": "avtr_1", "actionType": "created" }),
+            PipelineEventKind::ContentRefresh,
+        ),
+        (
+            "instance-queue-joined",
+            json!({ "instanceLocation": "wrld_home:1", "position": 3 }),
+            PipelineEventKind::InstanceQueueJoined,
+        ),
+        (
+            "instance-queue-ready",
+            json!({ "instanceLocation": "wrld_home:1", "expiry": "2026-10-18T10:00:00Z" }),
+            PipelineEventKind::InstanceQueueReady,
+        ),
+        (
+            "notification",
+            notification,
+            PipelineEventKind::Notification,
+        ),
+        (
+            "response-notification",
+            json!({ "notificationId": "not_1", "receiverId": "usr_me", "responseId": "not_2" }),
+            PipelineEventKind::ResponseNotification,
+        ),
+        (
+            "notification-v2",
+            json!({ "id": "not_3", "type": "group.announcement" }),
+            PipelineEventKind::NotificationV2,
+        ),
+        (
+            "notification-v2-update",
+            json!({ "id": "not_3", "version": 2, "updates": { "seen": true } }),
+            PipelineEventKind::NotificationV2Update,
+        ),
+        (
+            "notification-v2-delete",
+            json!({ "ids": ["not_3"], "version": 3 }),
+            PipelineEventKind::NotificationV2Delete,
+        ),
+        (
+            "group-joined",
+            json!({ "groupId": "grp_1" }),
+            PipelineEventKind::GroupJoined,
+        ),
+        (
+            "group-left",
+            json!({ "groupId": "grp_1" }),
+            PipelineEventKind::GroupLeft,
+        ),
+        (
+            "group-member-updated",
+            json!({ "member": { "groupId": "grp_1", "userId": "usr_me" } }),
+            PipelineEventKind::GroupMemberUpdated,
+        ),
+        (
+            "group-role-updated",
+            json!({ "role": { "id": "grol_1", "groupId": "grp_1" } }),
+            PipelineEventKind::GroupRoleUpdated,
+        ),
+    ];
+    for (kind, content, expected) in frames {
+        let event = decode(kind, &content.to_string());
+        assert_eq!(event.map(|event| event.kind()), Some(expected), "{kind}");
+    }
+
+    // These carry a bare notification id, or nothing, instead of JSON.
+    let seen = decode("see-notification", "not_1");
+    assert!(matches!(seen, Some(PipelineEvent::SeeNotification(id)) if id == "not_1"));
+    let hidden = decode("hide-notification", "not_1");
+    assert!(matches!(hidden, Some(PipelineEvent::HideNotification(id)) if id == "not_1"));
+    let cleared = decode("clear-notification", "");
+    assert!(matches!(cleared, Some(PipelineEvent::ClearNotification)));
+}
+
+#[test]
+fn keeps_the_typed_content_of_a_decoded_event() {
+    let content = friend_online_event("usr_alice", "Alice", "wrld_home:1").to_string();
+    let Some(PipelineEvent::FriendOnline(event)) = decode("friend-online", &content) else {
+        panic!("The event was not decoded as friend-online.");
+    };
+    assert_eq!(event.user_id, "usr_alice");
+    assert_eq!(event.location, "wrld_home:1");
+    assert_eq!(event.user.display_name, "Alice");
+}
+
+#[test]
+fn keeps_unknown_events_as_raw_json() {
+    let event = decode("world-exploded", r#"{"worldId":"wrld_home"}"#);
+    assert!(matches!(
+        event,
+        Some(PipelineEvent::Unknown { kind, content })
+            if kind == "world-exploded" && content == json!({ "worldId": "wrld_home" })
+    ));
+
+    let event = decode("world-exploded", "not json");
+    assert!(matches!(
+        event,
+        Some(PipelineEvent::Unknown { content, .. }) if content == json!("not json")
+    ));
+}
+
+#[test]
+fn drops_a_known_event_with_unexpected_content() {
+    assert!(decode("friend-online", r#"{"userId":"usr_alice"}"#).is_none());
+    assert!(decode("friend-delete", "usr_alice").is_none());
+}
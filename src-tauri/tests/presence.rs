@@ -0,0 +1,302 @@
+mod common;
+
+use chrono::{DateTime, Local, TimeZone};
+use common::{logged_in_app, RECONNECT_TIMEOUT, TIMEOUT};
+use std::time::Duration;
+use tauri::Manager;
+use vfriends_lib::test_support::{
+    co_presence_graph, mock_friend, presence_stats, top_companions, AuthState, MockAccount,
+    MockServer, Observation, PresenceStore, Session,
+};
+
+const FRIEND: &str = "usr_alice";
+
+/// 2026-10-12 is a Monday.
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    Local
+        .with_ymd_and_hms(2026, 10, day, hour, minute, 0)
+        .single()
+        .expect("Ambiguous local time.")
+}
+
+fn observe(store: &PresenceStore, time: DateTime<Local>, location: Option<&str>, exact: bool) {
+    observe_friend(store, FRIEND, time, location, exact);
+}
+
+fn observe_friend(
+    store: &PresenceStore,
+    friend_id: &str,
+    time: DateTime<Local>,
+    location: Option<&str>,
+    exact: bool,
+) {
+    store
+        .observe_all(&[Observation {
+            friend_id,
+            display_name: friend_id.trim_start_matches("usr_"),
+            location,
+            platform: "standalonewindows",
+            at: time.timestamp_millis(),
+            exact,
+        }])
+        .expect("Failed to observe.");
+}
+
+fn store_with_a_week() -> PresenceStore {
+    let store = PresenceStore::in_memory().expect("Failed to open the presence history.");
+    observe(&store, at(12, 20, 0), Some("wrld_home:1"), true);
+    observe(&store, at(12, 21, 0), Some("wrld_club:2"), true);
+    observe(&store, at(12, 22, 30), None, true);
+    observe(&store, at(13, 23, 30), Some("private"), true);
+    observe(&store, at(14, 0, 30), None, true);
+    // Already online when the app started.
+    observe(&store, at(14, 11, 0), Some("wrld_home:1"), false);
+    store
+}
+
+#[test]
+fn aggregates_sessions_of_the_week() {
+    let store = store_with_a_week();
+
+    let stats = presence_stats(&store, FRIEND, 4, at(14, 12, 0)).unwrap();
+
+    assert_eq!(stats.display_name.as_deref(), Some("alice"));
+    assert!(stats.online);
+    assert_eq!(stats.last_seen, Some(at(14, 12, 0).timestamp_millis()));
+    assert_eq!(stats.session_count, 2);
+    assert_eq!(stats.average_session_secs, Some((150 + 60) * 60 / 2));
+    assert_eq!(stats.week_total_secs, (150 + 60 + 60) * 60);
+}
+
+#[test]
+fn spreads_sessions_over_local_hours() {
+    let store = store_with_a_week();
+
+    let heatmap = presence_stats(&store, FRIEND, 4, at(14, 12, 0))
+        .unwrap()
+        .heatmap;
+
+    assert_eq!(heatmap.len(), 7);
+    assert_eq!(heatmap[0][20..23], [60, 60, 30]);
+    // Crossing midnight splits the session between Tuesday and Wednesday.
+    assert_eq!(heatmap[1][23], 30);
+    assert_eq!(heatmap[2][0], 30);
+    assert_eq!(heatmap[2][11], 60);
+    let total: u32 = heatmap.iter().flatten().sum();
+    assert_eq!(total, 150 + 60 + 60);
+}
+
+#[test]
+fn last_seen_is_the_end_of_the_last_session() {
+    let store = store_with_a_week();
+    observe(&store, at(14, 11, 45), None, true);
+
+    let stats = presence_stats(&store, FRIEND, 4, at(14, 12, 0)).unwrap();
+
+    assert!(!stats.online);
+    assert_eq!(stats.last_seen, Some(at(14, 11, 45).timestamp_millis()));
+    // The last session started before the app was watching.
+    assert_eq!(stats.session_count, 2);
+}
+
+#[test]
+fn repeated_observations_do_not_open_sessions() {
+    let store = PresenceStore::in_memory().unwrap();
+    observe(&store, at(12, 20, 0), Some("wrld_home:1"), true);
+    observe(&store, at(12, 20, 10), Some("wrld_home:1"), true);
+    observe(&store, at(12, 21, 0), None, true);
+    observe(&store, at(12, 21, 5), None, true);
+
+    let stats = presence_stats(&store, FRIEND, 4, at(12, 22, 0)).unwrap();
+
+    assert_eq!(stats.session_count, 1);
+    assert_eq!(stats.average_session_secs, Some(60 * 60));
+}
+
+/// Alice and Bob spend an hour together, Carol joins Alice for 30 minutes and passes Bob in a
+/// private world.
+fn store_with_meetings() -> PresenceStore {
+    let store = PresenceStore::in_memory().unwrap();
+    observe_friend(
+        &store,
+        "usr_alice",
+        at(12, 20, 0),
+        Some("wrld_home:1~friends(usr_x)"),
+        true,
+    );
+    observe_friend(
+        &store,
+        "usr_bob",
+        at(12, 20, 15),
+        Some("wrld_home:1~friends(usr_x)"),
+        true,
+    );
+    observe_friend(&store, "usr_carol", at(12, 20, 0), Some("private"), true);
+    observe_friend(&store, "usr_bob", at(12, 21, 15), Some("private"), true);
+    observe_friend(
+        &store,
+        "usr_carol",
+        at(12, 21, 20),
+        Some("wrld_home:1~friends(usr_x)"),
+        true,
+    );
+    observe_friend(
+        &store,
+        "usr_alice",
+        at(12, 21, 50),
+        Some("wrld_club:2"),
+        true,
+    );
+    // Carol only passes through the club.
+    observe_friend(
+        &store,
+        "usr_carol",
+        at(12, 21, 51),
+        Some("wrld_club:2"),
+        true,
+    );
+    observe_friend(&store, "usr_carol", at(12, 21, 52), None, true);
+    observe_friend(&store, "usr_alice", at(12, 23, 0), None, true);
+    observe_friend(&store, "usr_bob", at(12, 23, 0), None, true);
+    store
+}
+
+#[test]
+fn weighs_the_graph_by_time_in_the_same_instance() {
+    let store = store_with_meetings();
+    let since = at(12, 0, 0).timestamp_millis();
+    let until = at(13, 0, 0).timestamp_millis();
+
+    let graph = co_presence_graph(&store, since, until).unwrap();
+
+    let edges: Vec<_> = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            (
+                edge.source.as_str(),
+                edge.target.as_str(),
+                edge.together_secs,
+            )
+        })
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            ("usr_alice", "usr_bob", 60 * 60),
+            ("usr_alice", "usr_carol", 30 * 60)
+        ]
+    );
+    assert_eq!(graph.nodes[0].friend_id, "usr_alice");
+    assert_eq!(graph.nodes[0].together_secs, 90 * 60);
+    assert_eq!(graph.nodes[0].display_name.as_deref(), Some("alice"));
+}
+
+#[test]
+fn clips_meetings_to_the_range() {
+    let store = store_with_meetings();
+    let since = at(12, 20, 45).timestamp_millis();
+    let until = at(12, 21, 0).timestamp_millis();
+
+    let graph = co_presence_graph(&store, since, until).unwrap();
+
+    assert_eq!(graph.edges.len(), 1);
+    assert_eq!(graph.edges[0].together_secs, 15 * 60);
+}
+
+#[test]
+fn lists_the_companions_of_a_friend() {
+    let store = store_with_meetings();
+    let since = at(12, 0, 0).timestamp_millis();
+    let until = at(13, 0, 0).timestamp_millis();
+
+    let companions = top_companions(&store, "usr_carol", since, until, 5).unwrap();
+    assert_eq!(companions.len(), 1);
+    assert_eq!(companions[0].friend_id, "usr_alice");
+    assert_eq!(companions[0].encounters, 1);
+
+    let companions = top_companions(&store, "usr_alice", since, until, 1).unwrap();
+    let friend_ids: Vec<_> = companions.iter().map(|c| c.friend_id.as_str()).collect();
+    assert_eq!(friend_ids, ["usr_bob"]);
+}
+
+async fn wait_for_sessions(
+    store: &PresenceStore,
+    friend_id: &str,
+    condition: impl Fn(&[Session]) -> bool,
+    timeout: Duration,
+) -> Vec<Session> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let sessions = store.sessions_since(friend_id, 0).unwrap();
+        if condition(&sessions) || tokio::time::Instant::now() >= deadline {
+            return sessions;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn is_open(session: &Session) -> bool {
+    session.ended_at.is_none()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn closes_open_sessions_after_logging_out() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![mock_friend("usr_alice", "Alice", "wrld_home:1")]);
+    let app = logged_in_app(&server).await;
+    let store = app.state::<PresenceStore>();
+    let sessions = wait_for_sessions(
+        &store,
+        "usr_alice",
+        |sessions| sessions.iter().any(is_open),
+        TIMEOUT,
+    )
+    .await;
+    assert_eq!(sessions.len(), 1);
+
+    app.state::<AuthState>().logout(app.handle()).unwrap();
+
+    let sessions = store.sessions_since("usr_alice", 0).unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert!(!is_open(&sessions[0]));
+    assert!(!sessions[0].exact);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reopens_sessions_after_the_pipeline_reconnects() {
+    let server = MockServer::start(MockAccount::default()).await;
+    server.set_friends(vec![mock_friend("usr_alice", "Alice", "wrld_home:1")]);
+    let app = logged_in_app(&server).await;
+    let store = app.state::<PresenceStore>();
+    wait_for_sessions(
+        &store,
+        "usr_alice",
+        |sessions| sessions.iter().any(is_open),
+        TIMEOUT,
+    )
+    .await;
+    assert!(server.wait_for_pipeline_connections(1, TIMEOUT).await);
+
+    server.disconnect_pipeline();
+    let sessions = wait_for_sessions(
+        &store,
+        "usr_alice",
+        |sessions| !sessions.iter().any(is_open),
+        TIMEOUT,
+    )
+    .await;
+    assert_eq!(sessions.len(), 1);
+    assert!(!is_open(&sessions[0]));
+
+    // Alice stayed online, so the resync after reconnecting finds her again.
+    let sessions = wait_for_sessions(
+        &store,
+        "usr_alice",
+        |sessions| sessions.len() == 2,
+        RECONNECT_TIMEOUT + TIMEOUT,
+    )
+    .await;
+    assert_eq!(sessions.len(), 2);
+    assert!(is_open(&sessions[1]));
+}
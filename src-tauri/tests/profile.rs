@@ -0,0 +1,231 @@
+mod common;
+
+use common::TIMEOUT;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Manager;
+use vfriends_lib::test_support::{
+    compose_profile_change, mock_app, mock_friend, profile_changes, AppSettings,
+    FriendLocationEvent, FriendRoster, FriendSettings, MockAccount, MockServer, PipelineEvent,
+    ProfileChange, ProfileSubscription, RecordingBackend, ResyncReason, RosterEntry, SettingsStore,
+};
+use vrchatapi::models;
+
+fn alice() -> models::LimitedUserFriend {
+    mock_friend("usr_alice", "Alice", "wrld_home:1")
+}
+
+fn alice_with(update: impl FnOnce(&mut models::LimitedUserFriend)) -> models::LimitedUserFriend {
+    let mut friend = alice();
+    update(&mut friend);
+    friend
+}
+
+fn subscribed_to(subscriptions: Vec<ProfileSubscription>) -> AppSettings {
+    let mut settings = AppSettings::default();
+    settings.friend_settings.insert(
+        String::from("usr_alice"),
+        FriendSettings {
+            profile_subscriptions: subscriptions,
+            ..FriendSettings::default()
+        },
+    );
+    settings
+}
+
+fn body_of(settings: &AppSettings, change: &ProfileChange) -> Option<String> {
+    let entry = RosterEntry::new(alice(), None);
+    compose_profile_change(settings, &entry, change, false).map(|notification| notification.body)
+}
+
+fn status_change(from: models::UserStatus, to: models::UserStatus) -> ProfileChange {
+    ProfileChange::Status { from, to }
+}
+
+#[test]
+fn reports_status_switches_but_not_coming_online_or_going_offline() {
+    let busy = alice_with(|friend| friend.status = models::UserStatus::Busy);
+    assert_eq!(
+        profile_changes(&alice(), &busy),
+        [status_change(
+            models::UserStatus::Active,
+            models::UserStatus::Busy
+        )]
+    );
+
+    let offline = alice_with(|friend| friend.status = models::UserStatus::Offline);
+    assert!(profile_changes(&alice(), &offline).is_empty());
+    assert!(profile_changes(&offline, &busy).is_empty());
+    assert!(profile_changes(&alice(), &alice()).is_empty());
+}
+
+#[test]
+fn reports_a_new_status_description() {
+    let described = alice_with(|friend| friend.status_description = String::from("Sleepy"));
+    assert_eq!(
+        profile_changes(&alice(), &described),
+        [ProfileChange::StatusDescription {
+            from: String::new(),
+            to: String::from("Sleepy"),
+        }]
+    );
+}
+
+#[test]
+fn reports_a_new_bio_but_not_a_missing_one() {
+    let empty = alice_with(|friend| friend.bio = Some(String::new()));
+    assert!(profile_changes(&alice(), &empty).is_empty());
+
+    let written = alice_with(|friend| friend.bio = Some(String::from("Hello!")));
+    assert_eq!(
+        profile_changes(&empty, &written),
+        [ProfileChange::Bio {
+            from: String::new(),
+            to: String::from("Hello!"),
+        }]
+    );
+}
+
+#[test]
+fn reports_a_new_avatar_only_with_an_image_url() {
+    let with_avatar = |url: &str| {
+        alice_with(|friend| {
+            friend.current_avatar_image_url = Some(url.to_string());
+            friend.current_avatar_thumbnail_image_url = Some(format!("{url}/thumbnail"));
+        })
+    };
+    let before = with_avatar("https://example.com/avatar/1");
+
+    assert!(profile_changes(&before, &with_avatar("")).is_empty());
+    assert!(profile_changes(&before, &before.clone()).is_empty());
+    assert_eq!(
+        profile_changes(&before, &with_avatar("https://example.com/avatar/2")),
+        [ProfileChange::Avatar {
+            thumbnail_url: Some(String::from("https://example.com/avatar/2/thumbnail")),
+        }]
+    );
+}
+
+#[test]
+fn notifies_only_about_subscribed_changes() {
+    let to_busy = status_change(models::UserStatus::Active, models::UserStatus::Busy);
+    let to_join_me = status_change(models::UserStatus::Active, models::UserStatus::JoinMe);
+    let bio = ProfileChange::Bio {
+        from: String::new(),
+        to: String::from("Hello!"),
+    };
+
+    assert_eq!(body_of(&AppSettings::default(), &to_busy), None);
+    assert_eq!(body_of(&subscribed_to(Vec::new()), &to_busy), None);
+
+    let any_status = subscribed_to(vec![ProfileSubscription::Status { to: None }]);
+    assert_eq!(
+        body_of(&any_status, &to_busy).as_deref(),
+        Some("Alice is now Busy.")
+    );
+    assert_eq!(body_of(&any_status, &bio), None);
+
+    let only_join_me = subscribed_to(vec![ProfileSubscription::Status {
+        to: Some(models::UserStatus::JoinMe),
+    }]);
+    assert_eq!(body_of(&only_join_me, &to_busy), None);
+    assert_eq!(
+        body_of(&only_join_me, &to_join_me).as_deref(),
+        Some("Alice is now Join Me.")
+    );
+}
+
+#[test]
+fn renders_each_kind_of_profile_change() {
+    let settings = subscribed_to(vec![
+        ProfileSubscription::StatusDescription,
+        ProfileSubscription::Bio,
+        ProfileSubscription::Avatar,
+    ]);
+    let description = |to: &str| ProfileChange::StatusDescription {
+        from: String::from("Sleepy"),
+        to: to.to_string(),
+    };
+
+    assert_eq!(
+        body_of(&settings, &description("Busy coding")).as_deref(),
+        Some("Alice: \"Busy coding\"")
+    );
+    assert_eq!(
+        body_of(&settings, &description("")).as_deref(),
+        Some("Alice cleared their status.")
+    );
+    assert_eq!(
+        body_of(
+            &settings,
+            &ProfileChange::Bio {
+                from: String::new(),
+                to: String::from("Hello!"),
+            }
+        )
+        .as_deref(),
+        Some("Alice updated their bio.")
+    );
+    assert_eq!(
+        body_of(
+            &settings,
+            &ProfileChange::Avatar {
+                thumbnail_url: None
+            }
+        )
+        .as_deref(),
+        Some("Alice changed their avatar.")
+    );
+}
+
+#[test]
+fn skips_profile_changes_of_disabled_friends() {
+    let mut settings = subscribed_to(vec![ProfileSubscription::Status { to: None }]);
+    if let Some(friend_settings) = settings.friend_settings.get_mut("usr_alice") {
+        friend_settings.enabled = false;
+    }
+    let to_busy = status_change(models::UserStatus::Active, models::UserStatus::Busy);
+    assert_eq!(body_of(&settings, &to_busy), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn notifies_a_status_change_that_arrives_with_a_location_event() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+    app.state::<SettingsStore>()
+        .consume(|settings| {
+            *settings = subscribed_to(vec![ProfileSubscription::Status { to: None }])
+        })
+        .unwrap();
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+    let roster = app.state::<FriendRoster>();
+    roster.replace_all(vec![alice()], ResyncReason::Seed);
+
+    let user = models::User {
+        id: String::from("usr_alice"),
+        display_name: String::from("Alice"),
+        status: models::UserStatus::Busy,
+        ..Default::default()
+    };
+    assert!(
+        roster.apply(&PipelineEvent::FriendLocation(FriendLocationEvent {
+            user_id: String::from("usr_alice"),
+            location: String::from("wrld_away:2"),
+            traveling_to_location: None,
+            world_id: None,
+            can_request_invite: None,
+            user,
+        }))
+    );
+
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while notifications.shown().is_empty() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let bodies: Vec<String> = notifications
+        .shown()
+        .into_iter()
+        .map(|notification| notification.body)
+        .collect();
+    assert_eq!(bodies, ["Alice is now Busy."]);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use common::TIMEOUT;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Manager;
+use vfriends_lib::test_support::{
+    mock_app, mock_friend, replay, FlapSuppression, FriendRoster, FriendSettings, HistoryQuery,
+    HistoryStore, MockAccount, MockServer, NotifierScope, OnlineVerdict, PipelineRecorder,
+    RecordingBackend, ReplaySpeed, SettingsStore,
+};
+use vrchatapi::models;
+
+/// A fresh directory for one test.
+fn test_dir(name: &str) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("vfriends-recording-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).expect("Failed to create the test directory.");
+    directory
+}
+
+fn frame_line(received_at: u64, kind: &str, content: Value) -> Value {
+    let frame = json!({ "type": kind, "content": content.to_string() });
+    json!({ "kind": "frame", "receivedAt": received_at, "frame": frame.to_string() })
+}
+
+fn write_recording(path: &Path, lines: &[Value]) {
+    let contents: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    std::fs::write(path, contents).expect("Failed to write the recording.");
+}
+
+fn read_frames(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| {
+            let line: Value = serde_json::from_str(line).expect("Recorded an invalid line.");
+            line["frame"].as_str().unwrap_or_default().to_string()
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replays_notifications_without_touching_the_live_state() {
+    let server = MockServer::start(MockAccount::default()).await;
+    let app = mock_app(server.endpoints());
+    app.state::<SettingsStore>()
+        .consume(|settings| {
+            settings.online_batch_window_ms = 0;
+            settings
+                .friend_settings
+                .insert(String::from("usr_carol"), FriendSettings::default());
+        })
+        .unwrap();
+
+    let alice = models::User {
+        id: String::from("usr_alice"),
+        display_name: String::from("Alice"),
+        ..Default::default()
+    };
+    let path = test_dir("replay").join("pipeline.jsonl");
+    write_recording(
+        &path,
+        &[
+            json!({
+                "kind": "roster",
+                "receivedAt": 1_000,
+                "reason": "seed",
+                "friends": [
+                    mock_friend("usr_alice", "Alice", "offline"),
+                    mock_friend("usr_carol", "Carol", "wrld_home:1"),
+                ],
+            }),
+            frame_line(
+                2_000,
+                "friend-online",
+                json!({
+                    "userId": "usr_alice",
+                    "platform": "standalonewindows",
+                    "location": "wrld_home:2",
+                    "canRequestInvite": true,
+                    "user": alice,
+                }),
+            ),
+            frame_line(3_000, "friend-delete", json!({ "userId": "usr_carol" })),
+        ],
+    );
+
+    // Each replay starts over, so the second one is not taken for a repeated event.
+    let notifications = Arc::clone(&app.state::<Arc<RecordingBackend>>());
+    for replays in 1..=2 {
+        let frames = replay(app.handle(), &path, ReplaySpeed::Instant)
+            .await
+            .expect("Replay failed.");
+        assert_eq!(frames, 2);
+
+        let deadline = tokio::time::Instant::now() + TIMEOUT;
+        while notifications.shown().len() < replays && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let bodies: Vec<String> = notifications
+        .shown()
+        .into_iter()
+        .map(|notification| notification.body)
+        .collect();
+    assert_eq!(bodies, ["Alice is now online!", "Alice is now online!"]);
+
+    let history = app.state::<HistoryStore>();
+    assert_eq!(history.page(&HistoryQuery::default()).unwrap().total, 0);
+    let flap_guard = app.state::<NotifierScope>().flap_guard().on_online(
+        "usr_alice",
+        "wrld_home:2|standalonewindows",
+        &FlapSuppression::default(),
+    );
+    assert_eq!(flap_guard, OnlineVerdict::Notify);
+
+    let roster = app.state::<FriendRoster>();
+    assert!(!roster.is_seeded());
+    assert!(roster.entries().is_empty());
+
+    let settings = app.state::<SettingsStore>().snapshot();
+    assert!(settings.friend_settings.contains_key("usr_carol"));
+    assert!(settings.archived_friend_settings.is_empty());
+}
+
+#[test]
+fn rotates_full_files_and_drops_the_oldest() {
+    let directory = test_dir("rotation");
+    let recorder = PipelineRecorder::new(directory.clone(), true).with_max_file_bytes(250);
+
+    // Each line is a little under 100 bytes, so every file holds two.
+    let frames: Vec<String> = (0..12).map(|index| format!("{index:0>40}")).collect();
+    for frame in &frames {
+        recorder.record_frame(frame);
+    }
+
+    let file = |rotation: usize| match rotation {
+        0 => directory.join("pipeline.jsonl"),
+        rotation => directory.join(format!("pipeline.{rotation}.jsonl")),
+    };
+    assert!(!file(5).exists());
+    for rotation in 0..5 {
+        let size = std::fs::metadata(file(rotation))
+            .expect("A rotated file is missing.")
+            .len();
+        assert!(size <= 250, "pipeline.{rotation}.jsonl has {size} bytes");
+    }
+
+    let kept: Vec<String> = (0..5)
+        .rev()
+        .flat_map(|rotation| read_frames(&file(rotation)))
+        .collect();
+    assert_eq!(kept, frames[2..]);
+}
+
+#[test]
+fn records_nothing_while_disabled() {
+    let directory = test_dir("disabled");
+    let recorder = PipelineRecorder::new(directory.clone(), false);
+    recorder.record_frame("{}");
+    assert!(!directory.join("pipeline.jsonl").exists());
+
+    recorder.set_enabled(true);
+    recorder.record_frame("{}");
+    assert_eq!(read_frames(&directory.join("pipeline.jsonl")), ["{}"]);
+}
@@ -2,6 +2,8 @@ import {listen, type UnlistenFn} from '@tauri-apps/api/event';
 import {computed, onBeforeUnmount, onMounted, reactive, shallowRef, toRefs} from 'vue';
 import {t} from '../i18n';
 import {beginAuth, restoreSession, verifyTwoFactor} from '../invokes';
+import type {AppErrorCode} from '../types.ts';
+import {isAppError} from '../utils.ts';
 import {VRChat} from '../vrchat.ts';
 import {useAuthSession, type AuthAction, type AuthEvent} from './useAuthSession';
 
@@ -27,6 +29,12 @@ type AuthState = {
   activeAction: AuthAction | null;
 };
 
+const FAILURE_MESSAGE_KEYS: Partial<Record<AppErrorCode, string>> = {
+  sessionExpired: 'auth.errors.sessionExpired',
+  rateLimited: 'auth.errors.rateLimited',
+  network: 'auth.errors.loginFailedNetwork',
+};
+
 const methodSort = (a: TwoFactorMethod, b: TwoFactorMethod) =>
   KNOWN_2FA_METHODS.indexOf(a) - KNOWN_2FA_METHODS.indexOf(b);
 
@@ -131,9 +139,18 @@ export const useAuthFlow = (options: UseLoginFlowOptions = {}) => {
         options.onLoginSuccess?.(state.authedUser);
         return;
       }
-      case 'failure':
-        failWith(event.message);
+      case 'failure': {
+        const code = event.code as AppErrorCode | undefined;
+        if (code === 'unauthorized') {
+          failWith(t(state.activeAction === 'twoFactor'
+            ? 'auth.errors.verifyFailed'
+            : 'auth.errors.invalidCredentials'));
+          return;
+        }
+        const key = code ? FAILURE_MESSAGE_KEYS[code] : undefined;
+        failWith(key ? t(key) : event.message);
         return;
+      }
       case 'loggedOut':
         markIdle();
         resetForm();
@@ -161,7 +178,9 @@ export const useAuthFlow = (options: UseLoginFlowOptions = {}) => {
       await beginAuth(trimmedUsername, state.password);
     } catch (error) {
       markIdle();
-      state.errorMessage = t('auth.errors.loginFailedNetwork');
+      state.errorMessage = isAppError(error) && error.code === 'rateLimited'
+        ? t('auth.errors.rateLimited')
+        : t('auth.errors.loginFailedNetwork');
       console.error(error);
     }
   };
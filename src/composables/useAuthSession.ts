@@ -2,6 +2,7 @@ import {computed, Ref, ref} from 'vue';
 import type {VRChat} from '../vrchat.ts';
 
 export type AuthAction = 'credentials' | 'twoFactor';
+export const SESSION_EXPIRED_CODE = 'sessionExpired';
 export type AuthEvent =
   | { type: 'started'; action: AuthAction }
   | { type: 'twoFactorRequired'; methods?: string[]; message?: string }
@@ -31,6 +32,10 @@ export const useAuthSession = () => {
     }
     if (event.type === 'loggedOut') {
       clearCurrentUser();
+      return;
+    }
+    if (event.type === 'failure' && event.code === SESSION_EXPIRED_CODE) {
+      clearCurrentUser();
     }
   };
 
@@ -0,0 +1,31 @@
+import {listen, type UnlistenFn} from '@tauri-apps/api/event';
+import {computed, ref} from 'vue';
+import {fetchConnectionState} from '../invokes.ts';
+import type {ConnectionState} from '../types.ts';
+
+const connectionState = ref<ConnectionState>({state: 'disconnected', reason: null});
+let unlisten: UnlistenFn | null = null;
+
+export const useConnectionState = () => {
+  const isLive = computed(() => connectionState.value.state === 'connected');
+
+  const start = async () => {
+    if (unlisten) return;
+    unlisten = await listen<ConnectionState>('vrc:connection-state', (event) => {
+      connectionState.value = event.payload;
+    });
+    connectionState.value = await fetchConnectionState();
+  };
+
+  const stop = () => {
+    unlisten?.();
+    unlisten = null;
+  };
+
+  return {
+    connectionState,
+    isLive,
+    start,
+    stop,
+  };
+};
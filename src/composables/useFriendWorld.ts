@@ -1,24 +1,19 @@
 import {computed, ref, type Ref, watch} from 'vue';
 import {useI18n} from 'vue-i18n';
-import {fetchWorld} from '../invokes';
+import {fetchWorld, parseLocation} from '../invokes';
+import type {Location} from '../types.ts';
 import type {VRChat} from '../vrchat.ts';
 
 const worldCache = new Map<string, VRChat.World | null>();
 const worldRequests = new Map<string, Promise<VRChat.World | null>>();
+const locationCache = new Map<string, Location | null>();
 
-const splitLocation = (location: string) => {
-  if (!location.startsWith('wrld_')) return {worldId: null, instanceId: ''};
-  const [worldId, instanceId = ''] = location.split(':');
-  return {
-    worldId: worldId || null,
-    instanceId,
-  };
-};
-
-const toInstanceShortId = (instanceId: string) => {
-  if (!instanceId) return '';
-  const [shortId] = instanceId.split('~');
-  return shortId ?? '';
+const loadLocation = async (location: string) => {
+  if (!locationCache.has(location)) {
+    const parsed = await parseLocation(location).catch(() => null);
+    locationCache.set(location, parsed);
+  }
+  return locationCache.get(location) ?? null;
 };
 
 const loadWorld = async (worldId: string) => {
@@ -50,12 +45,25 @@ export const useFriendWorld = (friend: Ref<VRChat.LimitedUserFriend>) => {
   const {t} = useI18n();
   const world = ref<VRChat.World | null>(null);
 
-  const locationParts = computed(() => splitLocation(friend.value.location ?? ''));
-  const worldId = computed(() => locationParts.value.worldId);
-  const instanceId = computed(() => locationParts.value.instanceId);
-  const instanceShortId = computed(() => toInstanceShortId(locationParts.value.instanceId));
+  const location = ref<Location | null>(null);
+  const instance = computed(() =>
+    location.value?.kind === 'instance' ? location.value : null,
+  );
+  const worldId = computed(() => instance.value?.worldId ?? null);
+  const instanceId = computed(() =>
+    instance.value ? instance.value.location.slice(instance.value.worldId.length + 1) : '',
+  );
   const isWorldLocation = computed(() => Boolean(worldId.value));
 
+  watch(
+    () => friend.value.location ?? '',
+    async (raw) => {
+      const parsed = raw ? await loadLocation(raw) : null;
+      if (raw === (friend.value.location ?? '')) location.value = parsed;
+    },
+    {immediate: true},
+  );
+
   watch(
     worldId,
     async (nextWorldId) => {
@@ -75,19 +83,13 @@ export const useFriendWorld = (friend: Ref<VRChat.LimitedUserFriend>) => {
   const locationLabel = computed(() => {
     const raw = friend.value.location ?? '';
     if (!raw) return '';
-    const normalized = raw.toLowerCase();
-    if (normalized.startsWith('wrld_')) {
-      if (world.value?.name) {
-        return instanceShortId.value
-          ? `${world.value.name} #${instanceShortId.value}`
-          : world.value.name;
-      }
-      return raw;
+    if (instance.value) {
+      return world.value?.name ? `${world.value.name} #${instance.value.instanceName}` : raw;
     }
-    if (normalized === 'private') return t('friends.location.private');
-    if (normalized === 'traveling') return t('friends.location.traveling');
-    if (normalized === 'offline') return t('friends.location.offline');
-    if (normalized === 'web') return t('friends.location.web');
+    if (location.value?.kind === 'private') return t('friends.location.private');
+    if (location.value?.kind === 'traveling') return t('friends.location.traveling');
+    if (location.value?.kind === 'offline') return t('friends.location.offline');
+    if (raw === 'web') return t('friends.location.web');
     return raw;
   });
 
@@ -7,12 +7,17 @@ import {VRChat} from '../vrchat.ts';
 const entries = ref<VRChat.LimitedUserFriend[]>([]);
 const isLoading = ref(false);
 const errorMessage = ref('');
-const friendEventUnlisten = ref<UnlistenFn | null>(null);
+const friendEventUnlisten = ref<UnlistenFn[]>([]);
 const isListening = ref(false);
 let pendingSnapshot: VRChat.LimitedUserFriend[] | null = null;
+let pendingDeltas: FriendDelta[] = [];
 let refreshFrame: number | null = null;
 let isRefreshSuspended = false;
 
+type FriendDelta =
+  | {kind: 'upsert'; friend: VRChat.LimitedUserFriend}
+  | {kind: 'remove'; userId: string};
+
 const mergeFriends = (
   current: VRChat.LimitedUserFriend[],
   next: VRChat.LimitedUserFriend[],
@@ -49,7 +54,35 @@ const applyFriendsSnapshot = (friends: VRChat.LimitedUserFriend[]) => {
   entries.value = mergeFriends(entries.value, friends);
 };
 
+const applyFriendDelta = (delta: FriendDelta) => {
+  if (delta.kind === 'remove') {
+    entries.value = entries.value.filter((friend) => friend.id !== delta.userId);
+    return;
+  }
+  const existing = entries.value.find((friend) => friend.id === delta.friend.id);
+  if (existing) {
+    Object.assign(existing, delta.friend);
+    return;
+  }
+  entries.value = [...entries.value, delta.friend];
+};
+
+const scheduleDelta = (delta: FriendDelta) => {
+  if (isRefreshSuspended) {
+    pendingDeltas.push(delta);
+    return;
+  }
+  applyFriendDelta(delta);
+};
+
+const flushPendingDeltas = () => {
+  const deltas = pendingDeltas;
+  pendingDeltas = [];
+  deltas.forEach(applyFriendDelta);
+};
+
 const scheduleSnapshot = (friends: VRChat.LimitedUserFriend[]) => {
+  pendingDeltas = [];
   if (isRefreshSuspended) {
     pendingSnapshot = friends;
     return;
@@ -68,18 +101,26 @@ const scheduleSnapshot = (friends: VRChat.LimitedUserFriend[]) => {
   });
 };
 
+const keepListening = (unlisten: UnlistenFn) => {
+  if (!isListening.value) {
+    unlisten();
+    return;
+  }
+  friendEventUnlisten.value.push(unlisten);
+};
+
 const startAutoRefresh = () => {
   if (isListening.value) return;
   isListening.value = true;
   void listen<VRChat.LimitedUserFriend[]>('vrc:friends-refresh', (event) => {
     scheduleSnapshot(event.payload);
-  }).then((unlisten) => {
-    if (!isListening.value) {
-      unlisten();
-      return;
-    }
-    friendEventUnlisten.value = unlisten;
-  });
+  }).then(keepListening);
+  void listen<VRChat.LimitedUserFriend>('vrc:friend-upsert', (event) => {
+    scheduleDelta({kind: 'upsert', friend: event.payload});
+  }).then(keepListening);
+  void listen<{userId: string}>('vrc:friend-remove', (event) => {
+    scheduleDelta({kind: 'remove', userId: event.payload.userId});
+  }).then(keepListening);
 };
 
 const stopAutoRefresh = () => {
@@ -90,9 +131,9 @@ const stopAutoRefresh = () => {
     refreshFrame = null;
   }
   pendingSnapshot = null;
-  if (friendEventUnlisten.value === null) return;
-  friendEventUnlisten.value();
-  friendEventUnlisten.value = null;
+  pendingDeltas = [];
+  friendEventUnlisten.value.forEach((unlisten) => unlisten());
+  friendEventUnlisten.value = [];
 };
 
 export const useFriends = () => ({
@@ -105,9 +146,11 @@ export const useFriends = () => ({
   stopAutoRefresh,
   setRefreshSuspended: (value: boolean) => {
     isRefreshSuspended = value;
-    if (!isRefreshSuspended && pendingSnapshot) {
+    if (isRefreshSuspended) return;
+    if (pendingSnapshot) {
       applyFriendsSnapshot(pendingSnapshot);
       pendingSnapshot = null;
     }
+    flushPendingDeltas();
   },
 });
@@ -10,6 +10,12 @@ const en = {
   },
   titleBar: {
     search: 'Search friends here',
+    connection: {
+      connected: 'Connected',
+      connecting: 'Connecting...',
+      reconnecting: 'Reconnecting in {seconds}s',
+      disconnected: 'Disconnected',
+    },
   },
   userStatus: {
     joinMe: 'Join Me',
@@ -65,6 +71,9 @@ const en = {
       missingTwoFactor: 'Enter your 2FA code.',
       selectTwoFactor: 'Select a 2FA method.',
       verifyFailed: 'Failed to verify 2FA. Please try again.',
+      invalidCredentials: 'Incorrect username or password.',
+      sessionExpired: 'Your session has expired. Please log in again.',
+      rateLimited: 'Too many attempts. Please wait a moment and try again.',
     },
     welcomeTitle: 'Welcome!',
     welcomeSubtitle: 'Glad to see you, {name}.',
@@ -143,6 +152,13 @@ const en = {
         saveFailed: 'Failed to save notification config.',
       },
     },
+    endpoints: {
+      title: 'Servers',
+      description:
+        'The VRChat API and pipeline servers are set with "endpoints" in AppSettings.json, or with VFRIENDS_API_BASE_PATH and VFRIENDS_PIPELINE_URL, and apply after restarting.',
+      logoutNotice:
+        'Logins are saved per API server, so changing the API server logs you out.',
+    },
     sidebar: {
       appSettings: 'App config',
     },
@@ -167,7 +183,8 @@ const en = {
       customizeOff:
         'Turn this on to customize notifier for this friend.',
       messageLabel: 'Notification message',
-      messageHelper: '"%s" will be replaced with the display name.',
+      messageHelper:
+        'Placeholders like {\'{displayName}\'} or {\'{worldName}\'} are replaced. "%s" still works.',
       soundLabel: 'Notification sound',
       previewSound: 'Preview Sound',
     },
@@ -1,6 +1,12 @@
 const ja = {
   titleBar: {
     search: 'ここでフレンドを検索',
+    connection: {
+      connected: '接続中',
+      connecting: '接続しています...',
+      reconnecting: '{seconds}秒後に再接続',
+      disconnected: '切断されました',
+    },
   },
   filePicker: {
     clear: '削除',
@@ -60,6 +66,9 @@ const ja = {
       missingTwoFactor: '2FAコードを入力してください。',
       selectTwoFactor: '2FA方式を選択してください。',
       verifyFailed: '2FAの検証に失敗しました。もう一度お試しください。',
+      invalidCredentials: 'ユーザー名またはパスワードが正しくありません。',
+      sessionExpired: 'セッションの有効期限が切れました。もう一度ログインしてください。',
+      rateLimited: '試行回数が多すぎます。しばらく待ってから再度お試しください。',
     },
     welcomeTitle: 'ようこそ！',
     welcomeSubtitle: '{name} さん、ログインできました。',
@@ -138,6 +147,13 @@ const ja = {
         saveFailed: '通知設定の保存に失敗しました。',
       },
     },
+    endpoints: {
+      title: 'サーバー',
+      description:
+        'VRChat の API とパイプラインのサーバーは AppSettings.json の「endpoints」または VFRIENDS_API_BASE_PATH と VFRIENDS_PIPELINE_URL で設定し、再起動後に反映されます。',
+      logoutNotice:
+        'ログイン情報は API サーバーごとに保存されるため、API サーバーを変更するとログアウトされます。',
+    },
     sidebar: {
       appSettings: 'アプリ設定',
     },
@@ -162,7 +178,8 @@ const ja = {
       customizeOff:
         'トグルスイッチをオンにして，このフレンドからの通知を特別なものにできます:)',
       messageLabel: '通知メッセージ',
-      messageHelper: '「%s」は表示名に置き換えられます。',
+      messageHelper:
+        '{\'{displayName}\'} や {\'{worldName}\'} などのプレースホルダーが置き換えられます。「%s」も使えます。',
       soundLabel: '通知サウンド',
       previewSound: 'サウンドをプレビュー',
     },
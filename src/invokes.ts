@@ -1,13 +1,37 @@
 import {invoke} from '@tauri-apps/api/core';
-import {AppSettings, FriendSettings} from './types.ts';
+import {
+  AppSettings,
+  ArchivedFriendSettings,
+  Companion,
+  ConnectionState,
+  CoPresenceGraph,
+  FriendSettings,
+  HistoryFilter,
+  HistoryPage,
+  HistoryQuery,
+  Location,
+  PresenceStats,
+} from './types.ts';
 import type {VRChat} from './vrchat.ts';
 
 export const fetchFriends = async (): Promise<VRChat.LimitedUserFriend[]> =>
   invoke<VRChat.LimitedUserFriend[]>('fetch_friends');
 
+export const fetchConnectionState = () =>
+  invoke<ConnectionState>('fetch_connection_state');
+
+export const fetchPipelineRecordingDir = () =>
+  invoke<string>('fetch_pipeline_recording_dir');
+
+export const replayPipelineRecording = (path: string, speed?: number) =>
+  invoke<number>('replay_pipeline_recording', {path, speed});
+
 export const fetchWorld = async (worldId: string): Promise<VRChat.World> =>
   invoke<VRChat.World>('fetch_world', {worldId});
 
+export const parseLocation = (location: string) =>
+  invoke<Location>('parse_location', {location});
+
 export const fetchIconDataUri = async (url: string) => {
   if (!url) return null;
   return invoke<string | null>('fetch_icon_data_uri', {url});
@@ -27,6 +51,9 @@ export const logout = async () => invoke('logout');
 export const fetchFriendSettings = () =>
   invoke<Record<string, FriendSettings>>('fetch_friend_settings');
 
+export const fetchArchivedFriendSettings = () =>
+  invoke<Record<string, ArchivedFriendSettings>>('fetch_archived_friend_settings');
+
 export const setFriendSettings = (
   friendId: string,
   patch: Partial<FriendSettings>,
@@ -40,13 +67,11 @@ export const fetchAppSettings = () =>
   invoke<AppSettings>('fetch_app_settings');
 
 export const setAppSettings = (settings: Partial<AppSettings>) => {
-  const payload: Partial<AppSettings> = {};
-  if ('defaultMessage' in settings) {
-    payload.defaultMessage = settings.defaultMessage;
-  }
-  if ('defaultSound' in settings) {
-    payload.defaultSound = settings.defaultSound;
-  }
+  const {
+    friendSettings: _friendSettings,
+    archivedFriendSettings: _archivedFriendSettings,
+    ...payload
+  } = settings;
   return invoke<AppSettings>('set_app_settings', {
     settings: payload,
   });
@@ -67,3 +92,32 @@ export const saveNotificationSoundPath = (path: string) =>
   invoke<string>('save_notification_sound_path', {
     path,
   });
+
+export const fetchNotificationHistory = (query?: HistoryQuery) =>
+  invoke<HistoryPage>('fetch_notification_history', {query});
+
+export const exportNotificationHistory = (
+  path: string,
+  format: 'csv' | 'json',
+  filter?: HistoryFilter,
+) =>
+  invoke<number>('export_notification_history', {
+    filter,
+    format,
+    path,
+  });
+
+export const clearNotificationHistory = () => invoke('clear_notification_history');
+
+export const fetchPresenceStats = (friendId: string, weeks?: number) =>
+  invoke<PresenceStats>('fetch_presence_stats', {friendId, weeks});
+
+export const fetchAllPresenceStats = (weeks?: number) =>
+  invoke<PresenceStats[]>('fetch_all_presence_stats', {weeks});
+
+/** Timestamps are Unix milliseconds and default to the last 30 days. */
+export const fetchCoPresenceGraph = (since?: number, until?: number) =>
+  invoke<CoPresenceGraph>('fetch_co_presence_graph', {since, until});
+
+export const fetchTopCompanions = (friendId: string, limit?: number, since?: number) =>
+  invoke<Companion[]>('fetch_top_companions', {friendId, limit, since});
@@ -1,12 +1,240 @@
+import type {VRChat} from './vrchat.ts';
+
+export type FriendToggle = 'inherit' | 'enabled' | 'disabled';
+
+export type MissedOnlineMode = 'batched' | 'individual' | 'off';
+
 export type AppSettings = {
+  /** Templates support placeholders such as {displayName}, fallbacks and sections. */
+  defaultTitle: string;
   defaultMessage: string;
   defaultSound: string | null;
+  offlineNotificationsEnabled: boolean;
+  defaultOfflineMessage: string;
+  defaultOfflineSound: string | null;
+  locationNotificationsEnabled: boolean;
+  defaultLocationMessage: string;
+  friendAddedNotificationsEnabled: boolean;
+  defaultFriendAddedMessage: string;
+  friendRemovedNotificationsEnabled: boolean;
+  defaultFriendRemovedMessage: string;
+  missedOnlineNotifications: MissedOnlineMode;
+  /** Website activity counts as `web`. */
+  platformFilter: PlatformFilter;
+  /** Online events within this many milliseconds are merged into one notification. 0 disables it. */
+  onlineBatchWindowMs: number;
+  onlineBatchKeepsOverrides: boolean;
+  flapSuppression: FlapSuppression;
+  quietHours: QuietHours;
   friendSettings: Record<string, FriendSettings>;
+  archivedFriendSettings: Record<string, ArchivedFriendSettings>;
+  /** Notification history older than this is deleted. 0 keeps it. */
+  historyRetentionDays: number;
+  /** Read at startup. Changing the API server logs the user out. */
+  endpoints: EndpointSettings;
+  recordPipeline: boolean;
+};
+
+export type EndpointSettings = {
+  apiBasePath: string | null;
+  pipelineUrl: string | null;
 };
 
 export type FriendSettings = {
   enabled: boolean;
   useOverride?: boolean;
+  titleOverride?: string | null;
   messageOverride?: string | null;
   soundOverride?: string | null;
+  offlineEnabled?: FriendToggle;
+  offlineMessageOverride?: string | null;
+  offlineSoundOverride?: string | null;
+  locationEnabled?: FriendToggle;
+  locationMessageOverride?: string | null;
+  locationSoundOverride?: string | null;
+  profileSubscriptions?: ProfileSubscription[];
+  /** Replaces the global filter. `null` follows it. */
+  platformFilter?: PlatformFilter | null;
+  flapSuppression?: FlapSuppressionOverride;
+  bypassQuietHours?: boolean;
+};
+
+export type PlatformFilter = {
+  pc: boolean;
+  android: boolean;
+  ios: boolean;
+  web: boolean;
+};
+
+/** Durations in seconds. 0 turns the check off. */
+export type FlapSuppression = {
+  onlineCooldownSecs: number;
+  minOnlineSecs: number;
+  dedupWindowSecs: number;
+};
+
+/** Unset or null values follow the global setting. */
+export type FlapSuppressionOverride = {
+  [Key in keyof FlapSuppression]?: number | null;
+};
+
+export type ProfileSubscription =
+  | {field: 'status'; to?: VRChat.UserStatus | null}
+  | {field: 'statusDescription'}
+  | {field: 'bio'}
+  | {field: 'avatar'};
+
+export type ArchivedFriendSettings = {
+  displayName: string;
+  archivedAt: number;
+  settings: FriendSettings;
+};
+
+export type Weekday = 'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun';
+
+export type QuietMode = 'silent' | 'drop' | 'digest';
+
+/** Times are local, e.g. "01:00". A window ends on the next day if `end` is not after `start`. */
+export type QuietWindow = {
+  days: Weekday[];
+  start: string;
+  end: string;
+};
+
+export type QuietHours = {
+  enabled: boolean;
+  mode: QuietMode;
+  windows: QuietWindow[];
+};
+
+export type NotificationKind =
+  | 'online'
+  | 'offline'
+  | 'location'
+  | 'friendAdded'
+  | 'friendRemoved'
+  | 'profileChange';
+
+export type NotificationDecision = 'shown' | 'batched' | 'deferred' | 'suppressed';
+
+export type SuppressionReason = 'disabled' | 'platform' | 'duplicate' | 'flapping' | 'quietHours';
+
+export type HistoryRecord = {
+  id: number;
+  /** Unix timestamp in milliseconds. */
+  recordedAt: number;
+  friendId: string;
+  displayName: string;
+  kind: NotificationKind;
+  location: string | null;
+  decision: NotificationDecision;
+  reason: SuppressionReason | null;
+};
+
+/** Timestamps in milliseconds. `since` is inclusive, `until` is not. */
+export type HistoryFilter = {
+  friendId?: string;
+  displayName?: string;
+  kinds?: NotificationKind[];
+  decisions?: NotificationDecision[];
+  since?: number;
+  until?: number;
+};
+
+export type HistoryQuery = HistoryFilter & {
+  offset?: number;
+  limit?: number;
+};
+
+/** Newest first. `total` counts the matches across all pages. */
+export type HistoryPage = {
+  records: HistoryRecord[];
+  total: number;
+};
+
+/** Aggregated presence of one friend. Timestamps are Unix milliseconds. */
+export type PresenceStats = {
+  friendId: string;
+  displayName: string | null;
+  online: boolean;
+  /** The current time while the friend is online. */
+  lastSeen: number | null;
+  averageSessionSecs: number | null;
+  sessionCount: number;
+  /** Since Monday 00:00, local time. */
+  weekTotalSecs: number;
+  /** Minutes online per local hour, `heatmap[weekday][hour]` with Monday first. */
+  heatmap: number[][];
+  heatmapWeeks: number;
+};
+
+/** Who played with whom. Edges are weighted by the time spent in the same instance. */
+export type CoPresenceGraph = {
+  nodes: {friendId: string; displayName: string | null; togetherSecs: number}[];
+  /** Heaviest first. */
+  edges: {source: string; target: string; togetherSecs: number; encounters: number}[];
+};
+
+export type Companion = {
+  friendId: string;
+  displayName: string | null;
+  togetherSecs: number;
+  encounters: number;
+};
+
+export type AccessType =
+  | 'public'
+  | 'friendsPlus'
+  | 'friends'
+  | 'invitePlus'
+  | 'invite'
+  | 'group'
+  | 'groupPlus'
+  | 'groupPublic';
+
+/** A parsed VRChat location. `location` formats back to the original string. */
+export type Location =
+  | {kind: 'offline' | 'private' | 'traveling'; location: string}
+  | {
+      kind: 'instance';
+      location: string;
+      worldId: string;
+      instanceName: string;
+      accessType: AccessType;
+      owner: string | null;
+      region: string;
+      group: string | null;
+      canRequestInvite: boolean;
+    };
+
+export type ConnectionState =
+  | {state: 'connecting'}
+  | {state: 'connected'; since: number}
+  | {state: 'reconnecting'; delaySecs: number; reason: string}
+  | {state: 'disconnected'; reason: string | null};
+
+export type AppErrorCode =
+  | 'unauthorized'
+  | 'sessionExpired'
+  | 'rateLimited'
+  | 'notFound'
+  | 'api'
+  | 'network'
+  | 'invalidInput'
+  | 'invalidSound'
+  | 'invalidTemplate'
+  | 'notification'
+  | 'io'
+  | 'settings'
+  | 'internal';
+
+/** What every command rejects with. */
+export type AppError = {
+  code: AppErrorCode;
+  message: string;
+  retryAfter?: number | null;
+  status?: number;
+  /** The settings field and character position of an invalid template. */
+  field?: string;
+  position?: number;
 };
@@ -1,4 +1,5 @@
 import {saveNotificationSound, saveNotificationSoundPath} from './invokes.ts';
+import type {AppError} from './types.ts';
 
 const yieldToUi = () =>
   new Promise<void>((resolve) => {
@@ -36,3 +37,6 @@ export const resolveSoundPath = async (file: File | null) => {
   const storedPath = await saveNotificationSound(file.name, bytes);
   return storedPath || null;
 };
+
+export const isAppError = (error: unknown): error is AppError =>
+  typeof error === 'object' && error !== null && 'code' in error && 'message' in error;